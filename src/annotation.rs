@@ -0,0 +1,227 @@
+use {
+  image::{Rgba, RgbaImage},
+  crate::pattern::CellRect,
+};
+
+/// Label, arrow or rectangle attached to cells, drawn over the universe.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Annotation {
+  /// Text with its top left corner on a cell.
+  Text { at: [i64; 2], text: String, color: [u8; 3] },
+  /// From the centre of a cell to the centre of another.
+  Arrow { from: [i64; 2], to: [i64; 2], color: [u8; 3] },
+  Rect { rect: CellRect, color: [u8; 3] },
+}
+
+impl Annotation {
+  pub fn color(&self) -> [u8; 3] {
+    match self {
+      Annotation::Text { color, .. } | Annotation::Arrow { color, .. } | Annotation::Rect { color, .. } => *color,
+    }
+  }
+
+  /// One line of an annotation file, e.g. `arrow 10 4 20 4 ffcc00`.
+  pub fn to_line(&self) -> String {
+    let hex = |[r, g, b]: [u8; 3]| format!("{r:02x}{g:02x}{b:02x}");
+    match self {
+      Annotation::Text { at: [x, y], text, color } => format!("text {x} {y} {} {text}", hex(*color)),
+      Annotation::Arrow { from: [x0, y0], to: [x1, y1], color } => format!("arrow {x0} {y0} {x1} {y1} {}", hex(*color)),
+      Annotation::Rect { rect, color } =>
+        format!("rect {} {} {} {} {}", rect.x, rect.y, rect.width, rect.height, hex(*color)),
+    }
+  }
+
+  /// Inverse of `to_line`. A leading `#C`, as in the comments of an RLE
+  /// file, is skipped.
+  pub fn parse_line(line: &str) -> Result<Self, String> {
+    let line = line.trim();
+    let line = line.strip_prefix("#C").unwrap_or(line).trim_start();
+    let (kind, rest) = split_word(line);
+    let mut words = rest.split_whitespace();
+    let mut number = || words.next().and_then(|n| n.parse::<i64>().ok()).ok_or("expected a number");
+    let color = |word: Option<&str>| word
+      .filter(|hex| hex.len() == 6)
+      .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+      .map(|rgb| [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8])
+      .ok_or("expected an RRGGBB colour");
+
+    let annotation = match kind {
+      "text" => {
+        let at = [number()?, number()?];
+        // the text keeps its inner spacing
+        let (_, rest) = split_word(split_word(rest).1);
+        let (hex, text) = split_word(rest);
+        Annotation::Text { at, text: text.to_string(), color: color(Some(hex))? }
+      },
+      "arrow" => Annotation::Arrow {
+        from: [number()?, number()?],
+        to: [number()?, number()?],
+        color: color(words.next())?,
+      },
+      "rect" => {
+        let (x, y) = (number()?, number()?);
+        let mut size = || words.next()
+          .and_then(|n| n.parse::<u32>().ok())
+          .filter(|&n| n > 0)
+          .ok_or("expected a positive size");
+        let (width, height) = (size()?, size()?);
+        Annotation::Rect {
+          rect: CellRect { x, y, width, height },
+          color: color(words.next())?,
+        }
+      },
+      _ => return Err(format!("unknown annotation {kind:?}")),
+    };
+    Ok(annotation)
+  }
+}
+
+/// First word of `s`, and what follows the whitespace after it.
+fn split_word(s: &str) -> (&str, &str) {
+  let s = s.trim_start();
+  match s.split_once(char::is_whitespace) {
+    Some((word, rest)) => (word, rest.trim_start()),
+    None => (s, ""),
+  }
+}
+
+/// Annotation file: one annotation per line, `#` starts a comment unless it
+/// is an RLE `#C` line.
+pub fn parse(text: &str) -> Result<Vec<Annotation>, String> {
+  text.lines().enumerate()
+    .filter(|(_, line)| {
+      let line = line.trim();
+      !line.is_empty() && (!line.starts_with('#') || line.starts_with("#C"))
+    })
+    .map(|(i, line)| Annotation::parse_line(line).map_err(|e| format!("line {}: {e}", i + 1)))
+    .collect()
+}
+
+pub fn to_text(annotations: &[Annotation]) -> String {
+  let mut text = "# hutton32-gpu annotations, in cell coordinates\n".to_string();
+  for annotation in annotations {
+    text += &annotation.to_line();
+    text.push('\n');
+  }
+  text
+}
+
+/// The annotations as `#C` lines, to go into the comments of an RLE file.
+pub fn to_rle_comments(annotations: &[Annotation]) -> String {
+  annotations.iter().map(|annotation| format!("#C {}\n", annotation.to_line())).collect()
+}
+
+/// 5x7 glyphs of printable ASCII, one byte per column, top row in bit 0.
+const FONT: [[u8; 5]; 95] = [
+  [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5f, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00],
+  [0x14, 0x7f, 0x14, 0x7f, 0x14], [0x24, 0x2a, 0x7f, 0x2a, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62],
+  [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], [0x00, 0x1c, 0x22, 0x41, 0x00],
+  [0x00, 0x41, 0x22, 0x1c, 0x00], [0x08, 0x2a, 0x1c, 0x2a, 0x08], [0x08, 0x08, 0x3e, 0x08, 0x08],
+  [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00],
+  [0x20, 0x10, 0x08, 0x04, 0x02], [0x3e, 0x51, 0x49, 0x45, 0x3e], [0x00, 0x42, 0x7f, 0x40, 0x00],
+  [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4b, 0x31], [0x18, 0x14, 0x12, 0x7f, 0x10],
+  [0x27, 0x45, 0x45, 0x45, 0x39], [0x3c, 0x4a, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03],
+  [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1e], [0x00, 0x36, 0x36, 0x00, 0x00],
+  [0x00, 0x56, 0x36, 0x00, 0x00], [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14],
+  [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3e],
+  [0x7e, 0x11, 0x11, 0x11, 0x7e], [0x7f, 0x49, 0x49, 0x49, 0x36], [0x3e, 0x41, 0x41, 0x41, 0x22],
+  [0x7f, 0x41, 0x41, 0x22, 0x1c], [0x7f, 0x49, 0x49, 0x49, 0x41], [0x7f, 0x09, 0x09, 0x09, 0x01],
+  [0x3e, 0x41, 0x49, 0x49, 0x7a], [0x7f, 0x08, 0x08, 0x08, 0x7f], [0x00, 0x41, 0x7f, 0x41, 0x00],
+  [0x20, 0x40, 0x41, 0x3f, 0x01], [0x7f, 0x08, 0x14, 0x22, 0x41], [0x7f, 0x40, 0x40, 0x40, 0x40],
+  [0x7f, 0x02, 0x0c, 0x02, 0x7f], [0x7f, 0x04, 0x08, 0x10, 0x7f], [0x3e, 0x41, 0x41, 0x41, 0x3e],
+  [0x7f, 0x09, 0x09, 0x09, 0x06], [0x3e, 0x41, 0x51, 0x21, 0x5e], [0x7f, 0x09, 0x19, 0x29, 0x46],
+  [0x46, 0x49, 0x49, 0x49, 0x31], [0x01, 0x01, 0x7f, 0x01, 0x01], [0x3f, 0x40, 0x40, 0x40, 0x3f],
+  [0x1f, 0x20, 0x40, 0x20, 0x1f], [0x3f, 0x40, 0x38, 0x40, 0x3f], [0x63, 0x14, 0x08, 0x14, 0x63],
+  [0x07, 0x08, 0x70, 0x08, 0x07], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7f, 0x41, 0x41, 0x00],
+  [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7f, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04],
+  [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78],
+  [0x7f, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7f],
+  [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7e, 0x09, 0x01, 0x02], [0x0c, 0x52, 0x52, 0x52, 0x3e],
+  [0x7f, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7d, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3d, 0x00],
+  [0x7f, 0x10, 0x28, 0x44, 0x00], [0x00, 0x41, 0x7f, 0x40, 0x00], [0x7c, 0x04, 0x18, 0x04, 0x78],
+  [0x7c, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7c, 0x14, 0x14, 0x14, 0x08],
+  [0x08, 0x14, 0x14, 0x18, 0x7c], [0x7c, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20],
+  [0x04, 0x3f, 0x44, 0x40, 0x20], [0x3c, 0x40, 0x40, 0x20, 0x7c], [0x1c, 0x20, 0x40, 0x20, 0x1c],
+  [0x3c, 0x40, 0x30, 0x40, 0x3c], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0c, 0x50, 0x50, 0x50, 0x3c],
+  [0x44, 0x64, 0x54, 0x4c, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7f, 0x00, 0x00],
+  [0x00, 0x41, 0x36, 0x08, 0x00], [0x08, 0x04, 0x08, 0x10, 0x08],
+];
+
+fn fill_square(image: &mut RgbaImage, center: [f64; 2], size: f64, color: Rgba<u8>) {
+  let half = size / 2.0;
+  let x0 = (center[0] - half).round().max(0.0) as u32;
+  let y0 = (center[1] - half).round().max(0.0) as u32;
+  let x1 = ((center[0] + half).round().max(0.0) as u32).min(image.width());
+  let y1 = ((center[1] + half).round().max(0.0) as u32).min(image.height());
+  for y in y0..y1 {
+    for x in x0..x1 {
+      image.put_pixel(x, y, color);
+    }
+  }
+}
+
+fn draw_line(image: &mut RgbaImage, from: [f64; 2], to: [f64; 2], width: f64, color: Rgba<u8>) {
+  let steps = (to[0] - from[0]).abs().max((to[1] - from[1]).abs()).ceil().max(1.0) as u32 * 2;
+  for i in 0..=steps {
+    let t = i as f64 / steps as f64;
+    fill_square(image, [from[0] + (to[0] - from[0]) * t, from[1] + (to[1] - from[1]) * t], width, color);
+  }
+}
+
+/// Draw `annotations` onto an image of `region` at `scale` pixels per cell,
+/// as rendered by `GPUDriver::render_region`. Text is about 3.5 cells high.
+pub fn draw(image: &mut RgbaImage, annotations: &[Annotation], region: CellRect, scale: u32) {
+  let scale = scale.max(1) as f64;
+  let pixel = |[x, y]: [i64; 2]| [(x - region.x) as f64 * scale, (y - region.y) as f64 * scale];
+  let width = (scale / 4.0).round().max(1.0);
+
+  for annotation in annotations {
+    let [r, g, b] = annotation.color();
+    let color = Rgba([r, g, b, 255]);
+    match annotation {
+      Annotation::Rect { rect, .. } => {
+        let [x0, y0] = pixel([rect.x, rect.y]);
+        let [x1, y1] = pixel([rect.x + rect.width as i64, rect.y + rect.height as i64]);
+        for (from, to) in [([x0, y0], [x1, y0]), ([x1, y0], [x1, y1]), ([x1, y1], [x0, y1]), ([x0, y1], [x0, y0])] {
+          draw_line(image, from, to, width, color);
+        }
+      },
+      Annotation::Arrow { from, to, .. } => {
+        let center = |cell| pixel(cell).map(|p| p + scale / 2.0);
+        let (from, to) = (center(*from), center(*to));
+        draw_line(image, from, to, width, color);
+        let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+        let length = dx.hypot(dy);
+        if length > 0.0 {
+          let head = (scale * 1.5).max(6.0);
+          let (ux, uy) = (dx / length * head, dy / length * head);
+          // both barbs at 30° off the shaft
+          let (c, s) = (30f64.to_radians().cos(), 30f64.to_radians().sin());
+          for s in [s, -s] {
+            draw_line(image, to, [to[0] - ux * c + uy * s, to[1] - uy * c - ux * s], width, color);
+          }
+        }
+      },
+      Annotation::Text { at, text, .. } => {
+        let size = (scale / 2.0).round().max(1.0);
+        let [x, y] = pixel(*at);
+        let mut dots = vec![];
+        for (i, c) in text.chars().enumerate() {
+          let c = if (' '..='~').contains(&c) { c } else { '?' };
+          for (column, bits) in FONT[c as usize - 0x20].iter().enumerate() {
+            for row in (0..7).filter(|row| bits >> row & 1 != 0) {
+              dots.push([x + ((i * 6 + column) as f64 + 0.5) * size, y + (row as f64 + 0.5) * size]);
+            }
+          }
+        }
+        // dark shadow first, so that labels stay legible over any state
+        for &[px, py] in &dots {
+          fill_square(image, [px + size / 2.0, py + size / 2.0], size, Rgba([0, 0, 0, 255]));
+        }
+        for &dot in &dots {
+          fill_square(image, dot, size, color);
+        }
+      },
+    }
+  }
+}
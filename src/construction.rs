@@ -0,0 +1,625 @@
+use {
+  std::{collections::{BTreeMap, HashSet}, ops::Range},
+  crate::pattern::Pattern,
+};
+
+/// Signal trains understood by the tip of a Hutton32 construction arm, see the
+/// header of `hutton32.rule.wgsl`. Directions are relative to the tip, so the
+/// same program builds the same shape whichever way the arm points.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Instruction {
+  Forward,
+  TurnLeft,
+  TurnRight,
+  WriteOtsForward,
+  WriteOtsLeft,
+  WriteOtsReverse,
+  WriteOtsRight,
+  WriteStsForward,
+  WriteStsLeft,
+  WriteStsReverse,
+  WriteStsRight,
+  WriteConfluent,
+  Retract,
+}
+
+impl Instruction {
+  pub const ALL: [Instruction; 13] = [
+    Instruction::Forward, Instruction::TurnLeft, Instruction::TurnRight,
+    Instruction::WriteOtsForward, Instruction::WriteOtsLeft, Instruction::WriteOtsReverse, Instruction::WriteOtsRight,
+    Instruction::WriteStsForward, Instruction::WriteStsLeft, Instruction::WriteStsReverse, Instruction::WriteStsRight,
+    Instruction::WriteConfluent, Instruction::Retract,
+  ];
+
+  pub fn mnemonic(&self) -> &'static str {
+    match self {
+      Instruction::Forward => "FWD",
+      Instruction::TurnLeft => "LEFT",
+      Instruction::TurnRight => "RIGHT",
+      Instruction::WriteOtsForward => "WRITE_OTS_FWD",
+      Instruction::WriteOtsLeft => "WRITE_OTS_LEFT",
+      Instruction::WriteOtsReverse => "WRITE_OTS_REV",
+      Instruction::WriteOtsRight => "WRITE_OTS_RIGHT",
+      Instruction::WriteStsForward => "WRITE_STS_FWD",
+      Instruction::WriteStsLeft => "WRITE_STS_LEFT",
+      Instruction::WriteStsReverse => "WRITE_STS_REV",
+      Instruction::WriteStsRight => "WRITE_STS_RIGHT",
+      Instruction::WriteConfluent => "WRITE_CONF",
+      Instruction::Retract => "RETRACT",
+    }
+  }
+
+  /// The signal train, first bit first. No train is a prefix of another, so
+  /// trains can follow each other without separators.
+  pub fn bits(&self) -> &'static str {
+    match self {
+      Instruction::Forward => "100000",
+      Instruction::TurnLeft => "100010",
+      Instruction::TurnRight => "10100",
+      Instruction::WriteOtsForward => "100001",
+      Instruction::WriteOtsLeft => "100011",
+      Instruction::WriteOtsReverse => "10011",
+      Instruction::WriteOtsRight => "10101",
+      Instruction::WriteStsForward => "101101",
+      Instruction::WriteStsLeft => "110001",
+      Instruction::WriteStsReverse => "110101",
+      Instruction::WriteStsRight => "111001",
+      Instruction::WriteConfluent => "1111",
+      Instruction::Retract => "101111",
+    }
+  }
+
+  pub fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|i| i.mnemonic().eq_ignore_ascii_case(mnemonic))
+  }
+}
+
+/// `0`s and `1`s, ignoring whitespace and `_`.
+pub fn parse_bits(text: &str) -> Result<Vec<bool>, String> {
+  text.chars()
+    .filter(|c| !c.is_whitespace() && *c != '_')
+    .map(|c| match c {
+      '0' => Ok(false),
+      '1' => Ok(true),
+      _ => Err(format!("{c:?} is not a bit")),
+    })
+    .collect()
+}
+
+pub fn bits_to_string(bits: &[bool]) -> String {
+  bits.iter().map(|&bit| if bit { '1' } else { '0' }).collect()
+}
+
+/// Largest repeat count of a statement.
+pub const MAX_REPEAT: usize = 1 << 16;
+
+/// Assembles a construction program into its signal train.
+///
+/// Statements are separated by `;` or line breaks, and `#` starts a comment.
+/// A statement is a mnemonic of `Instruction`, optionally followed by a repeat
+/// count up to `MAX_REPEAT`, as in `FWD 5; LEFT; WRITE_OTS_FWD; RETRACT`, or
+/// `BITS` followed by a raw train, e.g. `BITS 0000` to space the instructions out.
+pub fn assemble(source: &str) -> Result<Vec<bool>, String> {
+  let mut bits = vec![];
+  for (line_number, line) in source.lines().enumerate() {
+    let line = line.split('#').next().unwrap_or("");
+    for statement in line.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+      let error = |e: String| format!("line {}: {e}", line_number + 1);
+      let (word, rest) = statement.split_once(char::is_whitespace).unwrap_or((statement, ""));
+      let rest = rest.trim();
+      if word.eq_ignore_ascii_case("BITS") {
+        bits.extend(parse_bits(rest).map_err(error)?);
+        continue;
+      }
+      let instruction = Instruction::from_mnemonic(word)
+        .ok_or_else(|| error(format!("unknown instruction {word:?}")))?;
+      let count = match rest {
+        "" => 1,
+        count => match count.parse::<usize>() {
+          Ok(count) if count <= MAX_REPEAT => count,
+          Ok(_) => return Err(error(format!("repeat count {count} is above {MAX_REPEAT}"))),
+          Err(_) => return Err(error(format!("{count:?} is not a repeat count"))),
+        },
+      };
+      let train = parse_bits(instruction.bits()).unwrap();
+      for _ in 0..count {
+        bits.extend_from_slice(&train);
+      }
+    }
+  }
+  Ok(bits)
+}
+
+/// OTS line carrying `bits` east, excited for `1`: the first bit is the
+/// easternmost cell, so it leaves the line first. Rotate the pattern to feed
+/// an arm pointing elsewhere.
+pub fn tape_pattern(bits: &[bool]) -> Pattern {
+  let mut pattern = Pattern::new(bits.len() as u32, 1);
+  for (i, &bit) in bits.iter().enumerate() {
+    pattern.set((bits.len() - 1 - i) as u32, 0, if bit { 13 } else { 9 });
+  }
+  pattern
+}
+
+/// A tape stored in a repeater-emitter loop, see `tape_loop`.
+pub struct TapeLoop {
+  pub pattern: Pattern,
+  /// Generations per circuit, the tape length once padded.
+  pub period: usize,
+  /// The confluent cell feeding the arm, within `pattern`.
+  pub tap: [u32; 2],
+}
+
+/// Smallest side of the loop, so that its sides do not touch and the tap
+/// feeds nothing but the next loop cell and the arm.
+const MIN_LOOP_SIDE: u32 = 3;
+
+/// Encodes `bits` as the excitation states of a closed OTS loop circulating
+/// anticlockwise, emitting them in order forever through a confluent tap on
+/// its east side into an arm of `arm` OTS cells pointing east.
+///
+/// A loop of `n` cells takes `n + 1` generations per circuit, one more for
+/// the confluent, so the tape is padded with `0`s to the smallest loop that
+/// holds it; the padding reaches the arm as gaps between the repeats.
+pub fn tape_loop(bits: &[bool], arm: u32) -> TapeLoop {
+  // period 2 (w + h) - 3, with near square sides
+  let half = (bits.len() as u32 + 4) / 2;
+  let width = (half / 2).max(MIN_LOOP_SIDE);
+  let height = half.saturating_sub(width).max(MIN_LOOP_SIDE);
+  let tap = [width - 1, height / 2];
+
+  // from the tap, along the flow
+  let mut cells = vec![];
+  cells.extend((0..=tap[1]).rev().map(|y| [width - 1, y]));
+  cells.extend((0..width - 1).rev().map(|x| [x, 0]));
+  cells.extend((1..height).map(|y| [0, y]));
+  cells.extend((1..width).map(|x| [x, height - 1]));
+  cells.extend((tap[1] + 1..height - 1).rev().map(|y| [width - 1, y]));
+  let period = cells.len() + 1;
+  let bit = |i: usize| bits.get(i).copied().unwrap_or(false);
+
+  let mut pattern = Pattern::new(width + arm, height);
+  // The confluent holds the first bit, about to leave, and the second.
+  pattern.set(tap[0], tap[1], 25 + 2 * bit(0) as u8 + bit(1) as u8);
+  for k in 1..cells.len() {
+    let ([x, y], [nx, ny]) = (cells[k], cells[(k + 1) % cells.len()]);
+    let dir = match (nx as i64 - x as i64, ny as i64 - y as i64) {
+      (1, 0) => 0,
+      (0, -1) => 1,
+      (-1, 0) => 2,
+      _ => 3,
+    };
+    // the cell just before the tap leaves third, the one just after last
+    pattern.set(x, y, 9 + 4 * bit(period - k) as u8 + dir);
+  }
+  for x in width..width + arm {
+    pattern.set(x, tap[1], 9);
+  }
+  TapeLoop { pattern, period, tap }
+}
+
+/// A stretch of a signal train, as the tip of an arm reads it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Decoded {
+  Instruction(Instruction),
+  /// Quiescent bits between trains, ignored by the tip.
+  Gap(usize),
+  /// A train matching no instruction, up to the bit ruling them all out.
+  Unknown(Vec<bool>),
+  /// A train cut off by the end of the bits.
+  Incomplete(Vec<bool>),
+}
+
+/// Inverse of `assemble`: the stretches of `bits` with where each lies.
+pub fn disassemble(bits: &[bool]) -> Vec<(Range<usize>, Decoded)> {
+  let codes: Vec<(Instruction, Vec<bool>)> = Instruction::ALL.iter()
+    .map(|&i| (i, parse_bits(i.bits()).unwrap()))
+    .collect();
+  let mut steps = vec![];
+  let mut start = 0;
+  while start < bits.len() {
+    if !bits[start] {
+      let end = bits[start..].iter().position(|&bit| bit).map_or(bits.len(), |n| start + n);
+      steps.push((start..end, Decoded::Gap(end - start)));
+      start = end;
+      continue;
+    }
+    let mut end = start + 1;
+    let decoded = loop {
+      let train = &bits[start..end];
+      if let Some((instruction, _)) = codes.iter().find(|(_, code)| code == train) {
+        break Decoded::Instruction(*instruction);
+      }
+      if !codes.iter().any(|(_, code)| code.starts_with(train)) {
+        break Decoded::Unknown(train.to_vec());
+      }
+      if end == bits.len() {
+        break Decoded::Incomplete(train.to_vec());
+      }
+      end += 1;
+    };
+    steps.push((start..end, decoded));
+    start = end;
+  }
+  steps
+}
+
+/// A program `assemble` turns back into the same bits, one statement per
+/// line, with repeats folded into counts.
+pub fn to_source(steps: &[(Range<usize>, Decoded)]) -> String {
+  let mut lines: Vec<(String, usize)> = vec![];
+  for (_, decoded) in steps {
+    let line = match decoded {
+      Decoded::Instruction(instruction) => {
+        match lines.last_mut() {
+          Some((mnemonic, count)) if mnemonic == instruction.mnemonic() => *count += 1,
+          _ => lines.push((instruction.mnemonic().to_string(), 1)),
+        }
+        continue;
+      },
+      Decoded::Gap(n) => format!("BITS {}", "0".repeat(*n)),
+      Decoded::Unknown(train) => format!("BITS {} # unknown", bits_to_string(train)),
+      Decoded::Incomplete(train) => format!("BITS {} # incomplete", bits_to_string(train)),
+    };
+    lines.push((line, 1));
+  }
+  lines.into_iter()
+    .map(|(line, count)| if count > 1 { format!("{line} {count}\n") } else { format!("{line}\n") })
+    .collect()
+}
+
+/// Cell offset of each direction, in `dir` order of the rule files.
+const OFFSETS: [[i64; 2]; 4] = [[1, 0], [0, -1], [-1, 0], [0, 1]];
+
+fn step(cell: [i64; 2], dir: u8) -> [i64; 2] {
+  let [dx, dy] = OFFSETS[dir as usize];
+  [cell[0] + dx, cell[1] + dy]
+}
+
+fn is_ots(state: u8) -> bool {
+  (9..=16).contains(&state)
+}
+
+fn is_confluent(state: u8) -> bool {
+  (25..=28).contains(&state)
+}
+
+/// Direction of a transmission state.
+fn dir(state: u8) -> u8 {
+  (state - 9) % 4
+}
+
+/// `state` with its excitation dropped, as construction leaves it.
+pub fn quiescent(state: u8) -> u8 {
+  match state {
+    13..=16 | 21..=24 => state - 4,
+    25..=28 => 25,
+    _ => state,
+  }
+}
+
+/// Signals carried by an OTS wire, read back from its tip.
+pub struct Wire {
+  /// Cells from the tip back along the flow, with their quiescent states.
+  pub cells: Vec<([i64; 2], u8)>,
+  /// The bits in the order they reach the tip, two for a confluent cell.
+  pub bits: Vec<bool>,
+  /// Where the wire closes into a loop, the part of `bits` from here on
+  /// comes round again every circuit.
+  pub loop_start: Option<usize>,
+}
+
+impl Wire {
+  /// The bits reaching the tip over `circuits` circuits of the loop.
+  pub fn train(&self, circuits: usize) -> Vec<bool> {
+    match self.loop_start {
+      Some(start) => {
+        let mut train = self.bits[..start].to_vec();
+        for _ in 0..circuits {
+          train.extend_from_slice(&self.bits[start..]);
+        }
+        train
+      },
+      None => self.bits.clone(),
+    }
+  }
+}
+
+/// Traces the wire of `pattern`, lying at `origin`, back from its tip: the
+/// OTS cell pointing at anything but a transmission or confluent cell with
+/// the longest wire behind it. Confluent cells are followed through, so a
+/// repeater-emitter loop is read from its output arm.
+pub fn trace_wire(pattern: &Pattern, origin: [i64; 2]) -> Result<Wire, String> {
+  let state_at = |[x, y]: [i64; 2]| {
+    let (px, py) = (x - origin[0], y - origin[1]);
+    (px >= 0 && py >= 0 && px < pattern.width as i64 && py < pattern.height as i64)
+      .then(|| pattern.get(px as u32, py as u32))
+  };
+  let carries = |cell: [i64; 2]| state_at(cell).filter(|&s| is_ots(s) || is_confluent(s));
+  // `from` passes its signals on to `to`
+  let feeds = |from: [i64; 2], to: [i64; 2]| match (carries(from), carries(to)) {
+    (Some(f), Some(_)) if is_ots(f) => step(from, dir(f)) == to,
+    (Some(_), Some(t)) if is_ots(t) => OFFSETS.iter().any(|&[dx, dy]| [from[0] + dx, from[1] + dy] == to)
+      && step(to, dir(t)) != from,
+    _ => false,
+  };
+
+  let trace = |tip: [i64; 2]| {
+    let mut cells = vec![tip];
+    let mut visited = HashSet::from([tip]);
+    let loop_cell = loop {
+      let cell = *cells.last().unwrap();
+      let behind = state_at(cell).filter(|&s| is_ots(s)).map(|s| step(cell, (dir(s) + 2) % 4));
+      let mut feeding: Vec<[i64; 2]> = (0..4).map(|d| step(cell, d)).filter(|&n| feeds(n, cell)).collect();
+      feeding.sort_by_key(|&n| Some(n) != behind);
+      match feeding.iter().find(|n| !visited.contains(*n)) {
+        Some(&n) => {
+          cells.push(n);
+          visited.insert(n);
+        },
+        None => break feeding.first().copied(),
+      }
+    };
+    (cells, loop_cell)
+  };
+
+  let tips = (0..pattern.height as i64).flat_map(|y| (0..pattern.width as i64).map(move |x| [origin[0] + x, origin[1] + y]))
+    .filter(|&cell| carries(cell).is_some_and(is_ots))
+    .filter(|&cell| !feeds(cell, step(cell, dir(carries(cell).unwrap()))));
+  let (cells, loop_cell) = tips.map(trace)
+    .max_by_key(|(cells, _)| cells.len())
+    .ok_or("no wire tip in the selection: an OTS pointing at ground ends the arm")?;
+
+  let mut wire = Wire { cells: vec![], bits: vec![], loop_start: None };
+  for cell in cells {
+    let state = carries(cell).unwrap();
+    if Some(cell) == loop_cell {
+      wire.loop_start = Some(wire.bits.len());
+    }
+    match state {
+      13..=16 => wire.bits.push(true),
+      9..=12 => wire.bits.push(false),
+      // the output of this generation, then of the next
+      _ => wire.bits.extend([state >= 27, state == 26 || state == 28]),
+    }
+    wire.cells.push((cell, quiescent(state)));
+  }
+  Ok(wire)
+}
+
+/// What the arm at the tip of a wire is expected to build.
+pub struct Prediction {
+  /// Quiescent state of every cell the arm writes or clears.
+  pub cells: BTreeMap<[i64; 2], u8>,
+  /// Generations until the last instruction has taken effect, counting one
+  /// per bit and one more per cell the arm has grown by then. The tests
+  /// check this against a CPU port of the rule.
+  pub generations: u64,
+  pub instructions: usize,
+  /// Why the interpreter stopped early.
+  pub error: Option<String>,
+}
+
+/// Runs the c-arm semantics of the tip over `train`: moves and turns extend
+/// the arm by one OTS, writes place a cell in front of the tip and retract
+/// it, and retracting clears the tip. Directions are relative to the tip.
+///
+/// `state_at` gives the universe when the wire was read. The arm only builds
+/// into ground, anything else in front of the tip stops the prediction.
+pub fn predict(wire: &Wire, train: &[bool], state_at: impl Fn([i64; 2]) -> u8) -> Prediction {
+  // the arm, tip last, as far back as plain OTS go
+  let mut arm: Vec<([i64; 2], u8)> = wire.cells.iter()
+    .take_while(|(_, state)| is_ots(*state))
+    .map(|&(cell, state)| (cell, dir(state)))
+    .collect();
+  arm.reverse();
+
+  let mut prediction = Prediction { cells: BTreeMap::new(), generations: 0, instructions: 0, error: None };
+  let mut growth: i64 = 0;
+  for (range, decoded) in disassemble(train) {
+    let instruction = match decoded {
+      Decoded::Instruction(instruction) => instruction,
+      Decoded::Gap(_) | Decoded::Incomplete(_) => continue,
+      Decoded::Unknown(bits) => {
+        prediction.error = Some(format!("unknown train {} at bit {}", bits_to_string(&bits), range.start));
+        break;
+      },
+    };
+    let Some(&(tip, tip_dir)) = arm.last() else {
+      prediction.error = Some(format!("the arm is retracted past its wire at bit {}", range.start));
+      break;
+    };
+    let ahead = step(tip, tip_dir);
+    let blocking = prediction.cells.get(&ahead).copied().unwrap_or_else(|| state_at(ahead));
+    if blocking != 0 && instruction != Instruction::Retract {
+      prediction.error = Some(format!("at bit {}, the arm runs into state {blocking} at {ahead:?}", range.start));
+      break;
+    }
+    // relative to the tip: forward, left, reverse, right
+    let turned = |side: u8| (tip_dir + side) % 4;
+    let written = match instruction {
+      Instruction::Forward | Instruction::TurnLeft | Instruction::TurnRight => {
+        let new_dir = turned(match instruction {
+          Instruction::TurnLeft => 1,
+          Instruction::TurnRight => 3,
+          _ => 0,
+        });
+        prediction.cells.insert(ahead, 9 + new_dir);
+        arm.push((ahead, new_dir));
+        None
+      },
+      Instruction::WriteOtsForward => Some(9 + turned(0)),
+      Instruction::WriteOtsLeft => Some(9 + turned(1)),
+      Instruction::WriteOtsReverse => Some(9 + turned(2)),
+      Instruction::WriteOtsRight => Some(9 + turned(3)),
+      Instruction::WriteStsForward => Some(17 + turned(0)),
+      Instruction::WriteStsLeft => Some(17 + turned(1)),
+      Instruction::WriteStsReverse => Some(17 + turned(2)),
+      Instruction::WriteStsRight => Some(17 + turned(3)),
+      Instruction::WriteConfluent => Some(25),
+      Instruction::Retract => None,
+    };
+    if let Some(state) = written {
+      prediction.cells.insert(ahead, state);
+    }
+    // a sensitized cell takes a generation more to become a confluent
+    let settle = u64::from(instruction == Instruction::WriteConfluent);
+    prediction.generations = (range.end as i64 - 1 + growth).max(0) as u64 + settle;
+    if matches!(instruction, Instruction::Forward | Instruction::TurnLeft | Instruction::TurnRight) {
+      growth += 1;
+    } else {
+      prediction.cells.insert(tip, 0);
+      arm.pop();
+      growth -= 1;
+    }
+    prediction.instructions += 1;
+  }
+  prediction
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::rule::{hutton32b, Rule}};
+
+  #[test]
+  fn trains_are_prefix_free() {
+    for a in Instruction::ALL {
+      // a leading 0 would read as a gap
+      assert!(a.bits().starts_with('1'), "{a:?}");
+      for b in Instruction::ALL.into_iter().filter(|&b| b != a) {
+        assert!(!b.bits().starts_with(a.bits()), "{a:?} is a prefix of {b:?}");
+      }
+    }
+  }
+
+  #[test]
+  fn assemble_round_trips_through_disassemble() {
+    let source = "FWD 3\nBITS 000\nLEFT\nWRITE_CONF\nBITS 00\nRETRACT 2\nWRITE_STS_REV\n";
+    let bits = assemble(source).unwrap();
+    let steps = disassemble(&bits);
+    assert!(steps.iter().all(|(_, decoded)| !matches!(decoded, Decoded::Unknown(_) | Decoded::Incomplete(_))));
+    assert_eq!(to_source(&steps), source);
+    assert_eq!(assemble(&to_source(&steps)).unwrap(), bits);
+  }
+
+  #[test]
+  fn assemble_accepts_statements_and_comments() {
+    let bits = assemble("fwd 2; RIGHT # turn\n\n  BITS 0_1 ").unwrap();
+    assert_eq!(bits_to_string(&bits), "100000100000101000 1".replace(' ', ""));
+  }
+
+  #[test]
+  fn assemble_rejects_bad_repeat_counts() {
+    assert!(assemble(&format!("FWD {MAX_REPEAT}")).is_ok());
+    assert!(assemble(&format!("FWD {}", MAX_REPEAT + 1)).is_err());
+    assert!(assemble("FWD 4000000000").is_err());
+    assert!(assemble("FWD -1").is_err());
+    assert!(assemble("JUMP").is_err());
+  }
+
+  #[test]
+  fn tape_loop_holds_the_padded_tape() {
+    for len in [1, 6, 12, 13, 40, 101] {
+      let bits: Vec<bool> = (0..len).map(|i| i % 3 != 1).collect();
+      let arm = 2;
+      let tape_loop = tape_loop(&bits, arm);
+      assert!(tape_loop.period >= len);
+      let loop_cells = (0..tape_loop.pattern.height)
+        .flat_map(|y| (0..tape_loop.pattern.width - arm).map(move |x| (x, y)))
+        .filter(|&(x, y)| tape_loop.pattern.get(x, y) != 0)
+        .count();
+      assert_eq!(tape_loop.period, loop_cells + 1, "{len} bits");
+
+      let wire = trace_wire(&tape_loop.pattern, [0, 0]).unwrap();
+      let start = wire.loop_start.unwrap();
+      let mut padded = bits.clone();
+      padded.resize(tape_loop.period, false);
+      assert_eq!(wire.bits[start..], padded[..], "{len} bits");
+      assert!(wire.bits[..start].iter().all(|&bit| !bit));
+    }
+  }
+
+  #[test]
+  fn tape_loop_circulates_and_emits_the_tape() {
+    for len in [1, 6, 12, 13, 40, 101] {
+      let bits: Vec<bool> = (0..len).map(|i| i % 3 != 1).collect();
+      // long enough not to be retracted by the writes on the tape, with
+      // room for whatever the arm builds meanwhile
+      let arm = len as u32 + 4;
+      let tape_loop = tape_loop(&bits, arm);
+      let period = tape_loop.period;
+      let margin = period as u32;
+      let start = with_margin(&tape_loop.pattern, margin);
+      let loop_width = tape_loop.pattern.width - arm;
+      let in_loop = |pattern: &Pattern| (0..tape_loop.pattern.height)
+        .flat_map(|y| (0..loop_width).map(move |x| pattern.get(x + margin, y + margin)))
+        .collect::<Vec<_>>();
+      // the first arm cell, right after the tap
+      let first = [loop_width + margin, tape_loop.tap[1] + margin];
+
+      let mut universe = start.clone();
+      let mut emitted = vec![];
+      for _ in 0..period {
+        universe = hutton32b::step(&universe);
+        emitted.push(Rule::ACTIVE.is_excited(universe.get(first[0], first[1])));
+      }
+      let mut padded = bits.clone();
+      padded.resize(period, false);
+      assert_eq!(emitted, padded, "{len} bits");
+      assert_eq!(in_loop(&universe), in_loop(&start), "{len} bits");
+    }
+  }
+
+  /// `pattern` inside a margin of ground, so the arm has room to build.
+  fn with_margin(pattern: &Pattern, margin: u32) -> Pattern {
+    let mut out = Pattern::new(pattern.width + 2 * margin, pattern.height + 2 * margin);
+    for y in 0..pattern.height {
+      for x in 0..pattern.width {
+        out.set(x + margin, y + margin, pattern.get(x, y));
+      }
+    }
+    out
+  }
+
+  fn state_at(pattern: &Pattern) -> impl Fn([i64; 2]) -> u8 + '_ {
+    |[x, y]| match x >= 0 && y >= 0 && x < pattern.width as i64 && y < pattern.height as i64 {
+      true => pattern.get(x as u32, y as u32),
+      false => 0,
+    }
+  }
+
+  /// Runs the tape of `source` into ground under the rule, and checks the
+  /// predicted footprint is built exactly by the predicted generation.
+  fn check_prediction(source: &str) {
+    let mut universe = with_margin(&tape_pattern(&assemble(source).unwrap()), 8);
+    let wire = trace_wire(&universe, [0, 0]).unwrap();
+    let prediction = predict(&wire, &wire.train(1), state_at(&universe));
+    assert_eq!(prediction.error, None, "{source}");
+    let built = |universe: &Pattern| prediction.cells.iter()
+      .all(|(&cell, &state)| quiescent(state_at(universe)(cell)) == state);
+    for generation in 1..=prediction.generations {
+      universe = hutton32b::step(&universe);
+      assert_eq!(built(&universe), generation == prediction.generations, "{source} at generation {generation}");
+    }
+  }
+
+  #[test]
+  fn predict_one_instruction() {
+    for instruction in Instruction::ALL {
+      check_prediction(&format!("BITS 00; {}", instruction.mnemonic()));
+    }
+  }
+
+  #[test]
+  fn predict_a_program() {
+    check_prediction("BITS 00; FWD 2; LEFT; WRITE_OTS_FWD; RIGHT; WRITE_STS_LEFT; WRITE_CONF");
+  }
+
+  #[test]
+  fn predict_stops_at_obstacles() {
+    let wire = Wire { cells: vec![([0, 0], 9)], bits: vec![], loop_start: None };
+    let prediction = predict(&wire, &assemble("FWD; FWD").unwrap(), |cell| if cell == [2, 0] { 25 } else { 0 });
+    assert_eq!(prediction.instructions, 1);
+    assert!(prediction.error.is_some());
+  }
+}
@@ -1,6 +1,8 @@
 use {
   std::mem,
   image::Pixel,
+  crate::{rule::Rule, snapshot},
+  super::{gpu_profile::ProfiledPass, MAX_STEPS_PER_CALL},
   wgpu::{
     BufferDescriptor, BufferUsages, CommandEncoder, ComputePipelineDescriptor, Device, PipelineLayoutDescriptor, Queue
   }
@@ -30,13 +32,37 @@ impl super::GPUDriver {
     queue.submit(std::iter::once(encoder.finish()));
   }
 
+  /// Fingerprint of the transition table, stored in snapshots. The table is
+  /// a function of the rule source alone, so that is hashed rather than the
+  /// table read back from the GPU.
+  pub(in super) fn hash_ca_lut() -> u64 {
+    snapshot::fnv1a_64(Rule::ACTIVE.source())
+  }
+
+  /// Whether `device` can hold a `simulation_dimm` universe in one storage
+  /// buffer, and dispatch one invocation per cell.
+  pub fn check_simulation_dimm(device: &Device, [width, height]: [u32; 2]) -> Result<(), String> {
+    let limits = device.limits();
+    let max_side = limits.max_compute_workgroups_per_dimension;
+    let max_bytes = limits.max_buffer_size.min(limits.max_storage_buffer_binding_size as u64);
+    if width == 0 || height == 0 {
+      return Err("empty universe".to_string());
+    }
+    if width > max_side || height > max_side {
+      return Err(format!("{width}x{height} universe, the device dispatches at most {max_side} cells a side"));
+    }
+    match simulation_bytes([width, height], mem::size_of::<u32>()) {
+      Some(bytes) if bytes <= max_bytes => Ok(()),
+      _ => Err(format!("{width}x{height} universe, the device binds at most {max_bytes} bytes")),
+    }
+  }
+
   pub fn load_simulation(&mut self, device: &Device, queue: &Queue) {
     //TODO: RLE/Macrocell Pattern reader
     let path = "./doc/hutton32_squares.png";
     let mut pattern = image::open(path)
       .expect("Failed to load {path}")
       .to_rgba8();
-    self.uniforms.simulation_dimm = [pattern.width(), pattern.height()];
     pattern.pixels_mut().for_each(|pixel| {
       let pixel_rgb = pixel.to_rgb().0;
       for (i, color) in HUTTON32_COLORS.into_iter().enumerate() {
@@ -45,34 +71,119 @@ impl super::GPUDriver {
         }
       }
     });
+    self.allocate_simulation(device, queue, [pattern.width(), pattern.height()], &pattern);
+  }
+
+  /// Replace the simulation buffer with raw cell records. The rewind ring
+  /// restarts from them.
+  pub fn load_records(&mut self, device: &Device, queue: &Queue, simulation_dimm: [u32; 2], records: &[u32]) {
+    self.allocate_simulation(device, queue, simulation_dimm, bytemuck::cast_slice(records));
+  }
+
+  fn allocate_simulation(&mut self, device: &Device, queue: &Queue, simulation_dimm: [u32; 2], contents: &[u8]) {
+    let buffer_size = |element_size| simulation_bytes(simulation_dimm, element_size)
+      .unwrap_or_else(|| panic!("{simulation_dimm:?} universe overflows a buffer size"));
+    self.uniforms.simulation_dimm = simulation_dimm;
+    self.generation = 0;
     self.simulation_buffer = device.create_buffer(&BufferDescriptor {
       label: Some("Simulation Buffer"),
-      size: (pattern.width() * pattern.height() * mem::size_of::<u32>() as u32) as _,
+      size: buffer_size(mem::size_of::<u32>()),
       usage: BufferUsages::STORAGE
         | BufferUsages::COPY_DST
         | BufferUsages::COPY_SRC,
       mapped_at_creation: false
     });
+    self.heat_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Heat Buffer"),
+      size: buffer_size(mem::size_of::<f32>()),
+      usage: BufferUsages::STORAGE,
+      mapped_at_creation: false
+    });
     self.bind_group = Self::create_bind_group(
-      &device, &self.bind_group_layout,
-      &self.uniform_buffer, &self.simulation_buffer, &self.lut_buffer
+      device, &self.bind_group_layout,
+      [
+        &self.uniform_buffer, &self.simulation_buffer, &self.lut_buffer, &self.change_counter,
+        &self.heat_buffer, &self.palette_buffer
+      ]
     );
-    queue.write_buffer(&self.simulation_buffer, 0, &pattern);
+    queue.write_buffer(&self.simulation_buffer, 0, contents);
+    self.rebind_views(device);
+    self.set_rewind(device, queue, self.rewind_depth());
   }
 
   pub(in super) fn simulation_advance(&mut self, encoder: &mut CommandEncoder) {
-    for _ in 0..self.simulatiion_steps_per_call {
+    let first_generation = self.generation;
+    let passes = self.simulatiion_steps_per_call.min(MAX_STEPS_PER_CALL);
+    let count_changes = self.uniforms.count_changes != 0;
+    self.profile_begin(encoder, ProfiledPass::Simulation);
+    if count_changes {
+      encoder.clear_buffer(&self.change_counter, 0, None);
+    }
+
+    for pass in 0..passes {
       let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
       cpass.set_pipeline(&self.compute_pipeline);
       cpass.set_bind_group(0, &self.bind_group, &[]);
       cpass.insert_debug_marker("compute simulation iter");
       cpass.dispatch_workgroups(self.uniforms.simulation_dimm[0], self.uniforms.simulation_dimm[1], 1);
       //queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniforms]));
+      drop(cpass);
+      if count_changes {
+        let cell_size = mem::size_of::<u32>() as u64;
+        encoder.copy_buffer_to_buffer(&self.change_counter, 0, &self.change_log, pass * cell_size, cell_size);
+        encoder.clear_buffer(&self.change_counter, 0, None);
+      }
+      self.generation = self.generation.wrapping_add(1);
+      self.watch_pass(encoder, pass);
+      self.probe_pass(encoder);
+      self.population_pass(encoder, pass);
+      self.rewind_record(encoder);
+    }
+    let [width, height] = self.uniforms.simulation_dimm;
+    self.profile_end(encoder, ProfiledPass::Simulation, passes, width as u64 * height as u64);
+    self.watch_readback(encoder, first_generation, passes);
+    self.population_readback(encoder, first_generation, passes);
+
+    if count_changes && passes > 0 {
+      let size = passes * mem::size_of::<u32>() as u64;
+      if self.change_readback.encode_copy(encoder, &self.change_log, 0, size) {
+        self.change_readback_span = (first_generation, passes);
+      }
     }
   }
+
+  /// Advance by `simulatiion_steps_per_call` generations without rendering.
+  pub fn simulation_advance_submit(&mut self, device: &Device, queue: &Queue) {
+    // normally uploaded by `prepare`
+    queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniforms]));
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    self.simulation_advance(&mut encoder);
+    self.probe_readback(&mut encoder);
+    self.profile_resolve(&mut encoder);
+    queue.submit(std::iter::once(encoder.finish()));
+    self.after_submit();
+    self.profile_submitted(queue);
+  }
+
+  /// Changed cells of each pass, as `(generation reached, changed cells)`,
+  /// once the readback of a previous `simulation_advance` completes.
+  pub(in super) fn read_change_log(&mut self) -> Option<Vec<(u64, u32)>> {
+    let counts = self.change_readback.try_read::<u32>()?;
+    let (first_generation, passes) = self.change_readback_span;
+    Some(counts.into_iter()
+      .take(passes as usize)
+      .enumerate()
+      .map(|(i, changed)| (first_generation + i as u64 + 1, changed))
+      .collect())
+  }
+}
+
+/// Size of a buffer holding one `element_size` element per cell.
+fn simulation_bytes([width, height]: [u32; 2], element_size: usize) -> Option<u64> {
+  (width as u64).checked_mul(height as u64)?.checked_mul(element_size as u64)
 }
 
-pub(in super) const HUTTON32_COLORS: [[u8; 3]; 32] = [
+pub(crate) const HUTTON32_COLORS: [[u8; 3]; 32] = [
   [ 0 ,   0,   0],    // 0  dark gray
   [255,   0,   0],    // 1  red
   [255, 125,   0],    // 2  orange (to match red and yellow)
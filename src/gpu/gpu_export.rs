@@ -0,0 +1,117 @@
+use {
+  wgpu::{
+    util::{DeviceExt, BufferInitDescriptor}, BufferDescriptor, BufferUsages, Device, Queue,
+    TextureFormat, TextureViewDescriptor,
+  },
+  image::RgbaImage,
+  super::{gpu_io::map_read_blocking, Uniform, OVERLAY_ANY_ZOOM},
+  crate::pattern::CellRect,
+};
+
+/// Largest side of the offscreen texture, exports beyond it are rendered in
+/// tiles.
+const MAX_TILE_PIXELS: u32 = 4096;
+
+/// Largest image `render_region` produces, in bytes.
+const MAX_IMAGE_BYTES: u64 = 1 << 32;
+
+impl super::GPUDriver {
+  /// Blocking render of `rect` at `scale` pixels per cell, with `overlay`
+  /// bits drawn at any zoom. Cells outside the simulation are transparent.
+  pub fn render_region(
+    &self,
+    device: &Device,
+    queue: &Queue,
+    rect: CellRect,
+    scale: u32,
+    overlay: u32,
+  ) -> Result<RgbaImage, String> {
+    let scale = scale.max(1);
+    let (width, height) = (rect.width as u64 * scale as u64, rect.height as u64 * scale as u64);
+    if width == 0 || height == 0 {
+      return Err("empty region".to_string());
+    }
+    if width > u32::MAX as u64 || height > u32::MAX as u64 || width * height * 4 > MAX_IMAGE_BYTES {
+      return Err(format!("{width}×{height} pixels is too large"));
+    }
+    let mut image = RgbaImage::new(width as u32, height as u32);
+
+    // whole cells per tile, so that tiles meet on cell edges
+    let tile_pixels = MAX_TILE_PIXELS.min(device.limits().max_texture_dimension_2d);
+    let tile_cells = [
+      (tile_pixels / scale).clamp(1, rect.width),
+      (tile_pixels / scale).clamp(1, rect.height),
+    ];
+    let tile_size = tile_cells.map(|n| n * scale);
+
+    // sRGB, so that the PNG holds the palette colours, see the end of `fs_main`
+    let format = TextureFormat::Rgba8UnormSrgb;
+    let pipeline = self.export_pipeline
+      .get_or_init(|| Self::create_render_pipeline(device, &self.shader, &self.bind_group_layout, format));
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("export_texture"),
+      size: wgpu::Extent3d { width: tile_size[0], height: tile_size[1], depth_or_array_layers: 1 },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+    });
+    let texture_view = texture.create_view(&TextureViewDescriptor::default());
+    let bytes_per_row = (tile_size[0] * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let staging_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Export Readback Buffer"),
+      size: bytes_per_row as u64 * tile_size[1] as u64,
+      usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
+      label: Some("export_uniforms"),
+      contents: bytemuck::cast_slice(&[self.uniforms]),
+      usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+    });
+    let bind_group = self.create_view_bind_group(device, &uniform_buffer);
+
+    for tile_y in (0..rect.height).step_by(tile_cells[1] as usize) {
+      for tile_x in (0..rect.width).step_by(tile_cells[0] as usize) {
+        // the last tiles are rendered whole, and cropped by the copy
+        let (x, y) = ((rect.x + tile_x as i64) as f32, (rect.y + tile_y as i64) as f32);
+        let uniforms = Uniform {
+          display_x_range: [x, x + tile_cells[0] as f32],
+          display_y_range: [-y - tile_cells[1] as f32, -y],
+          overlay: overlay | OVERLAY_ANY_ZOOM,
+          ..self.uniforms
+        };
+        queue.write_buffer(&uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+
+        let copy_width = (rect.width - tile_x).min(tile_cells[0]) * scale;
+        let copy_height = (rect.height - tile_y).min(tile_cells[1]) * scale;
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.render_to(&mut encoder, pipeline, &texture_view, &bind_group);
+        encoder.copy_texture_to_buffer(
+          texture.as_image_copy(),
+          wgpu::ImageCopyBuffer {
+            buffer: &staging_buffer,
+            layout: wgpu::ImageDataLayout {
+              offset: 0,
+              bytes_per_row: std::num::NonZeroU32::new(bytes_per_row),
+              rows_per_image: None,
+            },
+          },
+          wgpu::Extent3d { width: copy_width, height: copy_height, depth_or_array_layers: 1 },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let data: Vec<u8> = map_read_blocking(device, &staging_buffer);
+        for row in 0..copy_height {
+          let line = &data[(row * bytes_per_row) as usize..][..(copy_width * 4) as usize];
+          for (column, pixel) in line.chunks_exact(4).enumerate() {
+            let (px, py) = (tile_x * scale + column as u32, tile_y * scale + row);
+            image.put_pixel(px, py, image::Rgba(pixel.try_into().unwrap()));
+          }
+        }
+      }
+    }
+    Ok(image)
+  }
+}
@@ -0,0 +1,153 @@
+use {
+  std::mem,
+  wgpu::{
+    BindGroup, BindGroupLayout, Buffer, BufferDescriptor, BufferUsages, CommandEncoder,
+    ComputePipeline, Device, Queue, ShaderModule,
+  },
+  crate::pattern::CellRect,
+  super::{gpu_readback::AsyncReadback, GPUDriver, StoragePass},
+};
+
+/// Largest side of a followed window, so that the coordinate sums of
+/// `compute_follow` cannot overflow.
+pub const MAX_FOLLOW_WINDOW: u32 = 1024;
+
+/// Cells whose centroid is followed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FollowClass {
+  /// Cells which changed in the last generation.
+  Changes = 0,
+  /// Cells carrying a signal.
+  Excited = 1,
+  NonGround = 2,
+}
+
+impl FollowClass {
+  pub const ALL: [FollowClass; 3] = [FollowClass::Changes, FollowClass::Excited, FollowClass::NonGround];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      FollowClass::Changes => "changed cells",
+      FollowClass::Excited => "excited cells",
+      FollowClass::NonGround => "non-ground cells",
+    }
+  }
+}
+
+/// Layout of `FollowWindow` in `./src/kernel/follow.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct FollowWindowRecord {
+  rect: [u32; 4],
+  cells: u32,
+  _padding: [u32; 3],
+}
+
+/// Centroid of a class of cells within a window, reduced by
+/// `compute_follow` once per frame.
+pub struct Follow {
+  pipeline: ComputePipeline,
+  bind_group: BindGroup,
+  window_buffer: Buffer,
+  sums_buffer: Buffer,
+  readback: AsyncReadback,
+  window: Option<FollowWindowRecord>,
+  /// Origin of the window being read back.
+  readback_origin: [u32; 2],
+}
+
+impl Follow {
+  pub(in super) fn new(device: &Device, shader: &ShaderModule, simulation_layout: &BindGroupLayout) -> Self {
+    let window_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Follow Window Buffer"),
+      size: mem::size_of::<FollowWindowRecord>() as _,
+      usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let sums_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Follow Sums Buffer"),
+      size: (3 * mem::size_of::<u32>()) as _,
+      usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let StoragePass { pipeline, bind_group, readback } = GPUDriver::create_storage_pass(
+      device, shader, simulation_layout, "follow", "compute_follow",
+      &[(&window_buffer, true), (&sums_buffer, false)],
+      sums_buffer.size(),
+    );
+
+    Self {
+      pipeline,
+      bind_group,
+      window_buffer,
+      readback,
+      sums_buffer,
+      window: None,
+      readback_origin: [0, 0],
+    }
+  }
+
+  pub(in super) fn after_submit(&self) {
+    self.readback.map();
+  }
+}
+
+impl super::GPUDriver {
+  /// Follow `class` cells within `window`, clipped to the simulation and to
+  /// `MAX_FOLLOW_WINDOW`, or nothing.
+  pub fn set_follow(&mut self, queue: &Queue, window: Option<(CellRect, FollowClass)>) {
+    let record = window.and_then(|(rect, class)| {
+      let rect = CellRect {
+        width: rect.width.min(MAX_FOLLOW_WINDOW),
+        height: rect.height.min(MAX_FOLLOW_WINDOW),
+        ..rect
+      }.clip(self.uniforms.simulation_dimm)?;
+      Some(FollowWindowRecord {
+        rect: [rect.x as u32, rect.y as u32, rect.width, rect.height],
+        cells: class as u32,
+        _padding: [0; 3],
+      })
+    });
+    if let Some(record) = record.filter(|&record| Some(record) != self.follow.window) {
+      queue.write_buffer(&self.follow.window_buffer, 0, bytemuck::bytes_of(&record));
+    }
+    self.follow.window = record;
+  }
+
+  /// Reduce the followed window, unless the previous sums are still being
+  /// read back.
+  pub(in super) fn follow_pass(&mut self, encoder: &mut CommandEncoder) {
+    let Some(window) = self.follow.window else {
+      return;
+    };
+    if !self.follow.readback.is_idle() {
+      return;
+    }
+    encoder.clear_buffer(&self.follow.sums_buffer, 0, None);
+    {
+      let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+      cpass.set_pipeline(&self.follow.pipeline);
+      cpass.set_bind_group(0, &self.bind_group, &[]);
+      cpass.set_bind_group(1, &self.follow.bind_group, &[]);
+      cpass.insert_debug_marker("compute follow");
+      cpass.dispatch_workgroups(window.rect[2].div_ceil(8), window.rect[3].div_ceil(8), 1);
+    }
+    if self.follow.readback.encode_copy(encoder, &self.follow.sums_buffer, 0, self.follow.sums_buffer.size()) {
+      self.follow.readback_origin = [window.rect[0], window.rect[1]];
+    }
+  }
+
+  /// Centroid of the followed cells in simulation coordinates, once a
+  /// readback completes and if any cell matched.
+  pub fn poll_follow(&mut self) -> Option<[f64; 2]> {
+    let sums = self.follow.readback.try_read::<u32>()?;
+    let [count, sum_x, sum_y] = sums[..] else {
+      return None;
+    };
+    let [x, y] = self.follow.readback_origin;
+    (count != 0).then(|| [
+      x as f64 + sum_x as f64 / count as f64 + 0.5,
+      y as f64 + sum_y as f64 / count as f64 + 0.5,
+    ])
+  }
+}
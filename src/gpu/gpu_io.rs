@@ -0,0 +1,130 @@
+use {
+  std::mem,
+  wgpu::{BufferDescriptor, BufferUsages, Device, Queue},
+  crate::{
+    history::CellEdit,
+    pattern::{CellRect, Pattern},
+  },
+};
+
+/// Current state of a cell record, see `get_cell` in `./src/kernel/util.wgsl`.
+pub fn record_state(record: u32) -> u8 {
+  let parity = record >> 16 != 0;
+  (record >> (u32::from(!parity) * 8)) as u8
+}
+
+/// Overwrite both generations of a record, keeping its parity.
+pub fn record_with_state(record: u32, state: u8) -> u32 {
+  (record & 0xffff_0000) | (state as u32) << 8 | state as u32
+}
+
+impl super::GPUDriver {
+  /// Blocking readback of the raw cell records within `rect`, row-major.
+  pub fn read_records(&self, device: &Device, queue: &Queue, rect: CellRect) -> Vec<u32> {
+    let Some(rect) = rect.clip(self.uniforms.simulation_dimm) else {
+      return vec![];
+    };
+    let cell_size = mem::size_of::<u32>() as u64;
+    let row_size = rect.width as u64 * cell_size;
+    let staging_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Readback Buffer"),
+      size: row_size * rect.height as u64,
+      usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    for row in 0..rect.height as u64 {
+      let offset = (rect.y as u64 + row) * self.uniforms.simulation_dimm[0] as u64 + rect.x as u64;
+      encoder.copy_buffer_to_buffer(
+        &self.simulation_buffer, offset * cell_size,
+        &staging_buffer, row * row_size,
+        row_size
+      );
+    }
+    queue.submit(std::iter::once(encoder.finish()));
+
+    map_read_blocking(device, &staging_buffer)
+  }
+
+  /// Blocking readback of the current cell states within `rect`.
+  pub fn read_pattern(&self, device: &Device, queue: &Queue, rect: CellRect) -> Pattern {
+    let rect = rect.clip(self.uniforms.simulation_dimm).unwrap_or(CellRect { width: 0, height: 0, ..rect });
+    Pattern {
+      width: rect.width,
+      height: rect.height,
+      cells: self.read_records(device, queue, rect).into_iter().map(record_state).collect()
+    }
+  }
+
+  /// Write `pattern` at `origin`, clipped to the simulation area. Ground cells
+  /// of the pattern are written as well. Returns the cells which changed.
+  pub fn write_pattern(&self, device: &Device, queue: &Queue, origin: [i64; 2], pattern: &Pattern) -> Vec<CellEdit> {
+    let dest = CellRect { x: origin[0], y: origin[1], width: pattern.width, height: pattern.height };
+    let Some(clipped) = dest.clip(self.uniforms.simulation_dimm) else {
+      return vec![];
+    };
+    let mut records = self.read_records(device, queue, clipped);
+    let mut edits = vec![];
+    for (i, record) in records.iter_mut().enumerate() {
+      let (dx, dy) = (i as u32 % clipped.width, i as u32 / clipped.width);
+      let x = (clipped.x - dest.x) as u32 + dx;
+      let y = (clipped.y - dest.y) as u32 + dy;
+      let (old, new) = (record_state(*record), pattern.get(x, y));
+      if old != new {
+        edits.push(CellEdit { x: clipped.x as u32 + dx, y: clipped.y as u32 + dy, old, new });
+      }
+      *record = record_with_state(*record, new);
+    }
+    self.write_records(queue, clipped, &records);
+    edits
+  }
+
+  /// Write individual cell states, later entries win.
+  pub fn write_cells(&self, device: &Device, queue: &Queue, cells: &[([u32; 2], u8)]) {
+    if cells.is_empty() {
+      return;
+    }
+    let (min, max) = cells.iter().fold(([u32::MAX; 2], [0; 2]), |(min, max), &(xy, _)| (
+      [min[0].min(xy[0]), min[1].min(xy[1])],
+      [max[0].max(xy[0]), max[1].max(xy[1])]
+    ));
+    let bbox = CellRect::from_corners([min[0] as i64, min[1] as i64], [max[0] as i64, max[1] as i64]);
+    let Some(bbox) = bbox.clip(self.uniforms.simulation_dimm) else {
+      return;
+    };
+    let mut records = self.read_records(device, queue, bbox);
+    for &(xy, state) in cells {
+      let (x, y) = (xy[0] as i64 - bbox.x, xy[1] as i64 - bbox.y);
+      if x >= 0 && y >= 0 && x < bbox.width as i64 && y < bbox.height as i64 {
+        let record = &mut records[(y * bbox.width as i64 + x) as usize];
+        *record = record_with_state(*record, state);
+      }
+    }
+    self.write_records(queue, bbox, &records);
+  }
+
+  pub fn simulation_rect(&self) -> CellRect {
+    let [width, height] = self.uniforms.simulation_dimm;
+    CellRect { x: 0, y: 0, width, height }
+  }
+
+  /// Write raw cell records, `rect` must lie within the simulation area.
+  pub fn write_records(&self, queue: &Queue, rect: CellRect, records: &[u32]) {
+    let cell_size = mem::size_of::<u32>() as u64;
+    for (row, data) in records.chunks(rect.width as usize).enumerate() {
+      let offset = (rect.y as u64 + row as u64) * self.uniforms.simulation_dimm[0] as u64 + rect.x as u64;
+      queue.write_buffer(&self.simulation_buffer, offset * cell_size, bytemuck::cast_slice(data));
+    }
+  }
+}
+
+/// Map a `MAP_READ` buffer and wait for the device.
+pub(in super) fn map_read_blocking<T: bytemuck::Pod>(device: &Device, buffer: &wgpu::Buffer) -> Vec<T> {
+  let slice = buffer.slice(..);
+  slice.map_async(wgpu::MapMode::Read, |result| result.expect("Failed to map buffer"));
+  device.poll(wgpu::Maintain::Wait);
+  let data = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+  buffer.unmap();
+  data
+}
@@ -0,0 +1,113 @@
+use {
+  std::mem,
+  wgpu::{
+    BindGroup, BindGroupLayout, Buffer, BufferDescriptor, BufferUsages, CommandEncoder,
+    ComputePipeline, Device, ShaderModule,
+  },
+  super::{gpu_readback::AsyncReadback, GPUDriver, StoragePass, MAX_STEPS_PER_CALL},
+};
+
+/// Number of states, and of bins in each histogram.
+pub const STATES: usize = 32;
+
+/// Per-state cell counts, reduced by `compute_populations` after each
+/// generation.
+pub struct Population {
+  pipeline: ComputePipeline,
+  bind_group: BindGroup,
+  histogram: Buffer,
+  /// Histograms of each pass within the last `simulation_advance`.
+  histogram_log: Buffer,
+  readback: AsyncReadback,
+  /// `(first generation, passes)` of the histogram log being read back.
+  readback_span: (u64, u64),
+  enabled: bool,
+}
+
+impl Population {
+  pub(in super) fn new(device: &Device, shader: &ShaderModule, simulation_layout: &BindGroupLayout) -> Self {
+    let histogram_size = (STATES * mem::size_of::<u32>()) as u64;
+    let histogram = device.create_buffer(&BufferDescriptor {
+      label: Some("Population Histogram"),
+      size: histogram_size,
+      usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let histogram_log = device.create_buffer(&BufferDescriptor {
+      label: Some("Population Log"),
+      size: histogram_size * MAX_STEPS_PER_CALL,
+      usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let StoragePass { pipeline, bind_group, readback } = GPUDriver::create_storage_pass(
+      device, shader, simulation_layout, "population", "compute_populations",
+      &[(&histogram, false)],
+      histogram_log.size(),
+    );
+
+    Self {
+      pipeline,
+      bind_group,
+      histogram,
+      readback,
+      histogram_log,
+      readback_span: (0, 0),
+      enabled: false,
+    }
+  }
+
+  pub(in super) fn after_submit(&self) {
+    self.readback.map();
+  }
+}
+
+impl super::GPUDriver {
+  /// Count cells per state after each generation. Off by default, as it
+  /// costs about as much as the simulation itself.
+  pub fn set_count_populations(&mut self, enabled: bool) {
+    self.population.enabled = enabled;
+  }
+
+  /// Reduce the generation just computed into a histogram, and log it.
+  pub(in super) fn population_pass(&self, encoder: &mut CommandEncoder, pass: u64) {
+    if !self.population.enabled {
+      return;
+    }
+    let [width, height] = self.uniforms.simulation_dimm;
+    {
+      let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+      cpass.set_pipeline(&self.population.pipeline);
+      cpass.set_bind_group(0, &self.bind_group, &[]);
+      cpass.set_bind_group(1, &self.population.bind_group, &[]);
+      cpass.insert_debug_marker("compute populations");
+      cpass.dispatch_workgroups(width.div_ceil(8), height.div_ceil(8), 1);
+    }
+    let size = self.population.histogram.size();
+    encoder.copy_buffer_to_buffer(&self.population.histogram, 0, &self.population.histogram_log, pass * size, size);
+    encoder.clear_buffer(&self.population.histogram, 0, None);
+  }
+
+  /// Histograms of a previous call are still being read back, those of this
+  /// call are skipped then.
+  pub(in super) fn population_readback(&mut self, encoder: &mut CommandEncoder, first_generation: u64, passes: u64) {
+    if !self.population.enabled || passes == 0 {
+      return;
+    }
+    let size = passes * self.population.histogram.size();
+    if self.population.readback.encode_copy(encoder, &self.population.histogram_log, 0, size) {
+      self.population.readback_span = (first_generation, passes);
+    }
+  }
+
+  /// Cells per state, as `(generation, histogram)`, once the readback of a
+  /// previous `simulation_advance` completes.
+  pub(in super) fn read_populations(&mut self) -> Option<Vec<(u64, [u32; STATES])>> {
+    let histograms = self.population.readback.try_read::<u32>()?;
+    let (first_generation, passes) = self.population.readback_span;
+    Some(histograms.chunks_exact(STATES)
+      .take(passes as usize)
+      .enumerate()
+      .map(|(i, histogram)| (first_generation + i as u64 + 1, histogram.try_into().unwrap()))
+      .collect())
+  }
+}
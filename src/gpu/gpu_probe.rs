@@ -0,0 +1,158 @@
+use {
+  std::mem,
+  wgpu::{
+    BindGroup, BindGroupLayout, Buffer, BufferDescriptor, BufferUsages, CommandEncoder,
+    ComputePipeline, Device, Queue, ShaderModule,
+  },
+  super::{gpu_readback::AsyncReadback, GPUDriver, StoragePass, MAX_STEPS_PER_CALL},
+};
+
+pub const MAX_PROBES: usize = 32;
+
+/// Generations kept on the GPU until read back, enough to cover a few frames
+/// of readback latency at the highest step rate.
+const RING_CAPACITY: u64 = 4 * MAX_STEPS_PER_CALL;
+
+/// Probed cells, sampled by `compute_probe` after each generation into a ring
+/// buffer, one row of `MAX_PROBES` states per generation.
+pub struct Probe {
+  pipeline: ComputePipeline,
+  bind_group: BindGroup,
+  cell_buffer: Buffer,
+  sample_buffer: Buffer,
+  ring: Buffer,
+  readback: AsyncReadback,
+  cells: Vec<[u32; 2]>,
+  /// Ring slot the next generation is written to.
+  next_slot: u64,
+  /// Rows in the ring not read back yet.
+  unread: u64,
+  /// Bumped whenever the probed cells change, so stale rows are dropped.
+  epoch: u64,
+  /// `(epoch, first generation, rows)` of the readback in flight.
+  readback_span: (u64, u64, u64),
+}
+
+impl Probe {
+  pub(in super) fn new(device: &Device, shader: &ShaderModule, simulation_layout: &BindGroupLayout) -> Self {
+    let cell_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Probe Cell Buffer"),
+      size: (MAX_PROBES * mem::size_of::<[u32; 2]>()) as _,
+      usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let row_size = (MAX_PROBES * mem::size_of::<u32>()) as u64;
+    let sample_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Probe Sample Buffer"),
+      size: row_size,
+      usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+      mapped_at_creation: false
+    });
+    let ring = device.create_buffer(&BufferDescriptor {
+      label: Some("Probe Ring Buffer"),
+      size: row_size * RING_CAPACITY,
+      usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let StoragePass { pipeline, bind_group, readback } = GPUDriver::create_storage_pass(
+      device, shader, simulation_layout, "probe", "compute_probe",
+      &[(&cell_buffer, true), (&sample_buffer, false)],
+      ring.size(),
+    );
+
+    Self {
+      pipeline,
+      bind_group,
+      cell_buffer,
+      sample_buffer,
+      readback,
+      ring,
+      cells: vec![],
+      next_slot: 0,
+      unread: 0,
+      epoch: 0,
+      readback_span: (0, 0, 0),
+    }
+  }
+
+  pub(in super) fn after_submit(&self) {
+    self.readback.map();
+  }
+}
+
+impl super::GPUDriver {
+  /// Replace the probed cells, at most `MAX_PROBES` are kept. Rows sampled
+  /// for the previous cells are discarded.
+  pub fn set_probes(&mut self, queue: &Queue, cells: &[[u32; 2]]) {
+    let cells = &cells[..cells.len().min(MAX_PROBES)];
+    if self.probe.cells == cells {
+      return;
+    }
+    if !cells.is_empty() {
+      queue.write_buffer(&self.probe.cell_buffer, 0, bytemuck::cast_slice(cells));
+    }
+    self.probe.cells = cells.to_vec();
+    self.probe.unread = 0;
+    self.probe.epoch += 1;
+    self.pending.probe_samples = None;
+  }
+
+  /// Sample the probed cells in the generation just computed.
+  pub(in super) fn probe_pass(&mut self, encoder: &mut CommandEncoder) {
+    if self.probe.cells.is_empty() {
+      return;
+    }
+    {
+      let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+      cpass.set_pipeline(&self.probe.pipeline);
+      cpass.set_bind_group(0, &self.bind_group, &[]);
+      cpass.set_bind_group(1, &self.probe.bind_group, &[]);
+      cpass.insert_debug_marker("compute probes");
+      cpass.dispatch_workgroups(self.probe.cells.len() as u32, 1, 1);
+    }
+    let row_size = self.probe.sample_buffer.size();
+    encoder.copy_buffer_to_buffer(
+      &self.probe.sample_buffer, 0,
+      &self.probe.ring, self.probe.next_slot * row_size,
+      row_size
+    );
+    self.probe.next_slot = (self.probe.next_slot + 1) % RING_CAPACITY;
+    // the oldest unread row is overwritten once the ring is full
+    self.probe.unread = (self.probe.unread + 1).min(RING_CAPACITY);
+  }
+
+  /// Copy the unread rows out of the ring, oldest first, unless the previous
+  /// readback is still in flight.
+  pub(in super) fn probe_readback(&mut self, encoder: &mut CommandEncoder) {
+    let probe = &mut self.probe;
+    if probe.unread == 0 || !probe.readback.is_idle() {
+      return;
+    }
+    let row_size = probe.sample_buffer.size();
+    let first_slot = (probe.next_slot + RING_CAPACITY - probe.unread) % RING_CAPACITY;
+    let head_rows = probe.unread.min(RING_CAPACITY - first_slot);
+    let tail_rows = probe.unread - head_rows;
+
+    probe.readback.encode_copy_ranges(encoder, &probe.ring, &[
+      (first_slot * row_size, head_rows * row_size),
+      (0, tail_rows * row_size),
+    ]);
+    probe.readback_span = (probe.epoch, self.generation + 1 - probe.unread, probe.unread);
+    probe.unread = 0;
+  }
+
+  /// States sampled by the last completed readback, as `(first generation,
+  /// rows)` with one row per generation and one state per probed cell.
+  pub(in super) fn read_probe_samples(&mut self) -> Option<(u64, Vec<Vec<u8>>)> {
+    let samples = self.probe.readback.try_read::<u32>()?;
+    let (epoch, first_generation, rows) = self.probe.readback_span;
+    if epoch != self.probe.epoch {
+      return None;
+    }
+    let width = self.probe.cells.len();
+    Some((first_generation, samples.chunks(MAX_PROBES)
+      .take(rows as usize)
+      .map(|row| row[..width].iter().map(|&state| state as u8).collect())
+      .collect()))
+  }
+}
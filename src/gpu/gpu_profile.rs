@@ -0,0 +1,176 @@
+use {
+  std::{mem, sync::{Arc, Mutex}, time::Instant},
+  wgpu::{
+    Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, Features, QuerySet,
+    QuerySetDescriptor, QueryType, Queue,
+  },
+  crate::profile::ProfileSample,
+  super::gpu_readback::AsyncReadback,
+};
+
+/// Passes timed, with a begin and an end timestamp each.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(in super) enum ProfiledPass {
+  Simulation = 0,
+  Render = 1,
+}
+
+const QUERIES: u32 = 4;
+
+/// What was encoded between the timestamps of one command buffer.
+#[derive(Clone, Copy, Default)]
+struct Span {
+  generations: u64,
+  cells: u64,
+  /// Both timestamps of the pass were written.
+  timed: [bool; 2],
+}
+
+/// Times `simulation_advance` and `render` with GPU timestamps where the
+/// device has `TIMESTAMP_QUERY`, otherwise each submission with the CPU
+/// wall time until the queue reports it done.
+pub struct Profiler {
+  timestamps: Option<Timestamps>,
+  /// Span of the command buffer being encoded.
+  encoding: Span,
+  /// When the first pass of `encoding` was begun, for the wall time.
+  encoding_since: Option<Instant>,
+  /// Sample completed by `on_submitted_work_done` without timestamps.
+  wall_sample: Arc<Mutex<Option<ProfileSample>>>,
+}
+
+struct Timestamps {
+  query_set: QuerySet,
+  resolve_buffer: Buffer,
+  readback: AsyncReadback,
+  /// Nanoseconds per tick.
+  period: f64,
+  /// Span of the readback in flight.
+  reading: Span,
+}
+
+impl Profiler {
+  pub(in super) fn new(device: &Device, queue: &Queue) -> Self {
+    let timestamps = device.features().contains(Features::TIMESTAMP_QUERY).then(|| {
+      let resolve_buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("Timestamp Resolve Buffer"),
+        size: QUERIES as u64 * mem::size_of::<u64>() as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+        mapped_at_creation: false
+      });
+      Timestamps {
+        query_set: device.create_query_set(&QuerySetDescriptor {
+          label: Some("Timestamp Query Set"),
+          ty: QueryType::Timestamp,
+          count: QUERIES,
+        }),
+        readback: AsyncReadback::new(device, resolve_buffer.size(), "Timestamp Readback"),
+        resolve_buffer,
+        period: queue.get_timestamp_period() as f64,
+        reading: Span::default(),
+      }
+    });
+    Self {
+      timestamps,
+      encoding: Span::default(),
+      encoding_since: None,
+      wall_sample: Arc::new(Mutex::new(None)),
+    }
+  }
+
+  pub(in super) fn after_submit(&self) {
+    if let Some(timestamps) = &self.timestamps {
+      timestamps.readback.map();
+    }
+  }
+}
+
+impl super::GPUDriver {
+  /// GPU timestamps are available, rather than CPU wall time.
+  pub fn has_timestamps(&self) -> bool {
+    self.profiler.timestamps.is_some()
+  }
+
+  pub(in super) fn profile_begin(&mut self, encoder: &mut CommandEncoder, pass: ProfiledPass) {
+    if let Some(timestamps) = &self.profiler.timestamps {
+      encoder.write_timestamp(&timestamps.query_set, 2 * pass as u32);
+    }
+    self.profiler.encoding_since.get_or_insert_with(Instant::now);
+  }
+
+  /// `generations` of `cells` cells each were encoded since `profile_begin`.
+  pub(in super) fn profile_end(&mut self, encoder: &mut CommandEncoder, pass: ProfiledPass, generations: u64, cells: u64) {
+    if let Some(timestamps) = &self.profiler.timestamps {
+      encoder.write_timestamp(&timestamps.query_set, 2 * pass as u32 + 1);
+    }
+    let span = &mut self.profiler.encoding;
+    span.timed[pass as usize] = true;
+    span.generations += generations;
+    span.cells = span.cells.max(cells);
+  }
+
+  /// Resolve the timestamps written into `encoder`, unless the previous ones
+  /// are still being read back, in which case they are dropped.
+  pub(in super) fn profile_resolve(&mut self, encoder: &mut CommandEncoder) {
+    let Some(timestamps) = &mut self.profiler.timestamps else {
+      return;
+    };
+    let span = mem::take(&mut self.profiler.encoding);
+    self.profiler.encoding_since = None;
+    if span.timed == [false; 2] || !timestamps.readback.is_idle() {
+      return;
+    }
+    encoder.resolve_query_set(&timestamps.query_set, 0..QUERIES, &timestamps.resolve_buffer, 0);
+    if timestamps.readback.encode_copy(encoder, &timestamps.resolve_buffer, 0, timestamps.resolve_buffer.size()) {
+      timestamps.reading = span;
+    }
+  }
+
+  /// Without timestamps, times the submission just made from the CPU, from
+  /// when its encoding began up to when the queue reports it done. Drivers
+  /// running the work inside `submit` would otherwise report nothing.
+  pub(in super) fn profile_submitted(&mut self, queue: &Queue) {
+    if self.profiler.timestamps.is_some() {
+      return;
+    }
+    let span = mem::take(&mut self.profiler.encoding);
+    let Some(begun) = self.profiler.encoding_since.take() else {
+      return;
+    };
+    if span.timed == [false; 2] {
+      return;
+    }
+    let wall_sample = self.profiler.wall_sample.clone();
+    queue.on_submitted_work_done(move || {
+      let ms = begun.elapsed().as_secs_f64() * 1000.0;
+      // the whole submission, credited to the simulation if it ran
+      *wall_sample.lock().unwrap() = Some(ProfileSample {
+        timestamps: false,
+        generations: span.generations,
+        cells: span.cells,
+        simulation_ms: (span.generations > 0).then_some(ms),
+        render_ms: (span.generations == 0 && span.timed[ProfiledPass::Render as usize]).then_some(ms),
+      });
+    });
+  }
+
+  /// Latest timing, once its readback or submission completes.
+  pub fn poll_profile(&mut self) -> Option<ProfileSample> {
+    let Some(timestamps) = &self.profiler.timestamps else {
+      return self.profiler.wall_sample.lock().unwrap().take();
+    };
+    let ticks = timestamps.readback.try_read::<u64>()?;
+    let span = timestamps.reading;
+    let ms = |pass: ProfiledPass| span.timed[pass as usize].then(|| {
+      let (begin, end) = (ticks[2 * pass as usize], ticks[2 * pass as usize + 1]);
+      end.saturating_sub(begin) as f64 * timestamps.period / 1e6
+    });
+    Some(ProfileSample {
+      timestamps: true,
+      generations: span.generations,
+      cells: span.cells,
+      simulation_ms: ms(ProfiledPass::Simulation).filter(|_| span.generations > 0),
+      render_ms: ms(ProfiledPass::Render),
+    })
+  }
+}
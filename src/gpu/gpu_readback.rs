@@ -0,0 +1,87 @@
+use {
+  std::sync::{Arc, atomic::{AtomicU8, Ordering}},
+  wgpu::{Buffer, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoder, Device},
+};
+
+const IDLE: u8 = 0;
+const COPY_ENCODED: u8 = 1;
+const MAPPING: u8 = 2;
+const MAPPED: u8 = 3;
+
+/// Staging buffer read back without stalling the frame: the copy is encoded
+/// with the simulation, mapped once submitted, and picked up by a later frame.
+pub struct AsyncReadback {
+  buffer: Buffer,
+  state: Arc<AtomicU8>,
+}
+
+impl AsyncReadback {
+  pub fn new(device: &Device, size: BufferAddress, label: &str) -> Self {
+    Self {
+      buffer: device.create_buffer(&BufferDescriptor {
+        label: Some(label),
+        size,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false
+      }),
+      state: Arc::new(AtomicU8::new(IDLE)),
+    }
+  }
+
+  pub fn is_idle(&self) -> bool {
+    self.state.load(Ordering::Acquire) == IDLE
+  }
+
+  /// Encode a copy into the staging buffer, unless a previous one is still in flight.
+  pub fn encode_copy(
+    &self,
+    encoder: &mut CommandEncoder,
+    source: &Buffer,
+    source_offset: BufferAddress,
+    size: BufferAddress
+  ) -> bool {
+    self.encode_copy_ranges(encoder, source, &[(source_offset, size)])
+  }
+
+  /// Like `encode_copy`, packing several `(offset, size)` ranges of the source
+  /// back to back.
+  pub fn encode_copy_ranges(
+    &self,
+    encoder: &mut CommandEncoder,
+    source: &Buffer,
+    ranges: &[(BufferAddress, BufferAddress)]
+  ) -> bool {
+    if !self.is_idle() {
+      return false;
+    }
+    let mut offset = 0;
+    for &(source_offset, size) in ranges.iter().filter(|(_, size)| *size > 0) {
+      encoder.copy_buffer_to_buffer(source, source_offset, &self.buffer, offset, size);
+      offset += size;
+    }
+    self.state.store(COPY_ENCODED, Ordering::Release);
+    true
+  }
+
+  /// Must be called after the encoder holding the copy was submitted.
+  pub fn map(&self) {
+    if self.state.compare_exchange(COPY_ENCODED, MAPPING, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+      let state = self.state.clone();
+      self.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+        state.store(if result.is_ok() { MAPPED } else { IDLE }, Ordering::Release);
+      });
+    }
+  }
+
+  /// Contents of the last completed copy, if any. Requires the device to be
+  /// polled in between.
+  pub fn try_read<T: bytemuck::Pod>(&self) -> Option<Vec<T>> {
+    if self.state.load(Ordering::Acquire) != MAPPED {
+      return None;
+    }
+    let data = bytemuck::cast_slice(&self.buffer.slice(..).get_mapped_range()).to_vec();
+    self.buffer.unmap();
+    self.state.store(IDLE, Ordering::Release);
+    Some(data)
+  }
+}
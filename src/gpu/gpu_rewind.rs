@@ -0,0 +1,110 @@
+use {
+  std::mem,
+  wgpu::{Buffer, BufferDescriptor, BufferUsages, CommandEncoder, Device, Queue},
+};
+
+/// GPU side ring buffer of the most recent generations.
+pub struct Rewind {
+  buffer: Buffer,
+  slot_size: u64,
+  capacity: u32,
+  /// Slot of the newest generation.
+  head: u32,
+  len: u32,
+  /// How many generations back the simulation buffer currently is.
+  cursor: u32,
+}
+
+impl Rewind {
+  fn slot_offset(&self, back: u32) -> u64 {
+    ((self.head + self.capacity - back) % self.capacity) as u64 * self.slot_size
+  }
+}
+
+impl super::GPUDriver {
+  /// Enable rewind with room for `depth` generations, or disable it with `None`.
+  /// The depth is clamped to fit into a single buffer. The current generation
+  /// is retained right away.
+  pub fn set_rewind(&mut self, device: &Device, queue: &Queue, depth: Option<u32>) {
+    let slot_size = self.uniforms.simulation_dimm[0] as u64 * self.uniforms.simulation_dimm[1] as u64
+      * mem::size_of::<u32>() as u64;
+    self.rewind = depth.map(|depth| {
+      let capacity = depth.clamp(1, (device.limits().max_buffer_size / slot_size).max(1) as u32);
+      Rewind {
+        buffer: device.create_buffer(&BufferDescriptor {
+          label: Some("Rewind Buffer"),
+          size: slot_size * capacity as u64,
+          usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+          mapped_at_creation: false
+        }),
+        slot_size,
+        capacity,
+        head: capacity - 1,
+        len: 0,
+        cursor: 0,
+      }
+    });
+    self.rewind_clear(device, queue);
+  }
+
+  /// Drop every retained generation but the current one. Called whenever the
+  /// simulation buffer is overwritten other than by stepping.
+  pub fn rewind_clear(&mut self, device: &Device, queue: &Queue) {
+    let Some(rewind) = self.rewind.as_mut() else {
+      return;
+    };
+    rewind.len = 0;
+    rewind.cursor = 0;
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    self.rewind_record(&mut encoder);
+    queue.submit(std::iter::once(encoder.finish()));
+  }
+
+  pub fn rewind_depth(&self) -> Option<u32> {
+    self.rewind.as_ref().map(|r| r.capacity)
+  }
+
+  /// `(retained generations, generations back)`
+  pub fn rewind_state(&self) -> Option<(u32, u32)> {
+    self.rewind.as_ref().map(|r| (r.len, r.cursor))
+  }
+
+  /// Push the current simulation buffer into the ring. Generations newer
+  /// than the one being displayed are dropped first.
+  pub(in super) fn rewind_record(&mut self, encoder: &mut CommandEncoder) {
+    let Some(rewind) = self.rewind.as_mut() else {
+      return;
+    };
+    rewind.head = (rewind.head + rewind.capacity - rewind.cursor) % rewind.capacity;
+    rewind.len -= rewind.cursor;
+    rewind.cursor = 0;
+
+    rewind.head = (rewind.head + 1) % rewind.capacity;
+    rewind.len = (rewind.len + 1).min(rewind.capacity);
+    encoder.copy_buffer_to_buffer(
+      &self.simulation_buffer, 0,
+      &rewind.buffer, rewind.slot_offset(0),
+      rewind.slot_size
+    );
+  }
+
+  /// Restore the generation `back` steps behind the newest one retained.
+  pub fn rewind_seek(&mut self, device: &Device, queue: &Queue, back: u32) -> bool {
+    let Some(rewind) = self.rewind.as_mut() else {
+      return false;
+    };
+    if back >= rewind.len {
+      return false;
+    }
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.copy_buffer_to_buffer(
+      &rewind.buffer, rewind.slot_offset(back),
+      &self.simulation_buffer, 0,
+      rewind.slot_size
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+    let previous = mem::replace(&mut rewind.cursor, back);
+    self.generation = (self.generation + previous as u64).saturating_sub(back as u64);
+    true
+  }
+}
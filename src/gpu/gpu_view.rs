@@ -0,0 +1,112 @@
+use {
+  wgpu::{
+    util::{DeviceExt, BufferInitDescriptor}, BindGroup, Buffer, BufferUsages, CommandEncoder,
+    Device, Queue, Texture, TextureView, TextureViewDescriptor,
+  },
+  egui::plot::PlotBounds,
+  super::Uniform,
+};
+
+/// Index of a secondary view, see `GPUDriver::add_view`.
+pub type ViewId = usize;
+
+/// Secondary render target of the simulation, with its own display ranges.
+pub struct View {
+  texture: (Texture, TextureView),
+  texture_size: [u32; 2],
+  uniform_buffer: Buffer,
+  bind_group: BindGroup,
+  /// Bounds and size of the next render.
+  pending: Option<(PlotBounds, [u32; 2])>,
+}
+
+impl super::GPUDriver {
+  pub(in super) fn create_view_bind_group(&self, device: &Device, uniform_buffer: &Buffer) -> BindGroup {
+    Self::create_bind_group(device, &self.bind_group_layout, [
+      uniform_buffer, &self.simulation_buffer, &self.lut_buffer, &self.change_counter,
+      &self.heat_buffer, &self.palette_buffer
+    ])
+  }
+
+  /// Allocate a view, rendered alongside the main one once requested with
+  /// `request_view`.
+  pub fn add_view(&mut self, device: &Device) -> ViewId {
+    let uniform_buffer = device.create_buffer_init(&BufferInitDescriptor {
+      label: Some("view_uniforms"),
+      contents: bytemuck::cast_slice(&[self.uniforms]),
+      usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+    });
+    let view = View {
+      texture: Self::create_texture(device, self.target_format, 1, 1, 1),
+      texture_size: [1, 1],
+      bind_group: self.create_view_bind_group(device, &uniform_buffer),
+      uniform_buffer,
+      pending: None,
+    };
+    match self.views.iter().position(Option::is_none) {
+      Some(id) => {
+        self.views[id] = Some(view);
+        id
+      },
+      None => {
+        self.views.push(Some(view));
+        self.views.len() - 1
+      },
+    }
+  }
+
+  pub fn remove_view(&mut self, id: ViewId) {
+    if let Some(view) = self.views.get_mut(id) {
+      *view = None;
+    }
+  }
+
+  /// Render `bounds` into a `size` texture with the next frame.
+  pub fn request_view(&mut self, id: ViewId, bounds: PlotBounds, size: [u32; 2]) {
+    if let Some(Some(view)) = self.views.get_mut(id) {
+      view.pending = Some((bounds, size.map(|n| n.max(1))));
+    }
+  }
+
+  pub fn view_texture(&self, id: ViewId) -> Option<TextureView> {
+    self.views.get(id)?.as_ref()
+      .map(|view| view.texture.0.create_view(&TextureViewDescriptor::default()))
+  }
+
+  /// Bind groups refer to the simulation buffer, which is reallocated with
+  /// each new simulation.
+  pub(in super) fn rebind_views(&mut self, device: &Device) {
+    let bind_groups: Vec<_> = self.views.iter()
+      .map(|view| view.as_ref().map(|view| self.create_view_bind_group(device, &view.uniform_buffer)))
+      .collect();
+    for (view, bind_group) in self.views.iter_mut().zip(bind_groups) {
+      if let (Some(view), Some(bind_group)) = (view, bind_group) {
+        view.bind_group = bind_group;
+      }
+    }
+  }
+
+  pub(in super) fn render_views(&mut self, device: &Device, queue: &Queue, encoder: &mut CommandEncoder) {
+    for id in 0..self.views.len() {
+      let Some(view) = self.views[id].as_mut() else {
+        continue;
+      };
+      let Some((bounds, size)) = view.pending.take() else {
+        continue;
+      };
+      if size != view.texture_size {
+        view.texture_size = size;
+        view.texture = Self::create_texture(device, self.target_format, 1, size[0], size[1]);
+      }
+      let uniforms = Uniform {
+        display_x_range: [bounds.min()[0] as f32, bounds.max()[0] as f32],
+        display_y_range: [bounds.min()[1] as f32, bounds.max()[1] as f32],
+        ..self.uniforms
+      };
+      queue.write_buffer(&view.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+
+      let view = self.views[id].as_ref().unwrap();
+      self.render_to(encoder, &self.render_pipeline, &view.texture.1, &view.bind_group);
+    }
+  }
+}
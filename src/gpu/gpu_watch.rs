@@ -0,0 +1,197 @@
+use {
+  std::mem,
+  wgpu::{
+    BindGroup, BindGroupLayout, Buffer, BufferDescriptor, BufferUsages, CommandEncoder,
+    ComputePipeline, Device, Queue, ShaderModule,
+  },
+  crate::pattern::CellRect,
+  super::{gpu_readback::AsyncReadback, GPUDriver, StoragePass, MAX_STEPS_PER_CALL},
+};
+
+pub const MAX_WATCHPOINTS: usize = 16;
+
+/// Layout of `Watchpoint` in `./src/kernel/watch.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
+struct WatchpointRecord {
+  rect: [u32; 4],
+  states: [u32; 2],
+  kind: u32,
+  _padding: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WatchCondition {
+  /// A cell enters the inclusive state range.
+  Enters(u8, u8),
+  /// A cell changes its state.
+  Changes,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Watchpoint {
+  pub rect: CellRect,
+  pub condition: WatchCondition,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WatchHit {
+  /// Index into the watchpoints passed to `set_watchpoints`.
+  pub watchpoint: usize,
+  /// First generation in which the condition held.
+  pub generation: u64,
+  pub cell: [u32; 2],
+}
+
+/// Watchpoints, evaluated by `compute_watch` after each generation.
+pub struct Watch {
+  pipeline: ComputePipeline,
+  bind_group: BindGroup,
+  watchpoint_buffer: Buffer,
+  hits_buffer: Buffer,
+  /// Hits of each pass within the last `simulation_advance`.
+  hits_log: Buffer,
+  readback: AsyncReadback,
+  /// `(first generation, passes)` of the hits log being read back.
+  readback_span: (u64, u64),
+  /// Clipped dispatch size, and number of watchpoints.
+  dispatch: [u32; 3],
+  /// Contents of `watchpoint_buffer`.
+  records: Vec<WatchpointRecord>,
+}
+
+impl Watch {
+  pub(in super) fn new(device: &Device, shader: &ShaderModule, simulation_layout: &BindGroupLayout) -> Self {
+    let watchpoint_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Watchpoint Buffer"),
+      size: (MAX_WATCHPOINTS * mem::size_of::<WatchpointRecord>()) as _,
+      usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let hits_size = (MAX_WATCHPOINTS * mem::size_of::<u32>()) as u64;
+    let hits_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Watch Hits Buffer"),
+      size: hits_size,
+      usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let hits_log = device.create_buffer(&BufferDescriptor {
+      label: Some("Watch Hits Log"),
+      size: hits_size * MAX_STEPS_PER_CALL,
+      usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+      mapped_at_creation: false
+    });
+    let StoragePass { pipeline, bind_group, readback } = GPUDriver::create_storage_pass(
+      device, shader, simulation_layout, "watch", "compute_watch",
+      &[(&watchpoint_buffer, true), (&hits_buffer, false)],
+      hits_log.size(),
+    );
+
+    Self {
+      pipeline,
+      bind_group,
+      watchpoint_buffer,
+      hits_buffer,
+      readback,
+      hits_log,
+      readback_span: (0, 0),
+      dispatch: [0, 0, 0],
+      records: vec![],
+    }
+  }
+
+  pub(in super) fn after_submit(&self) {
+    self.readback.map();
+  }
+}
+
+impl super::GPUDriver {
+  /// Replace the watchpoints, at most `MAX_WATCHPOINTS` are kept. Nothing is
+  /// uploaded unless they changed.
+  pub fn set_watchpoints(&mut self, queue: &Queue, watchpoints: &[Watchpoint]) {
+    let simulation_dimm = self.uniforms.simulation_dimm;
+    let records: Vec<_> = watchpoints.iter()
+      .take(MAX_WATCHPOINTS)
+      .map(|w| {
+        let rect = w.rect.clip(simulation_dimm)
+          .unwrap_or(CellRect { x: 0, y: 0, width: 0, height: 0 });
+        let (states, kind) = match w.condition {
+          WatchCondition::Enters(lo, hi) => ([lo as u32, hi as u32], 0),
+          WatchCondition::Changes => ([0, 0], 1),
+        };
+        WatchpointRecord {
+          rect: [rect.x as u32, rect.y as u32, rect.width, rect.height],
+          states,
+          kind,
+          _padding: 0,
+        }
+      })
+      .collect();
+    if records == self.watch.records {
+      return;
+    }
+
+    if !records.is_empty() {
+      queue.write_buffer(&self.watch.watchpoint_buffer, 0, bytemuck::cast_slice(&records));
+    }
+    self.watch.dispatch = [
+      records.iter().map(|r| r.rect[2]).max().unwrap_or(0),
+      records.iter().map(|r| r.rect[3]).max().unwrap_or(0),
+      records.len() as u32,
+    ];
+    self.watch.records = records;
+    self.pending.watch_hits.clear();
+  }
+
+  /// Evaluate the watchpoints against the generation just computed, and log
+  /// the hits of this pass.
+  pub(in super) fn watch_pass(&self, encoder: &mut CommandEncoder, pass: u64) {
+    let [x, y, z] = self.watch.dispatch;
+    if x == 0 || y == 0 || z == 0 {
+      return;
+    }
+    {
+      let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+      cpass.set_pipeline(&self.watch.pipeline);
+      cpass.set_bind_group(0, &self.bind_group, &[]);
+      cpass.set_bind_group(1, &self.watch.bind_group, &[]);
+      cpass.insert_debug_marker("compute watchpoints");
+      cpass.dispatch_workgroups(x, y, z);
+    }
+    let hits_size = self.watch.hits_buffer.size();
+    encoder.copy_buffer_to_buffer(&self.watch.hits_buffer, 0, &self.watch.hits_log, pass * hits_size, hits_size);
+    encoder.clear_buffer(&self.watch.hits_buffer, 0, None);
+  }
+
+  pub(in super) fn watch_readback(&mut self, encoder: &mut CommandEncoder, first_generation: u64, passes: u64) {
+    if self.watch.dispatch[2] == 0 || passes == 0 {
+      return;
+    }
+    let size = passes * self.watch.hits_buffer.size();
+    if self.watch.readback.encode_copy(encoder, &self.watch.hits_log, 0, size) {
+      self.watch.readback_span = (first_generation, passes);
+    }
+  }
+
+  /// Earliest hit of each watchpoint within the last completed readback.
+  pub(in super) fn read_watch_hits(&mut self) -> Vec<WatchHit> {
+    let Some(log) = self.watch.readback.try_read::<u32>() else {
+      return vec![];
+    };
+    let (first_generation, passes) = self.watch.readback_span;
+    let width = self.uniforms.simulation_dimm[0];
+    let mut hits: Vec<WatchHit> = vec![];
+    for (pass, pass_hits) in log.chunks(MAX_WATCHPOINTS).take(passes as usize).enumerate() {
+      for (watchpoint, &hit) in pass_hits.iter().enumerate() {
+        if hit != 0 && !hits.iter().any(|h| h.watchpoint == watchpoint) {
+          hits.push(WatchHit {
+            watchpoint,
+            generation: first_generation + pass as u64 + 1,
+            cell: [(hit - 1) % width, (hit - 1) / width],
+          });
+        }
+      }
+    }
+    hits
+  }
+}
@@ -0,0 +1,335 @@
+use {
+  std::{
+    mem,
+    sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+  },
+  wgpu::{Device, Maintain, Queue},
+  super::{GPUDriver, WatchHit, MAX_STEPS_PER_CALL, STATES},
+};
+
+/// The driver as shared between the viewer, its paint callback and the
+/// simulation thread.
+pub type SharedDriver = Arc<Mutex<GPUDriver>>;
+
+/// How many generations the simulation thread submits at a time.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Pace {
+  /// This many generations handed out by the viewer for its current frame,
+  /// submitted once.
+  Frame(u64),
+  /// Batches of this many generations, back to back.
+  Batch(u64),
+  /// Generations per second of wall time.
+  Rate(f64),
+  /// Batches grown or shrunk to take about this many milliseconds of GPU time.
+  Budget(f64),
+}
+
+/// What the simulation thread does, set by the viewer every frame.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WorkerControl {
+  pub running: bool,
+  /// Generation the thread stops at, e.g. a run target, until moved on.
+  pub stop_at: Option<u64>,
+  pub pace: Pace,
+}
+
+impl Default for WorkerControl {
+  fn default() -> Self {
+    Self {
+      running: false,
+      stop_at: None,
+      pace: Pace::Batch(1),
+    }}}
+
+/// How long the thread sleeps when there is nothing to submit.
+const IDLE_SLEEP: Duration = Duration::from_millis(2);
+
+/// Batch sizes at a rate or within a time budget, carried over between
+/// batches. Shared by the simulation thread and the frames picking theirs.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Pacer {
+  /// Generations due at a rate but not yet run.
+  pub owed: f64,
+  /// Batch size settled on within a time budget.
+  pub adaptive: f64,
+}
+
+impl Default for Pacer {
+  fn default() -> Self {
+    Self {
+      owed: 0.0,
+      adaptive: 1.0,
+    }}}
+
+impl Pacer {
+  /// Whole generations due once `dt` more seconds have passed at `rate`
+  /// generations per second, see `ran`.
+  pub fn due(&mut self, rate: f64, dt: f64) -> u64 {
+    self.owed = (self.owed + rate * dt).min(MAX_STEPS_PER_CALL as f64);
+    self.owed.floor() as u64
+  }
+
+  /// `steps` of the generations due have been run, the rest stays owed.
+  pub fn ran(&mut self, steps: u64) {
+    self.owed = (self.owed - steps as f64).max(0.0);
+  }
+
+  /// Grows or shrinks the batch size after a batch took `batch_ms`, towards
+  /// batches of `budget_ms`: at most halved when over, grown by a tenth
+  /// when well under.
+  pub fn adapt(&mut self, budget_ms: f64, batch_ms: f64) -> u64 {
+    self.adaptive = if batch_ms > budget_ms {
+      self.adaptive * (budget_ms / batch_ms).max(0.5)
+    } else if batch_ms < 0.8 * budget_ms {
+      self.adaptive * 1.1 + 1.0
+    } else {
+      self.adaptive
+    }.clamp(1.0, MAX_STEPS_PER_CALL as f64);
+    self.adaptive as u64
+  }
+}
+
+/// Thread advancing the simulation, the only one to do so, stopped and
+/// joined when dropped.
+pub struct SimulationWorker {
+  stop: Arc<AtomicBool>,
+  handle: Option<JoinHandle<()>>,
+}
+
+impl SimulationWorker {
+  pub fn spawn(driver: SharedDriver, device: Arc<Device>, queue: Arc<Queue>) -> Self {
+    Self::spawn_loop(move |stop| worker_loop(&driver, &device, &queue, stop))
+  }
+
+  /// Runs `body` on a thread of its own, until it returns once `stop` is set.
+  fn spawn_loop(body: impl FnOnce(&AtomicBool) + Send + 'static) -> Self {
+    let stop = Arc::new(AtomicBool::new(false));
+    let handle = thread::Builder::new()
+      .name("simulation".to_string())
+      .spawn({
+        let stop = stop.clone();
+        move || body(&stop)
+      })
+      .expect("failed to spawn the simulation thread");
+    Self { stop, handle: Some(handle) }
+  }
+}
+
+impl Drop for SimulationWorker {
+  fn drop(&mut self) {
+    self.stop.store(true, Ordering::Release);
+    if let Some(handle) = self.handle.take() {
+      let _ = handle.join();
+    }
+  }
+}
+
+fn worker_loop(driver: &Mutex<GPUDriver>, device: &Device, queue: &Queue, stop: &AtomicBool) {
+  let mut pacer = Pacer::default();
+  let mut last = Instant::now();
+
+  while !stop.load(Ordering::Acquire) {
+    let now = Instant::now();
+    let dt = (now - last).as_secs_f64().min(0.25);
+    last = now;
+
+    let submitted = {
+      let mut driver = driver.lock().unwrap();
+      let control = driver.worker;
+      if !control.running {
+        pacer.owed = 0.0;
+        None
+      } else {
+        let steps = match control.pace {
+          Pace::Frame(steps) | Pace::Batch(steps) => steps,
+          Pace::Rate(rate) => pacer.due(rate, dt),
+          Pace::Budget(_) => pacer.adaptive as u64,
+        };
+        let steps = match control.stop_at {
+          Some(stop_at) => steps.min(stop_at.saturating_sub(driver.generation)),
+          None => steps,
+        }.min(MAX_STEPS_PER_CALL);
+        (steps > 0).then(|| {
+          driver.simulatiion_steps_per_call = steps;
+          driver.simulation_advance_submit(device, queue);
+          match control.pace {
+            Pace::Frame(_) => driver.worker.pace = Pace::Frame(0),
+            Pace::Rate(_) => pacer.ran(steps),
+            Pace::Batch(_) | Pace::Budget(_) => (),
+          }
+          control.pace
+        })
+      }
+    };
+
+    let Some(pace) = submitted else {
+      thread::sleep(IDLE_SLEEP);
+      continue;
+    };
+    let started = Instant::now();
+    device.poll(Maintain::Wait);
+    // frees the staging buffers for the next batch, whether or not the
+    // viewer is polling, e.g. while minimised
+    driver.lock().unwrap().drain_readbacks();
+    if let Pace::Budget(budget_ms) = pace {
+      pacer.adapt(budget_ms, started.elapsed().as_secs_f64() * 1000.0);
+    }
+  }
+}
+
+/// Generations of logs kept for the viewer, older ones are dropped.
+const MAX_PENDING_GENERATIONS: usize = 1 << 16;
+
+/// Logs read back but not yet polled by the viewer.
+#[derive(Default)]
+pub(in super) struct PendingLogs {
+  changes: Vec<(u64, u32)>,
+  /// Earliest hit of each watchpoint.
+  pub(in super) watch_hits: Vec<WatchHit>,
+  pub(in super) probe_samples: Option<(u64, Vec<Vec<u8>>)>,
+  populations: Vec<(u64, [u32; STATES])>,
+}
+
+fn drop_oldest<T>(log: &mut Vec<T>) {
+  log.drain(..log.len().saturating_sub(MAX_PENDING_GENERATIONS));
+}
+
+impl GPUDriver {
+  /// Moves the completed readbacks into the pending logs, so the staging
+  /// buffers are free for the next submission. A watchpoint hit pauses the
+  /// simulation thread until the viewer has seen it.
+  pub fn drain_readbacks(&mut self) {
+    if let Some(changes) = self.read_change_log() {
+      self.pending.changes.extend(changes);
+      drop_oldest(&mut self.pending.changes);
+    }
+    let hits = self.read_watch_hits();
+    if !hits.is_empty() {
+      self.worker.running = false;
+    }
+    for hit in hits {
+      if !self.pending.watch_hits.iter().any(|h| h.watchpoint == hit.watchpoint) {
+        self.pending.watch_hits.push(hit);
+      }
+    }
+    if let Some((first_generation, rows)) = self.read_probe_samples() {
+      match &mut self.pending.probe_samples {
+        Some((first, pending)) if *first + pending.len() as u64 == first_generation => {
+          pending.extend(rows);
+          let dropped = pending.len().saturating_sub(MAX_PENDING_GENERATIONS);
+          pending.drain(..dropped);
+          *first += dropped as u64;
+        },
+        // a gap, the ring was overrun
+        _ => self.pending.probe_samples = Some((first_generation, rows)),
+      }
+    }
+    if let Some(populations) = self.read_populations() {
+      self.pending.populations.extend(populations);
+      drop_oldest(&mut self.pending.populations);
+    }
+  }
+
+  /// Changed cells of each pass since the last call, as `(generation
+  /// reached, changed cells)`.
+  pub fn poll_change_log(&mut self) -> Option<Vec<(u64, u32)>> {
+    self.drain_readbacks();
+    Some(mem::take(&mut self.pending.changes)).filter(|changes| !changes.is_empty())
+  }
+
+  /// Earliest hit of each watchpoint since the last call.
+  pub fn poll_watch_hits(&mut self) -> Vec<WatchHit> {
+    self.drain_readbacks();
+    mem::take(&mut self.pending.watch_hits)
+  }
+
+  /// States sampled since the last call, as `(first generation, rows)` with
+  /// one row per generation and one state per probed cell.
+  pub fn poll_probe_samples(&mut self) -> Option<(u64, Vec<Vec<u8>>)> {
+    self.drain_readbacks();
+    self.pending.probe_samples.take()
+  }
+
+  /// Cells per state since the last call, as `(generation, histogram)`.
+  pub fn poll_populations(&mut self) -> Option<Vec<(u64, [u32; STATES])>> {
+    self.drain_readbacks();
+    Some(mem::take(&mut self.pending.populations)).filter(|populations| !populations.is_empty())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, std::sync::atomic::AtomicUsize};
+
+  #[test]
+  fn rate_carries_fractions_over() {
+    let mut pacer = Pacer::default();
+    assert_eq!(pacer.due(2.5, 1.0), 2);
+    pacer.ran(2);
+    assert_eq!(pacer.due(2.5, 1.0), 3);
+    // cut short, e.g. by a run target
+    pacer.ran(1);
+    assert_eq!(pacer.due(2.5, 0.2), 2);
+    pacer.ran(2);
+    assert_eq!(pacer.owed, 0.5);
+    assert_eq!(pacer.due(0.4, 1.0), 0);
+    assert_eq!(pacer.due(0.1, 1.0), 1);
+  }
+
+  #[test]
+  fn rate_owes_at_most_one_batch() {
+    let mut pacer = Pacer::default();
+    assert_eq!(pacer.due(1e9, 1.0), MAX_STEPS_PER_CALL);
+    pacer.ran(MAX_STEPS_PER_CALL);
+    assert_eq!(pacer.owed, 0.0);
+  }
+
+  #[test]
+  fn budget_adapts_the_batch_size() {
+    let mut pacer = Pacer::default();
+    // well under budget, grows
+    assert_eq!(pacer.adapt(10.0, 1.0), 2);
+    assert_eq!(pacer.adapt(10.0, 1.0), 3);
+    // within the band, kept
+    assert_eq!(pacer.adapt(10.0, 9.0), 3);
+    pacer.adaptive = 100.0;
+    // over budget, shrunk in proportion but at most halved
+    assert_eq!(pacer.adapt(10.0, 12.5), 80);
+    assert_eq!(pacer.adapt(10.0, 1000.0), 40);
+    for _ in 0..100 {
+      pacer.adapt(10.0, 1000.0);
+    }
+    assert_eq!(pacer.adaptive, 1.0);
+    for _ in 0..200 {
+      pacer.adapt(10.0, 0.0);
+    }
+    assert_eq!(pacer.adaptive, MAX_STEPS_PER_CALL as f64);
+  }
+
+  #[test]
+  fn worker_stops_when_dropped() {
+    let iterations = Arc::new(AtomicUsize::new(0));
+    let finished = Arc::new(AtomicBool::new(false));
+    let worker = SimulationWorker::spawn_loop({
+      let (iterations, finished) = (iterations.clone(), finished.clone());
+      move |stop| {
+        while !stop.load(Ordering::Acquire) {
+          iterations.fetch_add(1, Ordering::Relaxed);
+          thread::sleep(IDLE_SLEEP);
+        }
+        finished.store(true, Ordering::Release);
+      }
+    });
+    while iterations.load(Ordering::Relaxed) == 0 {
+      thread::yield_now();
+    }
+    drop(worker);
+    assert!(finished.load(Ordering::Acquire));
+    let after = iterations.load(Ordering::Relaxed);
+    thread::sleep(IDLE_SLEEP * 5);
+    assert_eq!(iterations.load(Ordering::Relaxed), after);
+  }
+}
@@ -9,16 +9,46 @@ use {
     BindingType, BufferBindingType
   },
   egui::plot::PlotBounds,
+  crate::{palette::Palette, rule::Rule},
 };
 
 mod gpu_automata;
+mod gpu_export;
+mod gpu_follow;
+mod gpu_io;
+mod gpu_population;
+mod gpu_probe;
+mod gpu_profile;
+mod gpu_readback;
+mod gpu_rewind;
+mod gpu_view;
+mod gpu_watch;
+mod gpu_worker;
+
+use gpu_profile::ProfiledPass;
+
+pub(crate) use gpu_automata::HUTTON32_COLORS;
+pub use gpu_follow::{FollowClass, MAX_FOLLOW_WINDOW};
+pub use gpu_population::STATES;
+pub use gpu_probe::MAX_PROBES;
+pub use gpu_view::ViewId;
+pub use gpu_watch::{Watchpoint, WatchCondition, WatchHit, MAX_WATCHPOINTS};
+pub use gpu_worker::{Pace, Pacer, SharedDriver, SimulationWorker, WorkerControl};
 
 #[repr(C)]
 #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Uniform {
   pub display_x_range: [f32; 2],
   pub display_y_range: [f32; 2],
-  pub simulation_dimm: [u32; 2]
+  pub simulation_dimm: [u32; 2],
+  /// Non-zero to have `compute_main` count changed cells.
+  pub count_changes: u32,
+  /// A `RenderMode`.
+  pub render_mode: u32,
+  /// Factor the heat map fades by each generation.
+  pub heat_decay: f32,
+  /// `OVERLAY_*` bits.
+  pub overlay: u32,
 }
 
 impl Default for Uniform {
@@ -27,11 +57,61 @@ impl Default for Uniform {
       display_x_range: [0.0, 1.0],
       display_y_range: [0.0, 1.0],
       simulation_dimm: [1, 1],
+      count_changes: 0,
+      render_mode: RenderMode::Plain as u32,
+      heat_decay: 0.95,
+      overlay: OVERLAY_GLYPHS,
     }}}
 
+/// Draw direction arrows and state shapes once cells are large enough.
+pub const OVERLAY_GLYPHS: u32 = 1;
+/// Draw lines between cells once they are large enough.
+pub const OVERLAY_GRID: u32 = 2;
+/// Draw the other overlays however small cells are, as for exports.
+const OVERLAY_ANY_ZOOM: u32 = 4;
+
+/// How `fs_main` colours cells.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderMode {
+  Plain = 0,
+  /// Cells which changed in the last generation, in red over a dimmed palette.
+  Changes = 1,
+  /// Only cells carrying a signal keep their colour.
+  Excited = 2,
+  /// Recent activity, fading with `Uniform::heat_decay`.
+  Heat = 3,
+}
+
+impl RenderMode {
+  pub const ALL: [RenderMode; 4] = [RenderMode::Plain, RenderMode::Changes, RenderMode::Excited, RenderMode::Heat];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      RenderMode::Plain => "plain",
+      RenderMode::Changes => "changed cells",
+      RenderMode::Excited => "excited only",
+      RenderMode::Heat => "heat map",
+    }
+  }
+}
+
+/// Upper bound of `GPUDriver::simulatiion_steps_per_call`.
+pub const MAX_STEPS_PER_CALL: u64 = 512;
+
+/// Pipeline, bind group and readback of a compute pass reading the
+/// simulation in group 0 and storage buffers of its own in group 1, as the
+/// watch, probe, population and follow passes do.
+struct StoragePass {
+  pipeline: ComputePipeline,
+  bind_group: BindGroup,
+  readback: gpu_readback::AsyncReadback,
+}
+
 pub struct GPUDriver {
   shader: ShaderModule,
   render_pipeline: RenderPipeline,
+  /// `render_pipeline` for `gpu_export`, created on first use.
+  export_pipeline: std::sync::OnceLock<RenderPipeline>,
   compute_pipeline: ComputePipeline,
   texture: (Texture, TextureView),
   target_format: TextureFormat,
@@ -41,10 +121,35 @@ pub struct GPUDriver {
   uniform_buffer: Buffer,
   simulation_buffer: Buffer,
   lut_buffer: Buffer,
+  change_counter: Buffer,
+  /// `Palette` as `array<vec4<f32>, 32>`.
+  palette_buffer: Buffer,
+  /// Activity per cell, accumulated by `compute_main` for `RenderMode::Heat`.
+  heat_buffer: Buffer,
+  /// Changed cells of each pass within the last `simulation_advance`.
+  change_log: Buffer,
+  change_readback: gpu_readback::AsyncReadback,
+  /// `(first generation, passes)` of the change log being read back.
+  change_readback_span: (u64, u64),
+  rewind: Option<gpu_rewind::Rewind>,
+  watch: gpu_watch::Watch,
+  probe: gpu_probe::Probe,
+  population: gpu_population::Population,
+  follow: gpu_follow::Follow,
+  profiler: gpu_profile::Profiler,
+  views: Vec<Option<gpu_view::View>>,
+  pending: gpu_worker::PendingLogs,
 
   pub texture_size: [u32; 2],
   pub uniforms: Uniform,
+  pub lut_hash: u64,
+  /// Last palette passed to `set_palette`.
+  pub palette: Palette,
+  /// Generations encoded so far, advanced by `simulation_advance` only.
+  pub generation: u64,
   pub simulatiion_steps_per_call: u64,
+  /// Read by the simulation thread, if there is one.
+  pub worker: WorkerControl,
 }
 
 impl GPUDriver {
@@ -53,10 +158,6 @@ impl GPUDriver {
     let shader = device.create_shader_module(
       wgsl_preprocessor::ShaderBuilder::new("./src/kernel/main.wgsl")
         .expect("Failed to load ./src/kernel/main.wgsl")
-        .put_array_definition("hutton32_colors",
-          &gpu_automata::HUTTON32_COLORS.map(|x| x.map(|c| c as i32))
-            .iter().collect()
-        )
         .build()
     );
 
@@ -98,37 +199,40 @@ impl GPUDriver {
           min_binding_size: None,
         },
         count: None,
+      },
+      wgpu::BindGroupLayoutEntry { // change_counter
+        binding: 3,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+          ty: BufferBindingType::Storage { read_only: false },
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      },
+      wgpu::BindGroupLayoutEntry { // heat_buffer
+        binding: 4,
+        visibility: ShaderStages::FRAGMENT | ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+          ty: BufferBindingType::Storage { read_only: false },
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      },
+      wgpu::BindGroupLayoutEntry { // palette_buffer
+        binding: 5,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Buffer {
+          ty: BufferBindingType::Uniform,
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
       }],
     });
 
-    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-      label: Some("egui_plot_pipeline_layout"),
-      bind_group_layouts: &[&bind_group_layout],
-      ..Default::default()
-    });
-
-    let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-      label: Some("Render Pipeline"),
-      layout: Some(&render_pipeline_layout),
-      vertex: wgpu::VertexState {
-        module: &shader,
-        entry_point: "vs_main",
-        buffers: &[],
-      },
-      depth_stencil: None,
-      fragment: Some(wgpu::FragmentState {
-        module: &shader,
-        entry_point: "fs_main",
-        targets: &[Some(wgpu::ColorTargetState {
-          format: target_format,
-          blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-          write_mask: wgpu::ColorWrites::ALL,
-        })],
-      }),
-      primitive: Default::default(),
-      multisample: Default::default(),
-      multiview: None
-    });
+    let render_pipeline = Self::create_render_pipeline(device, &shader, &bind_group_layout, target_format);
 
     let uniforms = Uniform {
       simulation_dimm: [1, 1],
@@ -143,7 +247,7 @@ impl GPUDriver {
 
     let simulation_buffer = device.create_buffer(&BufferDescriptor {
       label: Some("Simulation Buffer"),
-      size: (/*sim_dimm[0] * sim_dimm[1]*/ 1 * mem::size_of::<u32>() as u32) as _,
+      size: /*sim_dimm[0] * sim_dimm[1] * */ mem::size_of::<u32>() as _,
       usage: BufferUsages::STORAGE
         | BufferUsages::COPY_DST
         | BufferUsages::COPY_SRC,
@@ -154,21 +258,56 @@ impl GPUDriver {
       label: Some("Simulation Buffer"),
       // c(5)-n(5)-e(5)-s(5)-w(5) = r(8)
       size: 2u32.pow(25) as _, // 32MB
+      usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+      mapped_at_creation: false
+    });
+
+    let change_counter = device.create_buffer(&BufferDescriptor {
+      label: Some("Change Counter"),
+      size: mem::size_of::<u32>() as _,
+      usage: BufferUsages::STORAGE
+        | BufferUsages::COPY_DST
+        | BufferUsages::COPY_SRC,
+      mapped_at_creation: false
+    });
+
+    let palette = Palette::default_for(Rule::ACTIVE);
+    let palette_buffer = device.create_buffer_init(&BufferInitDescriptor {
+      label: Some("Palette Buffer"),
+      contents: bytemuck::cast_slice(&Self::palette_data(&palette)),
+      usage: BufferUsages::COPY_DST | BufferUsages::UNIFORM,
+    });
+
+    let heat_buffer = device.create_buffer(&BufferDescriptor {
+      label: Some("Heat Buffer"),
+      size: mem::size_of::<f32>() as _,
       usage: BufferUsages::STORAGE,
       mapped_at_creation: false
     });
 
+    let change_log = device.create_buffer(&BufferDescriptor {
+      label: Some("Change Log"),
+      size: MAX_STEPS_PER_CALL * mem::size_of::<u32>() as u64,
+      usage: BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+      mapped_at_creation: false
+    });
+
     let bind_group = Self::create_bind_group(
-      &device, &bind_group_layout,
-      &uniform_buffer, &simulation_buffer, &lut_buffer
+      device, &bind_group_layout,
+      [&uniform_buffer, &simulation_buffer, &lut_buffer, &change_counter, &heat_buffer, &palette_buffer]
     );
 
     let compute_pipeline = Self::create_compute_pipeline(device, &[&bind_group_layout], &shader, None);
+    let watch = gpu_watch::Watch::new(device, &shader, &bind_group_layout);
+    let probe = gpu_probe::Probe::new(device, &shader, &bind_group_layout);
+    let population = gpu_population::Population::new(device, &shader, &bind_group_layout);
+    let follow = gpu_follow::Follow::new(device, &shader, &bind_group_layout);
 
-    let this = Self {
+    let mut this = Self {
       shader,
 
       render_pipeline,
+      export_pipeline: Default::default(),
       compute_pipeline,
 
       target_format,
@@ -178,15 +317,34 @@ impl GPUDriver {
       uniform_buffer,
       simulation_buffer,
       lut_buffer,
+      change_counter,
+      heat_buffer,
+      palette_buffer,
+      change_readback: gpu_readback::AsyncReadback::new(device, change_log.size(), "Change Log Readback"),
+      change_log,
+      change_readback_span: (0, 0),
+      rewind: None,
+      watch,
+      probe,
+      population,
+      follow,
+      profiler: gpu_profile::Profiler::new(device, queue),
+      views: vec![],
+      pending: gpu_worker::PendingLogs::default(),
 
       texture,
       texture_size: [0, 0],
       uniforms,
+      lut_hash: 0,
+      palette,
+      generation: 0,
 
-      simulatiion_steps_per_call: 1
+      simulatiion_steps_per_call: 1,
+      worker: WorkerControl::default(),
     };
 
-    this.initialize_ca_lut(&device, &queue);
+    this.initialize_ca_lut(device, queue);
+    this.lut_hash = Self::hash_ca_lut();
     this
   }
 
@@ -220,6 +378,42 @@ impl GPUDriver {
     self.texture.0.create_view(&TextureViewDescriptor::default())
   }
 
+  fn create_render_pipeline(
+    device: &Device,
+    shader: &ShaderModule,
+    bind_group_layout: &BindGroupLayout,
+    target_format: TextureFormat,
+  ) -> RenderPipeline {
+    let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+      label: Some("egui_plot_pipeline_layout"),
+      bind_group_layouts: &[bind_group_layout],
+      ..Default::default()
+    });
+
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+      label: Some("Render Pipeline"),
+      layout: Some(&render_pipeline_layout),
+      vertex: wgpu::VertexState {
+        module: shader,
+        entry_point: "vs_main",
+        buffers: &[],
+      },
+      depth_stencil: None,
+      fragment: Some(wgpu::FragmentState {
+        module: shader,
+        entry_point: "fs_main",
+        targets: &[Some(wgpu::ColorTargetState {
+          format: target_format,
+          blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+          write_mask: wgpu::ColorWrites::ALL,
+        })],
+      }),
+      primitive: Default::default(),
+      multisample: Default::default(),
+      multiview: None
+    })
+  }
+
   fn create_compute_pipeline(
     device: &Device,
     bind_group_layouts: &[&BindGroupLayout],
@@ -231,41 +425,93 @@ impl GPUDriver {
       bind_group_layouts,
       push_constant_ranges: &[],
     });
-    let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+    device.create_compute_pipeline(&ComputePipelineDescriptor {
       label,
       layout: Some(&layout),
       module: shader,
       entry_point: "compute_main",
-    });
-    pipeline
+    })
   }
 
-  fn create_bind_group(
+  /// `buffers` are bound in group 1 in order, with whether each is read only,
+  /// and `readback_size` bytes are read back. `name` prefixes the labels.
+  fn create_storage_pass(
     device: &Device,
-    layout: &BindGroupLayout,
-    uniform_buffer: &Buffer,
-    simulation_buffer: &Buffer,
-    lut_buffer: &Buffer,
-  ) -> BindGroup {
+    shader: &ShaderModule,
+    simulation_layout: &BindGroupLayout,
+    name: &str,
+    entry_point: &str,
+    buffers: &[(&Buffer, bool)],
+    readback_size: wgpu::BufferAddress,
+  ) -> StoragePass {
+    let layout_entries: Vec<_> = buffers.iter().enumerate()
+      .map(|(binding, &(_, read_only))| wgpu::BindGroupLayoutEntry {
+        binding: binding as u32,
+        visibility: ShaderStages::COMPUTE,
+        ty: BindingType::Buffer {
+          ty: BufferBindingType::Storage { read_only },
+          has_dynamic_offset: false,
+          min_binding_size: None,
+        },
+        count: None,
+      })
+      .collect();
+    let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+      label: Some(&format!("{name}_bind_group_layout")),
+      entries: &layout_entries,
+    });
+    let layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+      label: Some(&format!("{name} layout")),
+      bind_group_layouts: &[simulation_layout, &bind_group_layout],
+      push_constant_ranges: &[],
+    });
+    let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+      label: Some(&format!("{name} pipeline")),
+      layout: Some(&layout),
+      module: shader,
+      entry_point,
+    });
+    let entries: Vec<_> = buffers.iter().enumerate()
+      .map(|(binding, (buffer, _))| wgpu::BindGroupEntry {
+        binding: binding as u32,
+        resource: buffer.as_entire_binding(),
+      })
+      .collect();
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some(&format!("{name}_bind_group")),
+      layout: &bind_group_layout,
+      entries: &entries,
+    });
+    let readback = gpu_readback::AsyncReadback::new(device, readback_size, &format!("{name} readback"));
+    StoragePass { pipeline, bind_group, readback }
+  }
+
+  /// `buffers` in binding order: uniforms, simulation, LUT, change counter,
+  /// heat, palette.
+  fn create_bind_group(device: &Device, layout: &BindGroupLayout, buffers: [&Buffer; 6]) -> BindGroup {
+    let entries: Vec<_> = buffers.iter().enumerate()
+      .map(|(binding, buffer)| wgpu::BindGroupEntry {
+        binding: binding as u32,
+        resource: buffer.as_entire_binding(),
+      })
+      .collect();
     device.create_bind_group(&wgpu::BindGroupDescriptor {
       label: Some("egui_plot_bind_group"),
       layout,
-      entries: &[wgpu::BindGroupEntry {
-          binding: 0,
-          resource: uniform_buffer.as_entire_binding(),
-        },
-        wgpu::BindGroupEntry {
-          binding: 1,
-          resource: simulation_buffer.as_entire_binding(),
-        },
-        wgpu::BindGroupEntry {
-          binding: 2,
-          resource: lut_buffer.as_entire_binding(),
-        }
-      ],
+      entries: &entries,
     })
   }
 
+  fn palette_data(palette: &Palette) -> [[f32; 4]; 32] {
+    palette.colors.map(|[r, g, b]| [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0])
+  }
+
+  /// Takes effect on the next render, without recompiling the shader.
+  pub fn set_palette(&mut self, queue: &Queue, palette: &Palette) {
+    self.palette = *palette;
+    queue.write_buffer(&self.palette_buffer, 0, bytemuck::cast_slice(&Self::palette_data(palette)));
+  }
+
   fn prepare(
     &mut self,
     device: &Device,
@@ -287,12 +533,31 @@ impl GPUDriver {
     queue.write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[self.uniforms]));
   }
 
+  fn after_submit(&self) {
+    self.change_readback.map();
+    self.watch.after_submit();
+    self.probe.after_submit();
+    self.population.after_submit();
+    self.follow.after_submit();
+    self.profiler.after_submit();
+  }
+
   fn render(&self, encoder: &mut CommandEncoder) {
-    let view = self.create_view();
+    self.render_to(encoder, &self.render_pipeline, &self.create_view(), &self.bind_group);
+  }
 
+  /// Draw the simulation with the display ranges of the uniforms in
+  /// `bind_group`, `pipeline` matching the format of `view`.
+  fn render_to(
+    &self,
+    encoder: &mut CommandEncoder,
+    pipeline: &RenderPipeline,
+    view: &TextureView,
+    bind_group: &BindGroup
+  ) {
     // Render directly to the texture if no MSAA
     let rpass_color_attachment = wgpu::RenderPassColorAttachment {
-      view: &view,
+      view,
       resolve_target: None,
       ops: wgpu::Operations {
         load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
@@ -306,12 +571,16 @@ impl GPUDriver {
       depth_stencil_attachment: None,
     });
 
-    self.render_onto_renderpass(&mut rpass);
+    Self::render_onto_renderpass(&mut rpass, pipeline, bind_group);
   }
 
-  fn render_onto_renderpass<'rp>(&'rp self, rpass: &mut wgpu::RenderPass<'rp>) {
-    rpass.set_pipeline(&self.render_pipeline);
-    rpass.set_bind_group(0, &self.bind_group, &[]);
+  fn render_onto_renderpass<'rp>(
+    rpass: &mut wgpu::RenderPass<'rp>,
+    pipeline: &'rp RenderPipeline,
+    bind_group: &'rp BindGroup
+  ) {
+    rpass.set_pipeline(pipeline);
+    rpass.set_bind_group(0, bind_group, &[]);
     rpass.draw(0..6, 0..1);
   }
 }
@@ -319,18 +588,18 @@ impl GPUDriver {
 pub fn egui_wgpu_callback(
   bounds: PlotBounds,
   rect: egui::Rect,
-  compute_requested: bool
 ) -> egui::PaintCallback {
   let cb = egui_wgpu::CallbackFn::new()
     .prepare(move |device, queue, _encoder, paint_callback_resources| {
-      let gpu_driver: &mut GPUDriver = paint_callback_resources.get_mut().unwrap();
+      let shared_driver: &SharedDriver = paint_callback_resources.get().unwrap();
+      let mut gpu_driver = shared_driver.lock().unwrap();
 
       let mut encoder =
         device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
-      if compute_requested {
-        gpu_driver.simulation_advance(&mut encoder);
-      }
+      // also while paused, to flush the last rows of a run
+      gpu_driver.probe_readback(&mut encoder);
+      gpu_driver.follow_pass(&mut encoder);
 
       gpu_driver.prepare(
         device,
@@ -339,9 +608,19 @@ pub fn egui_wgpu_callback(
         &bounds,
       );
 
+      gpu_driver.profile_begin(&mut encoder, ProfiledPass::Render);
       gpu_driver.render(&mut encoder);
+      gpu_driver.render_views(device, queue, &mut encoder);
+      gpu_driver.profile_end(&mut encoder, ProfiledPass::Render, 0, 0);
+      gpu_driver.profile_resolve(&mut encoder);
+
+      // Submitted here rather than handed to egui, so that readbacks encoded
+      // above can be mapped right away.
+      queue.submit(std::iter::once(encoder.finish()));
+      gpu_driver.after_submit();
+      gpu_driver.profile_submitted(queue);
 
-      vec![encoder.finish()]
+      vec![]
     });
 
   egui::PaintCallback {
@@ -0,0 +1,224 @@
+use {
+  eframe::egui::{Key, KeyboardShortcut, Modifiers},
+  crate::{
+    gpu::GPUDriver,
+    pattern::Transform,
+  },
+  super::gui_annotation::Tool,
+};
+
+/// Everything the viewer can do from a shortcut or the command palette.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(in super) enum Action {
+  StartStop,
+  Step,
+  StepBack,
+  Reset,
+  Recompile,
+  Copy,
+  Paste,
+  Cancel,
+  RotateCW,
+  RotateCCW,
+  FlipH,
+  FlipV,
+  Undo,
+  Redo,
+  SaveSnapshot,
+  LoadSnapshot,
+  ExportView,
+  ExportSelection,
+  RecordView,
+  StopRecording,
+  FollowCursor,
+  FollowSelection,
+  StopFollowing,
+  TextTool,
+  ArrowTool,
+  RectTool,
+  NoTool,
+  NewViewWindow,
+  PaletteEditor,
+  Assembler,
+  Disassembler,
+  Keybindings,
+  CommandPalette,
+}
+
+const CTRL_SHIFT: Modifiers = Modifiers { shift: true, ..Modifiers::COMMAND };
+
+impl Action {
+  pub(in super) const ALL: [Action; 33] = [
+    Action::StartStop, Action::Step, Action::StepBack, Action::Reset, Action::Recompile,
+    Action::Copy, Action::Paste, Action::Cancel,
+    Action::RotateCW, Action::RotateCCW, Action::FlipH, Action::FlipV,
+    Action::Undo, Action::Redo,
+    Action::SaveSnapshot, Action::LoadSnapshot,
+    Action::ExportView, Action::ExportSelection, Action::RecordView, Action::StopRecording,
+    Action::FollowCursor, Action::FollowSelection, Action::StopFollowing,
+    Action::TextTool, Action::ArrowTool, Action::RectTool, Action::NoTool,
+    Action::NewViewWindow, Action::PaletteEditor, Action::Assembler, Action::Disassembler, Action::Keybindings, Action::CommandPalette,
+  ];
+
+  /// Stable name the bindings are stored under.
+  pub(in super) fn id(&self) -> &'static str {
+    match self {
+      Action::StartStop => "start_stop",
+      Action::Step => "step",
+      Action::StepBack => "step_back",
+      Action::Reset => "reset",
+      Action::Recompile => "recompile",
+      Action::Copy => "copy",
+      Action::Paste => "paste",
+      Action::Cancel => "cancel",
+      Action::RotateCW => "rotate_cw",
+      Action::RotateCCW => "rotate_ccw",
+      Action::FlipH => "flip_h",
+      Action::FlipV => "flip_v",
+      Action::Undo => "undo",
+      Action::Redo => "redo",
+      Action::SaveSnapshot => "save_snapshot",
+      Action::LoadSnapshot => "load_snapshot",
+      Action::ExportView => "export_view",
+      Action::ExportSelection => "export_selection",
+      Action::RecordView => "record_view",
+      Action::StopRecording => "stop_recording",
+      Action::FollowCursor => "follow_cursor",
+      Action::FollowSelection => "follow_selection",
+      Action::StopFollowing => "stop_following",
+      Action::TextTool => "text_tool",
+      Action::ArrowTool => "arrow_tool",
+      Action::RectTool => "rect_tool",
+      Action::NoTool => "no_tool",
+      Action::NewViewWindow => "new_view_window",
+      Action::PaletteEditor => "palette_editor",
+      Action::Assembler => "assembler",
+      Action::Disassembler => "disassembler",
+      Action::Keybindings => "keybindings",
+      Action::CommandPalette => "command_palette",
+    }
+  }
+
+  pub(in super) fn name(&self) -> &'static str {
+    match self {
+      Action::StartStop => "Start / stop",
+      Action::Step => "Step",
+      Action::StepBack => "Step back",
+      Action::Reset => "Reset",
+      Action::Recompile => "Recompile",
+      Action::Copy => "Copy selection",
+      Action::Paste => "Paste",
+      Action::Cancel => "Cancel paste / clear selection",
+      Action::RotateCW => "Rotate clockwise",
+      Action::RotateCCW => "Rotate counter-clockwise",
+      Action::FlipH => "Flip horizontally",
+      Action::FlipV => "Flip vertically",
+      Action::Undo => "Undo",
+      Action::Redo => "Redo",
+      Action::SaveSnapshot => "Save snapshot",
+      Action::LoadSnapshot => "Open snapshot",
+      Action::ExportView => "Export view as image",
+      Action::ExportSelection => "Export selection as image",
+      Action::RecordView => "Record view",
+      Action::StopRecording => "Stop recording",
+      Action::FollowCursor => "Follow cells around the cursor",
+      Action::FollowSelection => "Follow selection",
+      Action::StopFollowing => "Stop following",
+      Action::TextTool => "Annotate: text",
+      Action::ArrowTool => "Annotate: arrow",
+      Action::RectTool => "Annotate: rectangle",
+      Action::NoTool => "Annotate: off",
+      Action::NewViewWindow => "New view window",
+      Action::PaletteEditor => "Palette editor",
+      Action::Assembler => "Construction assembler",
+      Action::Disassembler => "Tape disassembler",
+      Action::Keybindings => "Edit keybindings",
+      Action::CommandPalette => "Command palette",
+    }
+  }
+
+  pub(in super) fn default_shortcuts(&self) -> &'static [KeyboardShortcut] {
+    match self {
+      Action::StartStop => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::Space }],
+      Action::Step => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::S }],
+      Action::StepBack => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::B }],
+      Action::Reset => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::R }],
+      Action::Recompile => &[KeyboardShortcut { modifiers: Modifiers::COMMAND, key: Key::R }],
+      Action::Copy => &[KeyboardShortcut { modifiers: Modifiers::COMMAND, key: Key::C }],
+      Action::Paste => &[KeyboardShortcut { modifiers: Modifiers::COMMAND, key: Key::V }],
+      Action::Cancel => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::Escape }],
+      Action::RotateCW => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::E }],
+      Action::RotateCCW => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::Q }],
+      Action::FlipH => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::X }],
+      Action::FlipV => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::Y }],
+      Action::Undo => &[KeyboardShortcut { modifiers: Modifiers::COMMAND, key: Key::Z }],
+      Action::Redo => &[
+        KeyboardShortcut { modifiers: Modifiers::COMMAND, key: Key::Y },
+        KeyboardShortcut { modifiers: CTRL_SHIFT, key: Key::Z },
+      ],
+      Action::SaveSnapshot => &[KeyboardShortcut { modifiers: Modifiers::COMMAND, key: Key::S }],
+      Action::LoadSnapshot => &[KeyboardShortcut { modifiers: Modifiers::COMMAND, key: Key::O }],
+      Action::ExportView => &[KeyboardShortcut { modifiers: Modifiers::COMMAND, key: Key::E }],
+      Action::FollowCursor => &[KeyboardShortcut { modifiers: Modifiers::NONE, key: Key::F }],
+      Action::CommandPalette => &[KeyboardShortcut { modifiers: Modifiers::COMMAND, key: Key::P }],
+      _ => &[],
+    }
+  }
+}
+
+impl super::GUI {
+  pub(in super) fn run_action(
+    &mut self,
+    action: Action,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    target_format: wgpu::TextureFormat
+  ) {
+    let simulation_dimm = gpu_driver.uniforms.simulation_dimm;
+    let selection = self.edit.selection.and_then(|s| s.clip(simulation_dimm));
+    match action {
+      Action::StartStop => self.on_start_click(gpu_driver, device, queue),
+      Action::Step => self.on_step_click(gpu_driver, device, queue),
+      Action::StepBack => self.on_step_back_click(gpu_driver, device, queue),
+      Action::Reset => self.on_reset_click(gpu_driver, device, queue),
+      Action::Recompile => self.on_recomple_click(gpu_driver, device, queue, target_format),
+      Action::Copy => self.on_copy_click(gpu_driver, device, queue),
+      Action::Paste => self.on_paste_click(),
+      Action::Cancel => self.on_cancel_click(),
+      Action::RotateCW => self.on_transform_click(Transform::RotateCW, gpu_driver, device, queue),
+      Action::RotateCCW => self.on_transform_click(Transform::RotateCCW, gpu_driver, device, queue),
+      Action::FlipH => self.on_transform_click(Transform::FlipH, gpu_driver, device, queue),
+      Action::FlipV => self.on_transform_click(Transform::FlipV, gpu_driver, device, queue),
+      Action::Undo => self.on_undo_click(gpu_driver, device, queue),
+      Action::Redo => self.on_redo_click(gpu_driver, device, queue),
+      Action::SaveSnapshot => self.on_save_snapshot_click(gpu_driver, device, queue),
+      Action::LoadSnapshot => self.on_load_snapshot_click(gpu_driver, device, queue),
+      Action::ExportView => if let Some(rect) = self.view_rect(simulation_dimm) {
+        self.on_export_image_click(gpu_driver, device, queue, rect);
+      },
+      Action::ExportSelection => if let Some(rect) = selection {
+        self.on_export_image_click(gpu_driver, device, queue, rect);
+      },
+      Action::RecordView => if let Some(rect) = self.view_rect(simulation_dimm) {
+        self.on_start_recording_click(gpu_driver, rect);
+      },
+      Action::StopRecording => self.on_stop_recording_click(),
+      Action::FollowCursor => self.follow.cursor_requested = true,
+      Action::FollowSelection => if let Some(rect) = selection {
+        self.start_following(rect);
+      },
+      Action::StopFollowing => self.stop_following(),
+      Action::TextTool => self.annotation.set_tool(Tool::Text),
+      Action::ArrowTool => self.annotation.set_tool(Tool::Arrow),
+      Action::RectTool => self.annotation.set_tool(Tool::Rect),
+      Action::NoTool => self.annotation.set_tool(Tool::None),
+      Action::NewViewWindow => self.on_new_viewport_click(gpu_driver, device),
+      Action::PaletteEditor => self.palette.show_editor ^= true,
+      Action::Assembler => self.assembler.show ^= true,
+      Action::Disassembler => self.disassembler.show ^= true,
+      Action::Keybindings => self.keymap.show_editor ^= true,
+      Action::CommandPalette => self.command_palette.open(),
+    }
+  }
+}
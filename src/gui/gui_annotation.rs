@@ -0,0 +1,189 @@
+use {
+  std::fs,
+  eframe::egui::{
+    self,
+    plot::{Arrows, PlotPoint, PlotPoints, PlotUi, Points, Polygon, Text},
+    Align2, Color32, RichText,
+  },
+  crate::{
+    annotation::{self, Annotation},
+    pattern::CellRect,
+  },
+  super::gui_edit::plot_to_cell,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(in super) enum Tool {
+  None,
+  Text,
+  Arrow,
+  Rect,
+}
+
+pub(in super) struct AnnotationState {
+  pub(in super) items: Vec<Annotation>,
+  pub(in super) edit_path: String,
+  /// Annotations are drawn over exported images and recordings.
+  pub(in super) in_exports: bool,
+  tool: Tool,
+  /// First corner or tail, placed with the first click of a two-click tool.
+  anchor: Option<[i64; 2]>,
+  edit_text: String,
+  color: [u8; 3],
+  status: String,
+}
+
+impl Default for AnnotationState {
+  fn default() -> Self {
+    Self {
+      items: vec![],
+      edit_path: "./annotations.txt".to_string(),
+      in_exports: true,
+      tool: Tool::None,
+      anchor: None,
+      edit_text: String::new(),
+      color: [255, 204, 0],
+      status: String::new(),
+    }}}
+
+impl AnnotationState {
+  pub(in super) fn set_tool(&mut self, tool: Tool) {
+    self.tool = tool;
+    self.anchor = None;
+  }
+}
+
+fn color32([r, g, b]: [u8; 3]) -> Color32 {
+  Color32::from_rgb(r, g, b)
+}
+
+fn cell_center([x, y]: [i64; 2]) -> [f64; 2] {
+  [x as f64 + 0.5, -(y as f64) - 0.5]
+}
+
+impl super::GUI {
+  fn on_load_annotations_click(&mut self) {
+    let path = &self.annotation.edit_path;
+    match fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|text| annotation::parse(&text)) {
+      Ok(items) => {
+        self.annotation.status = format!("loaded {} annotations", items.len());
+        self.annotation.items = items;
+      },
+      Err(e) => self.annotation.status = format!("load failed: {e}"),
+    }
+  }
+
+  fn on_save_annotations_click(&mut self) {
+    let path = &self.annotation.edit_path;
+    self.annotation.status = fs::write(path, annotation::to_text(&self.annotation.items))
+      .map(|_| format!("saved {} annotations", self.annotation.items.len()))
+      .unwrap_or_else(|e| format!("save failed: {e}"));
+  }
+
+  fn on_copy_rle_comments_click(&mut self, ui: &egui::Ui) {
+    ui.output().copied_text = annotation::to_rle_comments(&self.annotation.items);
+    self.annotation.status = format!("copied {} annotations as #C lines", self.annotation.items.len());
+  }
+
+  pub(in super) fn annotation_panel_ui(&mut self, ui: &mut egui::Ui) {
+    let state = &mut self.annotation;
+    ui.horizontal_wrapped(|ui| {
+      for (tool, label) in [(Tool::None, "off"), (Tool::Text, "text"), (Tool::Arrow, "arrow"), (Tool::Rect, "rectangle")] {
+        ui.radio_value(&mut state.tool, tool, label)
+          .changed().then(|| state.anchor = None);
+      }
+    });
+    ui.label(match state.tool {
+      Tool::None => "pick a tool, then click in the view",
+      Tool::Text => "click to place the text",
+      Tool::Arrow => "click the tail, then the tip",
+      Tool::Rect => "click two opposite corners",
+    });
+    ui.horizontal_wrapped(|ui| {
+      ui.label("text: ");
+      ui.text_edit_singleline(&mut state.edit_text);
+    });
+    ui.horizontal_wrapped(|ui| {
+      ui.label("colour: ");
+      ui.color_edit_button_srgb(&mut state.color);
+    });
+
+    let mut removed = None;
+    for (i, item) in state.items.iter().enumerate() {
+      ui.horizontal(|ui| {
+        ui.small_button("✖").clicked().then(|| removed = Some(i));
+        ui.label(RichText::new(item.to_line()).color(color32(item.color())));
+      });
+    }
+    if let Some(i) = removed {
+      state.items.remove(i);
+    }
+
+    ui.checkbox(&mut state.in_exports, "draw in exports");
+    ui.horizontal_wrapped(|ui| {
+      ui.label("path: ");
+      ui.text_edit_singleline(&mut state.edit_path);
+    });
+    ui.horizontal_wrapped(|ui| {
+      ui.button("Load")
+        .clicked().then(|| self.on_load_annotations_click());
+      ui.button("Save")
+        .on_hover_text_at_pointer("one per line; #C lines of an RLE file load as well")
+        .clicked().then(|| self.on_save_annotations_click());
+      ui.add_enabled(!self.annotation.items.is_empty(), egui::Button::new("Copy as #C"))
+        .on_hover_text_at_pointer("for the comments of an RLE file")
+        .clicked().then(|| self.on_copy_rle_comments_click(ui));
+      ui.button("Clear")
+        .clicked().then(|| self.annotation.items.clear());
+    });
+    if !self.annotation.status.is_empty() {
+      ui.label(&self.annotation.status);
+    }
+  }
+
+  /// Annotations over the universe, and placement with the active tool.
+  pub(in super) fn annotation_plot_ui(&mut self, ui: &mut PlotUi) {
+    let state = &mut self.annotation;
+    let clicked_cell = (ui.plot_clicked() && !self.edit.paste_preview)
+      .then(|| ui.pointer_coordinate().map(plot_to_cell))
+      .flatten();
+    if let (Some(cell), false) = (clicked_cell, state.tool == Tool::None) {
+      let color = state.color;
+      match (state.tool, state.anchor.take()) {
+        (Tool::Text, _) if !state.edit_text.is_empty() =>
+          state.items.push(Annotation::Text { at: cell, text: state.edit_text.clone(), color }),
+        (Tool::Arrow, Some(from)) if from != cell =>
+          state.items.push(Annotation::Arrow { from, to: cell, color }),
+        (Tool::Rect, Some(corner)) =>
+          state.items.push(Annotation::Rect { rect: CellRect::from_corners(corner, cell), color }),
+        (Tool::Arrow | Tool::Rect, None) => state.anchor = Some(cell),
+        _ => {},
+      }
+    }
+
+    for item in &state.items {
+      let color = color32(item.color());
+      match item {
+        Annotation::Text { at: [x, y], text, .. } => ui.text(
+          Text::new(PlotPoint::new(*x as f64, -(*y as f64)), RichText::new(text).color(color).strong())
+            .anchor(Align2::LEFT_TOP)
+            .name("Annotations")
+        ),
+        Annotation::Arrow { from, to, .. } => ui.arrows(
+          Arrows::new(PlotPoints::from(vec![cell_center(*from)]), PlotPoints::from(vec![cell_center(*to)]))
+            .color(color)
+            .name("Annotations")
+        ),
+        Annotation::Rect { rect, .. } => ui.polygon(
+          Polygon::new(PlotPoints::from(rect.plot_corners().to_vec()))
+            .color(color)
+            .fill_alpha(0.0)
+            .name("Annotations")
+        ),
+      }
+    }
+    if let Some(anchor) = state.anchor {
+      ui.points(Points::new(PlotPoints::from(vec![cell_center(anchor)])).radius(4.0).color(color32(state.color)));
+    }
+  }
+}
@@ -0,0 +1,139 @@
+use {
+  eframe::egui::{self, Color32, RichText, TextStyle},
+  crate::construction::{self, Instruction},
+};
+
+/// What the tape loop is generated from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(in super) enum LoopSource {
+  Program,
+  Bits,
+}
+
+pub(in super) struct AssemblerState {
+  pub(in super) show: bool,
+  pub(in super) source: String,
+  /// Assembled `source`, or why it failed.
+  output: Result<Vec<bool>, String>,
+  /// `source` the output was assembled from.
+  assembled: Option<String>,
+  loop_source: LoopSource,
+  pub(in super) edit_loop_bits: String,
+  /// OTS cells between the loop and the arm tip.
+  loop_arm: u32,
+  status: String,
+}
+
+impl Default for AssemblerState {
+  fn default() -> Self {
+    Self {
+      show: false,
+      source: "FWD 5; LEFT; WRITE_OTS_FWD; RETRACT".to_string(),
+      output: Ok(vec![]),
+      assembled: None,
+      loop_source: LoopSource::Program,
+      edit_loop_bits: String::new(),
+      loop_arm: 4,
+      status: String::new(),
+    }}}
+
+impl AssemblerState {
+  pub(in super) fn bits(&mut self) -> Result<&[bool], &str> {
+    if self.assembled.as_ref() != Some(&self.source) {
+      self.output = construction::assemble(&self.source);
+      self.assembled = Some(self.source.clone());
+    }
+    self.output.as_deref().map_err(String::as_str)
+  }
+}
+
+impl super::GUI {
+  /// The tape follows the pointer, to be placed so that its east end feeds
+  /// an arm; rotate it for arms pointing elsewhere.
+  fn on_paste_tape_click(&mut self, bits: &[bool]) {
+    self.edit.start_paste(construction::tape_pattern(bits));
+  }
+
+  fn on_paste_loop_click(&mut self) {
+    let bits = match self.assembler.loop_source {
+      LoopSource::Program => self.assembler.bits().map(<[bool]>::to_vec).map_err(str::to_string),
+      LoopSource::Bits => construction::parse_bits(&self.assembler.edit_loop_bits),
+    };
+    self.assembler.status = match bits {
+      Ok(bits) if bits.is_empty() => "no bits to store".to_string(),
+      Ok(bits) => {
+        let tape_loop = construction::tape_loop(&bits, self.assembler.loop_arm);
+        let [x, y] = tape_loop.tap;
+        let status = format!(
+          "{}x{} loop, period {} ({} bits of padding), tap at ({x}, {y})",
+          tape_loop.pattern.width - self.assembler.loop_arm, tape_loop.pattern.height,
+          tape_loop.period, tape_loop.period - bits.len()
+        );
+        self.edit.start_paste(tape_loop.pattern);
+        status
+      },
+      Err(e) => e,
+    };
+  }
+
+  pub(in super) fn assembler_window(&mut self, ctx: &egui::Context) {
+    let mut show = self.assembler.show;
+    egui::Window::new("📼 Construction assembler")
+      .open(&mut show)
+      .default_width(360.0)
+      .vscroll(true)
+      .show(ctx, |ui| {
+        ui.label("statements split by ; or lines, # comments, optional repeat counts")
+          .on_hover_text(Instruction::ALL.iter()
+            .map(|i| format!("{:<16} {}", i.mnemonic(), i.bits()))
+            .chain(["BITS 0101        raw bits".to_string()])
+            .collect::<Vec<_>>()
+            .join("\n"));
+        ui.add(egui::TextEdit::multiline(&mut self.assembler.source)
+          .code_editor()
+          .desired_rows(6)
+          .desired_width(f32::INFINITY));
+
+        match self.assembler.bits().map(<[bool]>::to_vec) {
+          Ok(bits) => {
+            let text = construction::bits_to_string(&bits);
+            ui.label(format!("{} bits, first bit first:", bits.len()));
+            ui.add(egui::Label::new(RichText::new(&text).text_style(TextStyle::Monospace)).wrap(true));
+            ui.horizontal_wrapped(|ui| {
+              ui.add_enabled(!bits.is_empty(), egui::Button::new("Paste as tape"))
+                .on_hover_text("a line of OTS pointing east, excited for 1, first bit on the east end")
+                .clicked().then(|| self.on_paste_tape_click(&bits));
+              ui.add_enabled(!bits.is_empty(), egui::Button::new("Copy bits"))
+                .clicked().then(|| ui.output().copied_text = text.clone());
+            });
+          },
+          Err(e) => {
+            ui.label(RichText::new(e).color(Color32::RED));
+          },
+        }
+
+        ui.separator();
+        ui.label("Repeater-emitter loop");
+        ui.horizontal_wrapped(|ui| {
+          ui.radio_value(&mut self.assembler.loop_source, LoopSource::Program, "program");
+          ui.radio_value(&mut self.assembler.loop_source, LoopSource::Bits, "bits");
+        });
+        if self.assembler.loop_source == LoopSource::Bits {
+          ui.add(egui::TextEdit::singleline(&mut self.assembler.edit_loop_bits)
+            .hint_text("e.g. 100000100010")
+            .desired_width(f32::INFINITY));
+        }
+        ui.horizontal_wrapped(|ui| {
+          ui.add(egui::DragValue::new(&mut self.assembler.loop_arm).clamp_range(1..=256).prefix("arm "));
+          ui.button("Paste as loop")
+            .on_hover_text("an anticlockwise OTS loop holding the tape, emitting it forever \
+              through a confluent on its east side into an arm pointing east")
+            .clicked().then(|| self.on_paste_loop_click());
+        });
+        if !self.assembler.status.is_empty() {
+          ui.label(&self.assembler.status);
+        }
+      });
+    self.assembler.show = show;
+  }
+}
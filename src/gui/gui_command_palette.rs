@@ -0,0 +1,106 @@
+use {
+  eframe::egui::{self, Align2, Key, RichText},
+  super::gui_actions::Action,
+};
+
+#[derive(Default)]
+pub(in super) struct CommandPalette {
+  open: bool,
+  query: String,
+  /// Index of the highlighted match.
+  selected: usize,
+}
+
+impl CommandPalette {
+  pub(in super) fn open(&mut self) {
+    *self = Self { open: true, ..Self::default() };
+  }
+}
+
+/// Score of `query` as a case-insensitive subsequence of `name`, higher for
+/// consecutive characters and word starts; `None` if it is not one.
+fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+  let name: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+  let mut score = 0;
+  let mut next = 0;
+  let mut previous_match = None;
+  for q in query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()) {
+    let i = next + name[next..].iter().position(|&c| c == q)?;
+    score += 1;
+    if previous_match.is_some_and(|p| p + 1 == i) {
+      score += 4;
+    }
+    if i == 0 || !name[i - 1].is_alphanumeric() {
+      score += 3;
+    }
+    score -= (i - next).min(8) as i32 / 2;
+    previous_match = Some(i);
+    next = i + 1;
+  }
+  Some(score)
+}
+
+impl super::GUI {
+  /// Actions matching the query, best first, in registry order when empty.
+  fn command_matches(&self) -> Vec<Action> {
+    let mut matches: Vec<_> = Action::ALL.into_iter()
+      .filter(|action| *action != Action::CommandPalette)
+      .filter_map(|action| Some((fuzzy_score(&self.command_palette.query, action.name())?, action)))
+      .collect();
+    matches.sort_by_key(|(score, _)| -score);
+    matches.into_iter().map(|(_, action)| action).collect()
+  }
+
+  /// Search box over every action. Up / Down highlight, Enter or a click
+  /// runs, Esc closes. Returns the action to run.
+  pub(in super) fn command_palette_window(&mut self, ctx: &egui::Context) -> Option<Action> {
+    if !self.command_palette.open {
+      return None;
+    }
+    let matches = self.command_matches();
+    let (up, down, enter, escape) = {
+      let input = ctx.input();
+      (input.key_pressed(Key::ArrowUp), input.key_pressed(Key::ArrowDown), input.key_pressed(Key::Enter), input.key_pressed(Key::Escape))
+    };
+    let palette = &mut self.command_palette;
+    palette.selected = match (up, down) {
+      (true, false) => palette.selected.saturating_sub(1),
+      (false, true) => palette.selected + 1,
+      _ => palette.selected,
+    }.min(matches.len().saturating_sub(1));
+
+    let mut run = (enter && !matches.is_empty()).then(|| matches[palette.selected]);
+    egui::Window::new("Command palette")
+      .title_bar(false)
+      .collapsible(false)
+      .resizable(false)
+      .anchor(Align2::CENTER_TOP, [0.0, 40.0])
+      .show(ctx, |ui| {
+        ui.set_width(320.0);
+        let edit = ui.add(egui::TextEdit::singleline(&mut palette.query).hint_text("type to search actions"));
+        edit.changed().then(|| palette.selected = 0);
+        edit.request_focus();
+        ui.separator();
+        egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+          for (i, action) in matches.iter().enumerate() {
+            ui.horizontal(|ui| {
+              let item = ui.selectable_label(i == palette.selected, action.name());
+              if i == palette.selected && (up || down) {
+                item.scroll_to_me(None);
+              }
+              item.clicked().then(|| run = Some(*action));
+              ui.label(RichText::new(self.keymap.keymap.hint(*action)).weak());
+            });
+          }
+          if matches.is_empty() {
+            ui.label("no matching action");
+          }
+        });
+      });
+
+    if run.is_some() || escape {
+      self.command_palette.open = false;
+    }
+    run
+  }
+}
@@ -0,0 +1,249 @@
+use {
+  eframe::egui::{
+    self,
+    plot::{PlotPoints, PlotUi, Polygon},
+    Color32, RichText,
+  },
+  crate::{
+    construction::{self, Prediction, Wire},
+    gpu::GPUDriver,
+    pattern::{CellRect, Pattern},
+  },
+};
+
+/// A wire read from the universe, with what its arm will build.
+struct ReadWire {
+  wire: Wire,
+  /// Universe around the wire when it was read.
+  pattern: Pattern,
+  origin: [i64; 2],
+  /// Generation the wire was read at.
+  generation: u64,
+  source: String,
+  prediction: Prediction,
+}
+
+pub(in super) struct DisassemblerState {
+  pub(in super) show: bool,
+  read: Option<ReadWire>,
+  /// Circuits of a loop the prediction covers.
+  circuits: usize,
+  show_footprint: bool,
+  /// The footprint is checked once the driver reaches this generation.
+  check_at: Option<u64>,
+  mismatches: Vec<[i64; 2]>,
+  status: String,
+}
+
+impl Default for DisassemblerState {
+  fn default() -> Self {
+    Self {
+      show: false,
+      read: None,
+      circuits: 1,
+      show_footprint: true,
+      check_at: None,
+      mismatches: vec![],
+      status: String::new(),
+    }}}
+
+/// The program of `circuits` circuits, and what it builds; cells outside
+/// `pattern` are assumed to be ground.
+fn predict(wire: &Wire, pattern: &Pattern, [ox, oy]: [i64; 2], circuits: usize) -> (String, Prediction) {
+  let train = wire.train(circuits);
+  let prediction = construction::predict(wire, &train, |[x, y]| {
+    let (px, py) = (x - ox, y - oy);
+    match px >= 0 && py >= 0 && px < pattern.width as i64 && py < pattern.height as i64 {
+      true => pattern.get(px as u32, py as u32),
+      false => 0,
+    }
+  });
+  (construction::to_source(&construction::disassemble(&train)), prediction)
+}
+
+impl ReadWire {
+  fn repredict(&mut self, circuits: usize) {
+    (self.source, self.prediction) = predict(&self.wire, &self.pattern, self.origin, circuits);
+  }
+}
+
+impl super::GUI {
+  fn on_read_wire_click(&mut self, gpu_driver: &GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let Some(selection) = self.edit.selection.and_then(|s| s.clip(gpu_driver.uniforms.simulation_dimm)) else {
+      return;
+    };
+    let pattern = gpu_driver.read_pattern(device, queue, selection);
+    let origin = [selection.x, selection.y];
+    let state = &mut self.disassembler;
+    state.mismatches.clear();
+    state.check_at = None;
+    match construction::trace_wire(&pattern, origin) {
+      Ok(wire) => {
+        state.status = format!(
+          "{} cells, {} bits{}",
+          wire.cells.len(), wire.bits.len(),
+          if wire.loop_start.is_some() { ", closed into a loop" } else { "" }
+        );
+        let (source, prediction) = predict(&wire, &pattern, origin, state.circuits);
+        state.read = Some(ReadWire { wire, pattern, origin, generation: gpu_driver.generation, source, prediction });
+      },
+      Err(e) => {
+        state.read = None;
+        state.status = e;
+      },
+    }
+  }
+
+  fn on_run_until_built_click(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let Some(read) = &self.disassembler.read else {
+      return;
+    };
+    let target = read.generation + read.prediction.generations;
+    if gpu_driver.generation != read.generation {
+      self.disassembler.status = format!("read at generation {}, read the wire again to run from here", read.generation);
+      return;
+    }
+    self.disassembler.check_at = Some(target);
+    self.run_until(target, gpu_driver, device, queue);
+  }
+
+  /// Compares the footprint with the universe, excitation aside.
+  fn on_check_footprint_click(&mut self, gpu_driver: &GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let Some(read) = &self.disassembler.read else {
+      return;
+    };
+    let cells = &read.prediction.cells;
+    let (Some(first), Some(last)) = (cells.keys().map(|c| c[0]).min(), cells.keys().map(|c| c[0]).max()) else {
+      self.disassembler.status = "nothing to check, the arm builds nothing".to_string();
+      return;
+    };
+    let (top, bottom) = (cells.keys().map(|c| c[1]).min().unwrap(), cells.keys().map(|c| c[1]).max().unwrap());
+    let bbox = CellRect::from_corners([first, top], [last, bottom]);
+    let Some(clipped) = bbox.clip(gpu_driver.uniforms.simulation_dimm) else {
+      self.disassembler.status = "the footprint lies outside the universe".to_string();
+      return;
+    };
+    let actual = gpu_driver.read_pattern(device, queue, clipped);
+    let actual_at = |[x, y]: [i64; 2]| {
+      let (px, py) = (x - clipped.x, y - clipped.y);
+      (px >= 0 && py >= 0 && px < clipped.width as i64 && py < clipped.height as i64)
+        .then(|| actual.get(px as u32, py as u32))
+    };
+    let mismatches: Vec<[i64; 2]> = cells.iter()
+      .filter(|(&cell, &state)| actual_at(cell).map(construction::quiescent) != Some(state))
+      .map(|(&cell, _)| cell)
+      .collect();
+    self.disassembler.status = match mismatches.len() {
+      0 => format!("all {} cells match at generation {}", cells.len(), gpu_driver.generation),
+      n => format!("{n} of {} cells differ at generation {}", cells.len(), gpu_driver.generation),
+    };
+    self.disassembler.mismatches = mismatches;
+  }
+
+  fn on_edit_program_click(&mut self) {
+    if let Some(read) = &self.disassembler.read {
+      self.assembler.source = read.source.clone();
+      self.assembler.show = true;
+    }
+  }
+
+  /// Checks the footprint once a run started from the window has stopped
+  /// at the generation the arm should be done by.
+  pub(in super) fn disassembler_control(&mut self, gpu_driver: &GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if let Some(check_at) = self.disassembler.check_at {
+      if gpu_driver.generation >= check_at && !self.compute_requested {
+        self.disassembler.check_at = None;
+        self.on_check_footprint_click(gpu_driver, device, queue);
+      }
+    }
+  }
+
+  pub(in super) fn disassembler_window(
+    &mut self,
+    ctx: &egui::Context,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) {
+    let mut show = self.disassembler.show;
+    egui::Window::new("📼 Tape disassembler")
+      .open(&mut show)
+      .default_width(360.0)
+      .vscroll(true)
+      .show(ctx, |ui| {
+        ui.horizontal_wrapped(|ui| {
+          ui.add_enabled(self.edit.selection.is_some(), egui::Button::new("Read selection"))
+            .on_hover_text("trace the wire back from its tip, an OTS pointing at ground, \
+              through the loop it is fed from if any")
+            .clicked().then(|| self.on_read_wire_click(gpu_driver, device, queue));
+          let is_loop = self.disassembler.read.as_ref().is_some_and(|read| read.wire.loop_start.is_some());
+          if is_loop {
+            let circuits = self.disassembler.circuits;
+            ui.add(egui::DragValue::new(&mut self.disassembler.circuits).clamp_range(1..=64).suffix(" circuits"));
+            if circuits != self.disassembler.circuits {
+              if let Some(read) = &mut self.disassembler.read {
+                read.repredict(self.disassembler.circuits);
+              }
+              self.disassembler.mismatches.clear();
+            }
+          }
+        });
+        if !self.disassembler.status.is_empty() {
+          ui.label(&self.disassembler.status);
+        }
+        let Some(read) = &self.disassembler.read else {
+          return;
+        };
+
+        ui.label(format!("bits reaching the tip: {}", construction::bits_to_string(&read.wire.bits)));
+        let mut source = read.source.as_str();
+        ui.add(egui::TextEdit::multiline(&mut source)
+          .code_editor()
+          .desired_rows(6)
+          .desired_width(f32::INFINITY));
+
+        let prediction = &read.prediction;
+        ui.label(format!(
+          "{} instructions build {} cells, done by generation {}",
+          prediction.instructions, prediction.cells.len(), read.generation + prediction.generations
+        ));
+        if let Some(error) = &prediction.error {
+          ui.label(RichText::new(error).color(Color32::RED));
+        }
+        ui.checkbox(&mut self.disassembler.show_footprint, "show footprint");
+        ui.horizontal_wrapped(|ui| {
+          ui.button("Run until built")
+            .on_hover_text("run to the generation the last instruction takes effect, then check")
+            .clicked().then(|| self.on_run_until_built_click(gpu_driver, device, queue));
+          ui.button("Check now")
+            .clicked().then(|| self.on_check_footprint_click(gpu_driver, device, queue));
+          ui.button("Edit in assembler")
+            .clicked().then(|| self.on_edit_program_click());
+        });
+      });
+    self.disassembler.show = show;
+  }
+
+  /// Predicted footprint, and the cells the simulation disagrees on.
+  pub(in super) fn disassembler_plot_ui(&self, ui: &mut PlotUi) {
+    let cell_polygon = |[x, y]: [i64; 2]| PlotPoints::from(CellRect { x, y, width: 1, height: 1 }.plot_corners().to_vec());
+    if let (true, Some(read)) = (self.disassembler.show_footprint, &self.disassembler.read) {
+      for &cell in read.prediction.cells.keys() {
+        ui.polygon(
+          Polygon::new(cell_polygon(cell))
+            .color(Color32::from_rgb(0, 200, 255))
+            .fill_alpha(0.0)
+            .name("Predicted footprint")
+        );
+      }
+    }
+    for &cell in &self.disassembler.mismatches {
+      ui.polygon(
+        Polygon::new(cell_polygon(cell))
+          .color(Color32::RED)
+          .fill_alpha(0.5)
+          .name("Footprint mismatch")
+      );
+    }
+  }
+}
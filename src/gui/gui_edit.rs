@@ -0,0 +1,217 @@
+use {
+  eframe::egui::{
+    self,
+    plot::{Line, PlotImage, PlotPoint, PlotPoints, PlotUi, Polygon},
+    Color32, TextureHandle, TextureOptions,
+  },
+  crate::{
+    gpu::GPUDriver,
+    pattern::{CellRect, Pattern, Transform},
+    rule::Rule,
+  },
+  super::gui_actions::Action,
+};
+
+/// Selection and clipboard of the viewer.
+#[derive(Default)]
+pub(in super) struct EditState {
+  pub selection: Option<CellRect>,
+  selection_anchor: Option<[i64; 2]>,
+  clipboard: Option<Pattern>,
+  /// The clipboard follows the pointer until placed with LMB.
+  pub paste_preview: bool,
+  preview_texture: Option<TextureHandle>,
+  /// Why the last transform was refused.
+  status: String,
+}
+
+impl EditState {
+  fn set_clipboard(&mut self, pattern: Pattern) {
+    self.clipboard = (pattern.width > 0 && pattern.height > 0).then_some(pattern);
+    self.preview_texture = None;
+  }
+
+  /// `pattern` follows the pointer until placed, as if copied.
+  pub fn start_paste(&mut self, pattern: Pattern) {
+    self.set_clipboard(pattern);
+    self.paste_preview = self.clipboard.is_some();
+  }
+
+  /// The preview is drawn again, e.g. with a new palette.
+  pub fn invalidate_preview(&mut self) {
+    self.preview_texture = None;
+  }
+}
+
+pub(in super) fn plot_to_cell(pt: PlotPoint) -> [i64; 2] {
+  [pt.x.floor() as i64, (-pt.y).floor() as i64]
+}
+
+impl super::GUI {
+  pub(in super) fn on_copy_click(&mut self, gpu_driver: &GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if let Some(selection) = self.edit.selection {
+      self.edit.set_clipboard(gpu_driver.read_pattern(device, queue, selection));
+    }
+  }
+
+  pub(in super) fn on_paste_click(&mut self) {
+    self.edit.paste_preview = self.edit.clipboard.is_some();
+  }
+
+  pub(in super) fn on_cancel_click(&mut self) {
+    self.edit.paste_preview = false;
+    self.edit.selection = None;
+  }
+
+  /// Transforms the paste preview if there is one, otherwise the selection in place.
+  pub(in super) fn on_transform_click(
+    &mut self,
+    transform: Transform,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) {
+    let state_permutation = Rule::ACTIVE.state_permutation(transform);
+
+    if self.edit.paste_preview {
+      if let Some(clipboard) = self.edit.clipboard.as_ref() {
+        let pattern = clipboard.transformed(transform, &state_permutation);
+        self.edit.set_clipboard(pattern);
+      }
+    } else if let Some(selection) = self.edit.selection.and_then(|s| s.clip(gpu_driver.uniforms.simulation_dimm)) {
+      let target = selection.transformed(transform);
+      if target.clip(gpu_driver.uniforms.simulation_dimm) != Some(target) {
+        self.edit.status = "the transformed selection would leave the universe".to_string();
+        return;
+      }
+      // quarter turns of non-square selections cover new cells, which must be ground
+      let covered = gpu_driver.read_pattern(device, queue, target);
+      let occupied = (0..target.height as i64)
+        .flat_map(|y| (0..target.width as i64).map(move |x| [target.x + x, target.y + y]))
+        .zip(&covered.cells)
+        .any(|(cell, &state)| state != 0 && !selection.contains(cell));
+      if occupied {
+        self.edit.status = "the transformed selection would overwrite cells around it".to_string();
+        return;
+      }
+      let pattern = gpu_driver
+        .read_pattern(device, queue, selection)
+        .transformed(transform, &state_permutation);
+      let mut edits = gpu_driver.write_pattern(device, queue, [selection.x, selection.y], &Pattern::new(selection.width, selection.height));
+      edits.extend(gpu_driver.write_pattern(device, queue, [target.x, target.y], &pattern));
+      self.record_edit(edits);
+      self.edit.selection = Some(target);
+      self.edit.status.clear();
+    }
+  }
+
+  fn on_paste_place(&mut self, cell: [i64; 2], gpu_driver: &GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if let Some(clipboard) = self.edit.clipboard.as_ref() {
+      let edits = gpu_driver.write_pattern(device, queue, cell, clipboard);
+      self.edit.selection = Some(CellRect { x: cell[0], y: cell[1], width: clipboard.width, height: clipboard.height });
+      self.record_edit(edits);
+    }
+    self.edit.paste_preview = false;
+  }
+
+  pub(in super) fn edit_panel_ui(
+    &mut self,
+    ui: &mut egui::Ui,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) {
+    ui.label(match self.edit.selection {
+      Some(rect) => format!("selection: {}x{} at ({}, {})", rect.width, rect.height, rect.x, rect.y),
+      None => "selection: none".to_string()
+    });
+    ui.horizontal_wrapped(|ui| {
+      ui.add_enabled(self.edit.selection.is_some(), egui::Button::new("Copy"))
+        .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::Copy))
+        .clicked().then(|| self.on_copy_click(gpu_driver, device, queue));
+      ui.add_enabled(self.edit.clipboard.is_some(), egui::Button::new("Paste"))
+        .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::Paste))
+        .clicked().then(|| self.on_paste_click());
+    });
+    ui.horizontal_wrapped(|ui| {
+      for (label, action, transform) in [
+        ("⟳", Action::RotateCW, Transform::RotateCW),
+        ("⟲", Action::RotateCCW, Transform::RotateCCW),
+        ("⬌", Action::FlipH, Transform::FlipH),
+        ("⬍", Action::FlipV, Transform::FlipV),
+      ] {
+        ui.button(label)
+          .on_hover_text_at_pointer(self.keymap.keymap.label(action))
+          .clicked().then(|| self.on_transform_click(transform, gpu_driver, device, queue));
+      }
+    });
+    if !self.edit.status.is_empty() {
+      ui.label(&self.edit.status);
+    }
+  }
+
+  /// Selection rectangle and paste preview, drawn within the plot. Shift+LMB
+  /// drag selects, LMB places the paste preview.
+  pub(in super) fn edit_plot_ui(
+    &mut self,
+    ui: &mut PlotUi,
+    selecting: bool,
+    gpu_driver: &GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) {
+    let pointer_cell = ui.pointer_coordinate().map(plot_to_cell);
+    let primary_down = ui.ctx().input().pointer.primary_down();
+
+    if selecting && !self.edit.paste_preview {
+      if primary_down && self.edit.selection_anchor.is_none() && ui.plot_hovered() {
+        self.edit.selection_anchor = pointer_cell;
+      }
+      if let (Some(anchor), Some(cell), true) = (self.edit.selection_anchor, pointer_cell, primary_down) {
+        self.edit.selection = Some(CellRect::from_corners(anchor, cell));
+      }
+    }
+    if !primary_down {
+      self.edit.selection_anchor = None;
+    }
+
+    if let Some(selection) = self.edit.selection {
+      let corners = selection.plot_corners();
+      ui.polygon(
+        Polygon::new(PlotPoints::from(corners.to_vec()))
+          .color(Color32::from_rgb(100, 200, 255))
+          .fill_alpha(0.1)
+          .name("Selection")
+      );
+    }
+
+    if self.edit.paste_preview {
+      let (Some(clipboard), Some(cell)) = (self.edit.clipboard.as_ref(), pointer_cell) else {
+        return;
+      };
+      let texture = self.edit.preview_texture.get_or_insert_with(|| ui.ctx().load_texture(
+        "paste_preview",
+        clipboard.to_color_image(&self.palette.palette.colors),
+        TextureOptions::NEAREST
+      ));
+      let rect = CellRect { x: cell[0], y: cell[1], width: clipboard.width, height: clipboard.height };
+      let corners = rect.plot_corners();
+      ui.image(
+        PlotImage::new(
+          texture.id(),
+          PlotPoint::new((corners[0][0] + corners[2][0]) / 2.0, (corners[0][1] + corners[2][1]) / 2.0),
+          [rect.width as f32, rect.height as f32]
+        )
+        .tint(Color32::from_white_alpha(200))
+        .name("Paste preview")
+      );
+      let mut outline = corners.to_vec();
+      outline.push(corners[0]);
+      ui.line(Line::new(PlotPoints::from(outline)).color(Color32::YELLOW));
+
+      if ui.plot_clicked() {
+        self.on_paste_place(cell, gpu_driver, device, queue);
+      }
+    }
+  }
+}
@@ -0,0 +1,105 @@
+use {
+  eframe::egui,
+  image::RgbaImage,
+  crate::{
+    annotation,
+    gpu::{GPUDriver, OVERLAY_GLYPHS, OVERLAY_GRID},
+    pattern::CellRect,
+  },
+};
+
+pub(in super) struct ExportState {
+  pub(in super) edit_path: String,
+  /// Pixels per cell.
+  pub(in super) scale: u32,
+  pub(in super) overlay: u32,
+  status: String,
+}
+
+impl Default for ExportState {
+  fn default() -> Self {
+    Self {
+      edit_path: "./export.png".to_string(),
+      scale: 4,
+      overlay: 0,
+      status: String::new(),
+    }}}
+
+impl super::GUI {
+  /// Cells covered by the main view, within the simulation.
+  pub(in super) fn view_rect(&self, simulation_dimm: [u32; 2]) -> Option<CellRect> {
+    let (min, max) = (self.view_bounds.min(), self.view_bounds.max());
+    let (x0, y0) = (min[0].floor() as i64, (-max[1]).floor() as i64);
+    let (x1, y1) = (max[0].ceil() as i64, (-min[1]).ceil() as i64);
+    if x1 <= x0 || y1 <= y0 {
+      return None;
+    }
+    CellRect { x: x0, y: y0, width: (x1 - x0) as u32, height: (y1 - y0) as u32 }.clip(simulation_dimm)
+  }
+
+  /// `rect` as `render_region` draws it, with the annotations unless
+  /// disabled for exports.
+  pub(in super) fn render_annotated(
+    &self,
+    gpu_driver: &GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    rect: CellRect
+  ) -> Result<RgbaImage, String> {
+    let mut image = gpu_driver.render_region(device, queue, rect, self.export.scale, self.export.overlay)?;
+    if self.annotation.in_exports {
+      annotation::draw(&mut image, &self.annotation.items, rect, self.export.scale);
+    }
+    Ok(image)
+  }
+
+  pub(in super) fn on_export_image_click(&mut self, gpu_driver: &GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue, rect: CellRect) {
+    let path = &self.export.edit_path;
+    self.export.status = self.render_annotated(gpu_driver, device, queue, rect)
+      .and_then(|image| image.save(path).map(|_| image).map_err(|e| e.to_string()))
+      .map(|image| format!("wrote {}×{} to {path}", image.width(), image.height()))
+      .unwrap_or_else(|e| format!("export failed: {e}"));
+  }
+
+  pub(in super) fn export_panel_ui(
+    &mut self,
+    ui: &mut egui::Ui,
+    gpu_driver: &GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) {
+    let simulation_dimm = gpu_driver.uniforms.simulation_dimm;
+    ui.add(egui::Slider::new(&mut self.export.scale, 1..=32).text("px / cell"));
+    for (bit, label) in [(OVERLAY_GLYPHS, "cell glyphs"), (OVERLAY_GRID, "grid lines")] {
+      let mut enabled = self.export.overlay & bit != 0;
+      ui.checkbox(&mut enabled, label)
+        .changed().then(|| self.export.overlay ^= bit);
+    }
+    ui.horizontal_wrapped(|ui| {
+      ui.label("path: ");
+      ui.text_edit_singleline(&mut self.export.edit_path);
+    });
+
+    let scale = self.export.scale;
+    let regions = [
+      ("View", self.view_rect(simulation_dimm)),
+      ("Selection", self.edit.selection.and_then(|s| s.clip(simulation_dimm))),
+      ("All", Some(gpu_driver.simulation_rect())),
+    ];
+    ui.horizontal_wrapped(|ui| {
+      for (label, rect) in regions {
+        let button = ui.add_enabled(rect.is_some(), egui::Button::new(label));
+        let button = match rect {
+          Some(r) => button.on_hover_text_at_pointer(format!("{}×{} px", r.width * scale, r.height * scale)),
+          None => button,
+        };
+        if let (true, Some(rect)) = (button.clicked(), rect) {
+          self.on_export_image_click(gpu_driver, device, queue, rect);
+        }
+      }
+    });
+    if !self.export.status.is_empty() {
+      ui.label(&self.export.status);
+    }
+  }
+}
@@ -0,0 +1,127 @@
+use {
+  eframe::egui::{
+    self,
+    plot::{PlotBounds, PlotPoints, PlotUi, Polygon},
+    Color32,
+  },
+  crate::{
+    gpu::{FollowClass, GPUDriver, MAX_FOLLOW_WINDOW},
+    pattern::CellRect,
+  },
+  super::{gui_actions::Action, gui_edit::plot_to_cell},
+};
+
+pub(in super) struct FollowState {
+  /// Window the centroid is computed in, recentred on it as it moves.
+  window: Option<CellRect>,
+  class: FollowClass,
+  /// Side of the window opened around the cursor.
+  cursor_window: u32,
+  /// Follow around the cursor once the plot knows where it is.
+  pub(in super) cursor_requested: bool,
+  /// Fraction of the way to the centroid the view moves each frame.
+  smoothing: f64,
+  /// Last centroid, in simulation coordinates.
+  centroid: Option<[f64; 2]>,
+}
+
+impl Default for FollowState {
+  fn default() -> Self {
+    Self {
+      window: None,
+      class: FollowClass::Changes,
+      cursor_window: 64,
+      cursor_requested: false,
+      smoothing: 0.2,
+      centroid: None,
+    }}}
+
+impl super::GUI {
+  pub(in super) fn start_following(&mut self, window: CellRect) {
+    self.follow.window = Some(CellRect {
+      width: window.width.min(MAX_FOLLOW_WINDOW),
+      height: window.height.min(MAX_FOLLOW_WINDOW),
+      ..window
+    });
+    self.follow.centroid = None;
+  }
+
+  pub(in super) fn stop_following(&mut self) {
+    self.follow.window = None;
+    self.follow.centroid = None;
+  }
+
+  /// Moves the window onto the centroid read back, and the view towards it.
+  pub(in super) fn follow_control(&mut self, ctx: &egui::Context, gpu_driver: &mut GPUDriver, queue: &wgpu::Queue) {
+    let Some(window) = self.follow.window.as_mut() else {
+      gpu_driver.set_follow(queue, None);
+      return;
+    };
+    if let Some(centroid) = gpu_driver.poll_follow() {
+      window.x = (centroid[0] - window.width as f64 / 2.0).round() as i64;
+      window.y = (centroid[1] - window.height as f64 / 2.0).round() as i64;
+      self.follow.centroid = Some(centroid);
+    }
+    gpu_driver.set_follow(queue, Some((*window, self.follow.class)));
+
+    let (Some([x, y]), None) = (self.follow.centroid, self.pending_bounds) else {
+      return;
+    };
+    if !self.view_bounds.is_valid() {
+      return;
+    }
+    let center = self.view_bounds.center();
+    let delta = [(x - center.x) * self.follow.smoothing, (-y - center.y) * self.follow.smoothing];
+    if delta[0].abs() > 1e-3 || delta[1].abs() > 1e-3 {
+      let (min, max) = (self.view_bounds.min(), self.view_bounds.max());
+      self.pending_bounds = Some(PlotBounds::from_min_max(
+        [min[0] + delta[0], min[1] + delta[1]],
+        [max[0] + delta[0], max[1] + delta[1]],
+      ));
+      ctx.request_repaint();
+    }
+  }
+
+  pub(in super) fn follow_panel_ui(&mut self, ui: &mut egui::Ui, gpu_driver: &GPUDriver) {
+    for class in FollowClass::ALL {
+      ui.radio_value(&mut self.follow.class, class, class.name());
+    }
+    ui.add(egui::Slider::new(&mut self.follow.cursor_window, 8..=MAX_FOLLOW_WINDOW).text("window"))
+      .on_hover_text(format!("side of the window followed around the cursor ({})", self.keymap.keymap.hint(Action::FollowCursor)));
+    ui.add(egui::Slider::new(&mut self.follow.smoothing, 0.02..=1.0).text("smoothing"))
+      .on_hover_text("fraction of the way to the centroid the view moves each frame");
+
+    let selection = self.edit.selection.and_then(|s| s.clip(gpu_driver.uniforms.simulation_dimm));
+    ui.horizontal_wrapped(|ui| {
+      ui.add_enabled(selection.is_some(), egui::Button::new("Follow selection"))
+        .on_hover_text_at_pointer(format!("or {} over the view, for a window around the cursor", self.keymap.keymap.hint(Action::FollowCursor)))
+        .clicked().then(|| selection.map(|s| self.start_following(s)));
+      ui.add_enabled(self.follow.window.is_some(), egui::Button::new("Stop"))
+        .clicked().then(|| self.stop_following());
+    });
+    match (self.follow.window, self.follow.centroid) {
+      (Some(_), Some([x, y])) => ui.label(format!("following ({x:.1}, {y:.1})")),
+      (Some(_), None) => ui.label("no matching cells yet"),
+      _ => ui.label("not following"),
+    };
+  }
+
+  /// Starts following around the cursor when requested, and outlines the window.
+  pub(in super) fn follow_plot_ui(&mut self, ui: &mut PlotUi) {
+    let requested = std::mem::take(&mut self.follow.cursor_requested);
+    if let (true, Some(pointer)) = (requested, ui.pointer_coordinate()) {
+      let [x, y] = plot_to_cell(pointer);
+      let side = self.follow.cursor_window;
+      self.start_following(CellRect { x: x - side as i64 / 2, y: y - side as i64 / 2, width: side, height: side });
+    }
+
+    if let Some(window) = self.follow.window {
+      ui.polygon(
+        Polygon::new(PlotPoints::from(window.plot_corners().to_vec()))
+          .color(Color32::from_rgb(255, 160, 0))
+          .fill_alpha(0.0)
+          .name("Follow window")
+      );
+    }
+  }
+}
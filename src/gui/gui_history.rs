@@ -0,0 +1,108 @@
+use {
+  eframe::egui,
+  crate::{
+    gpu::GPUDriver,
+    history::{CellEdit, HistoryEntry},
+  },
+  super::gui_actions::Action,
+};
+
+impl super::GUI {
+  pub(in super) fn record_edit(&mut self, edits: Vec<CellEdit>) {
+    self.history.push(HistoryEntry::Edit(edits));
+  }
+
+  /// Full copy of the simulation buffer, taken before anything overwrites it
+  /// wholesale (Start, Step, Reset, Recompile).
+  pub(in super) fn record_snapshot(&mut self, gpu_driver: &GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if self.history.snapshot_before_run {
+      let snapshot = self.capture_snapshot(gpu_driver, device, queue);
+      self.history.push(snapshot);
+    }
+  }
+
+  fn capture_snapshot(&self, gpu_driver: &GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) -> HistoryEntry {
+    HistoryEntry::Snapshot {
+      generation: gpu_driver.generation,
+      simulation_dimm: gpu_driver.uniforms.simulation_dimm,
+      records: gpu_driver.read_records(device, queue, gpu_driver.simulation_rect()),
+    }
+  }
+
+  /// Applies `entry` in the given direction, returns the entry which reverts it.
+  fn apply_history_entry(
+    &mut self,
+    entry: HistoryEntry,
+    undo: bool,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) -> HistoryEntry {
+    match entry {
+      HistoryEntry::Edit(edits) => {
+        let cells: Vec<_> = if undo {
+          edits.iter().rev().map(|e| ([e.x, e.y], e.old)).collect()
+        } else {
+          edits.iter().map(|e| ([e.x, e.y], e.new)).collect()
+        };
+        gpu_driver.write_cells(device, queue, &cells);
+        HistoryEntry::Edit(edits)
+      },
+      HistoryEntry::Snapshot { generation, simulation_dimm, records } => {
+        let current = self.capture_snapshot(gpu_driver, device, queue);
+        if simulation_dimm == gpu_driver.uniforms.simulation_dimm {
+          gpu_driver.write_records(queue, gpu_driver.simulation_rect(), &records);
+          gpu_driver.generation = generation;
+          gpu_driver.rewind_clear(device, queue);
+        }
+        current
+      }
+    }
+  }
+
+  pub(in super) fn on_undo_click(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if let Some(entry) = self.history.pop_undo() {
+      let inverse = self.apply_history_entry(entry, true, gpu_driver, device, queue);
+      self.history.push_redo(inverse);
+    }
+  }
+
+  pub(in super) fn on_redo_click(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if let Some(entry) = self.history.pop_redo() {
+      let inverse = self.apply_history_entry(entry, false, gpu_driver, device, queue);
+      self.history.push_undo(inverse);
+    }
+  }
+
+  pub(in super) fn history_panel_ui(
+    &mut self,
+    ui: &mut egui::Ui,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) {
+    ui.horizontal_wrapped(|ui| {
+      ui.add_enabled(self.history.can_undo(), egui::Button::new("↶ Undo"))
+        .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::Undo))
+        .clicked().then(|| self.on_undo_click(gpu_driver, device, queue));
+      ui.add_enabled(self.history.can_redo(), egui::Button::new("↷ Redo"))
+        .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::Redo))
+        .clicked().then(|| self.on_redo_click(gpu_driver, device, queue));
+    });
+    ui.checkbox(&mut self.history.snapshot_before_run, "snapshot before run");
+    ui.horizontal_wrapped(|ui| {
+      ui.label("budget (MB): ");
+      let mut budget_mb = self.history.budget_bytes() / (1024 * 1024);
+      ui.add(egui::DragValue::new(&mut budget_mb).clamp_range(0..=16384))
+        .changed().then(|| self.history.set_budget_bytes(budget_mb * 1024 * 1024));
+    });
+    let (undo_depth, redo_depth) = self.history.depth();
+    ui.label(format!(
+      "{} undo / {} redo, {:.1} MB",
+      undo_depth, redo_depth,
+      self.history.size_bytes() as f64 / (1024.0 * 1024.0)
+    ));
+    ui.add_enabled(undo_depth + redo_depth > 0, egui::Button::new("Clear history"))
+      .clicked().then(|| self.history.clear());
+  }
+}
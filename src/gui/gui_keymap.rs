@@ -0,0 +1,227 @@
+use {
+  std::collections::HashMap,
+  eframe::egui::{self, Color32, Event, Key, KeyboardShortcut, Modifiers, RichText},
+  super::gui_actions::Action,
+};
+
+/// Every key a binding can use, to parse them back by name.
+const KEYS: [Key; 73] = [
+  Key::ArrowDown, Key::ArrowLeft, Key::ArrowRight, Key::ArrowUp,
+  Key::Escape, Key::Tab, Key::Backspace, Key::Enter, Key::Space,
+  Key::Insert, Key::Delete, Key::Home, Key::End, Key::PageUp, Key::PageDown,
+  Key::Minus, Key::PlusEquals,
+  Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+  Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+  Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+  Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10,
+  Key::F11, Key::F12, Key::F13, Key::F14, Key::F15, Key::F16, Key::F17, Key::F18, Key::F19, Key::F20,
+];
+
+/// Ctrl and Cmd are one modifier, so that bindings carry over between platforms.
+fn normalize(modifiers: Modifiers) -> Modifiers {
+  Modifiers {
+    alt: modifiers.alt,
+    shift: modifiers.shift,
+    command: modifiers.command || modifiers.ctrl || modifiers.mac_cmd,
+    ctrl: false,
+    mac_cmd: false,
+  }
+}
+
+pub(in super) fn shortcut_text(shortcut: &KeyboardShortcut) -> String {
+  let modifiers = normalize(shortcut.modifiers);
+  let mut text = String::new();
+  for (held, name) in [(modifiers.command, "Ctrl+"), (modifiers.alt, "Alt+"), (modifiers.shift, "Shift+")] {
+    if held {
+      text += name;
+    }
+  }
+  text + shortcut.key.name()
+}
+
+fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+  let (modifier_names, key_name) = text.trim().rsplit_once('+')
+    .map_or(("", text.trim()), |(m, k)| (m, k));
+  let mut modifiers = Modifiers::NONE;
+  for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+    match name.to_ascii_lowercase().as_str() {
+      "ctrl" | "cmd" => modifiers.command = true,
+      "alt" => modifiers.alt = true,
+      "shift" => modifiers.shift = true,
+      _ => return None,
+    }
+  }
+  let key = KEYS.into_iter().find(|key| key.name().eq_ignore_ascii_case(key_name))?;
+  Some(KeyboardShortcut { modifiers, key })
+}
+
+/// Shortcuts of every action, the defaults unless changed.
+pub(in super) struct Keymap {
+  bindings: HashMap<Action, Vec<KeyboardShortcut>>,
+}
+
+impl Default for Keymap {
+  fn default() -> Self {
+    Self {
+      bindings: Action::ALL.iter()
+        .map(|action| (*action, action.default_shortcuts().to_vec()))
+        .collect(),
+    }}}
+
+impl Keymap {
+  /// One `id=Ctrl+R, Space` line per action; actions missing from `text`
+  /// keep their defaults, unknown ones and unreadable shortcuts are skipped.
+  pub(in super) fn from_text(text: &str) -> Self {
+    let mut keymap = Self::default();
+    for (id, shortcuts) in text.lines().filter_map(|line| line.split_once('=')) {
+      if let Some(action) = Action::ALL.into_iter().find(|action| action.id() == id.trim()) {
+        keymap.bindings.insert(action, shortcuts.split(',')
+          .filter(|s| !s.trim().is_empty())
+          .filter_map(parse_shortcut)
+          .collect());
+      }
+    }
+    keymap
+  }
+
+  pub(in super) fn to_text(&self) -> String {
+    Action::ALL.iter()
+      .map(|action| format!(
+        "{}={}\n",
+        action.id(),
+        self.shortcuts(*action).iter().map(shortcut_text).collect::<Vec<_>>().join(", ")
+      ))
+      .collect()
+  }
+
+  pub(in super) fn shortcuts(&self, action: Action) -> &[KeyboardShortcut] {
+    self.bindings.get(&action).map_or(&[], Vec::as_slice)
+  }
+
+  /// Bindings of `action` for hover texts and the command palette.
+  pub(in super) fn hint(&self, action: Action) -> String {
+    match self.shortcuts(action) {
+      [] => "unbound".to_string(),
+      shortcuts => shortcuts.iter().map(shortcut_text).collect::<Vec<_>>().join(" / "),
+    }
+  }
+
+  /// `name (shortcut)`, or the name alone when unbound.
+  pub(in super) fn label(&self, action: Action) -> String {
+    match self.shortcuts(action) {
+      [] => action.name().to_string(),
+      _ => format!("{} ({})", action.name(), self.hint(action)),
+    }
+  }
+
+  /// Binds `shortcut` to `action`, taking it from whichever action had it.
+  /// Returns that action.
+  fn bind(&mut self, action: Action, shortcut: KeyboardShortcut) -> Option<Action> {
+    let previous = self.bindings.iter_mut()
+      .find_map(|(other, shortcuts)| {
+        let i = shortcuts.iter().position(|s| *s == shortcut)?;
+        shortcuts.remove(i);
+        Some(*other)
+      });
+    self.bindings.entry(action).or_default().push(shortcut);
+    previous.filter(|previous| *previous != action)
+  }
+}
+
+pub(in super) struct KeymapState {
+  pub(in super) keymap: Keymap,
+  pub(in super) show_editor: bool,
+  /// Action whose next key press is bound to it.
+  capture: Option<Action>,
+  status: String,
+}
+
+impl KeymapState {
+  pub(in super) fn new(keymap: Keymap) -> Self {
+    Self { keymap, show_editor: false, capture: None, status: String::new() }
+  }
+}
+
+impl super::GUI {
+  /// Actions whose shortcut was pressed this frame. Nothing fires while a
+  /// text field has focus, except for the command palette, or while a key
+  /// is being captured for a binding.
+  pub(in super) fn triggered_actions(&self, ctx: &egui::Context) -> Vec<Action> {
+    if self.keymap.capture.is_some() {
+      return vec![];
+    }
+    let typing = ctx.wants_keyboard_input();
+    let mut input = ctx.input_mut();
+    Action::ALL.into_iter()
+      .filter(|action| !typing || *action == Action::CommandPalette)
+      .filter(|action| self.keymap.keymap.shortcuts(*action).iter().any(|s| input.consume_shortcut(s)))
+      .collect()
+  }
+
+  /// Editor of the bindings; a key press is captured after clicking ＋.
+  pub(in super) fn keymap_editor_window(&mut self, ctx: &egui::Context) {
+    let state = &mut self.keymap;
+    if let Some(action) = state.capture {
+      // Removed from the input, so that Space or Enter does not also click
+      // the focused button.
+      let pressed = {
+        let events = &mut ctx.input_mut().events;
+        let i = events.iter().position(|event| matches!(event, Event::Key { pressed: true, .. }));
+        i.map(|i| events.remove(i)).and_then(|event| match event {
+          Event::Key { key, modifiers, .. } => Some(KeyboardShortcut { modifiers: normalize(modifiers), key }),
+          _ => None,
+        })
+      };
+      match pressed {
+        Some(KeyboardShortcut { key: Key::Escape, modifiers }) if modifiers.is_none() => state.capture = None,
+        Some(shortcut) => {
+          state.status = match state.keymap.bind(action, shortcut) {
+            Some(previous) => format!("{} moved from \"{}\"", shortcut_text(&shortcut), previous.name()),
+            None => String::new(),
+          };
+          state.capture = None;
+        },
+        None => {},
+      }
+    }
+
+    egui::Window::new("⌨ Keybindings")
+      .open(&mut state.show_editor)
+      .vscroll(true)
+      .show(ctx, |ui| {
+        ui.horizontal_wrapped(|ui| {
+          ui.button("Reset all")
+            .clicked().then(|| state.keymap = Keymap::default());
+          if state.capture.is_some() {
+            ui.label(RichText::new("press a key, Esc to cancel").color(Color32::YELLOW));
+          }
+        });
+        if !state.status.is_empty() {
+          ui.label(&state.status);
+        }
+        egui::Grid::new("keymap_grid").striped(true).show(ui, |ui| {
+          for action in Action::ALL {
+            ui.label(action.name());
+            ui.horizontal(|ui| {
+              let mut removed = None;
+              for (i, shortcut) in state.keymap.shortcuts(action).iter().enumerate() {
+                ui.small_button(format!("{} ✖", shortcut_text(shortcut)))
+                  .on_hover_text("remove")
+                  .clicked().then(|| removed = Some(i));
+              }
+              if let (Some(i), Some(shortcuts)) = (removed, state.keymap.bindings.get_mut(&action)) {
+                shortcuts.remove(i);
+              }
+              let capturing = state.capture == Some(action);
+              ui.small_button(if capturing { "…" } else { "＋" })
+                .clicked().then(|| state.capture = (!capturing).then_some(action));
+              ui.small_button("↺")
+                .on_hover_text("default")
+                .clicked().then(|| state.keymap.bindings.insert(action, action.default_shortcuts().to_vec()));
+            });
+            ui.end_row();
+          }
+        });
+      });
+  }
+}
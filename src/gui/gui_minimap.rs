@@ -0,0 +1,70 @@
+use {
+  eframe::egui::{
+    self,
+    plot::{Plot, PlotBounds, PlotImage, PlotPoint, PlotPoints, Polygon},
+    Color32, TextureId,
+  },
+  crate::gpu::{GPUDriver, ViewId},
+};
+
+/// Longest side of the overview texture, in pixels.
+const MINIMAP_RESOLUTION: u32 = 256;
+
+pub(in super) struct Minimap {
+  pub(in super) view: ViewId,
+  pub(in super) texture_id: TextureId,
+}
+
+impl super::GUI {
+  /// The whole universe at low resolution, with the current view outlined.
+  /// Clicking or dragging moves the view.
+  pub(in super) fn minimap_ui(&mut self, ui: &mut egui::Ui, gpu_driver: &mut GPUDriver) {
+    let [width, height] = gpu_driver.uniforms.simulation_dimm.map(|n| n as f64);
+    let scale = MINIMAP_RESOLUTION as f64 / width.max(height);
+    let whole = PlotBounds::from_min_max([0.0, -height], [width, 0.0]);
+    gpu_driver.request_view(
+      self.minimap.view,
+      whole,
+      [(width * scale).ceil() as u32, (height * scale).ceil() as u32]
+    );
+
+    let view_bounds = self.view_bounds;
+    let texture_id = self.minimap.texture_id;
+    let resp = Plot::new("minimap")
+      .data_aspect(1.0)
+      .height(ui.available_width() * (height / width).clamp(0.25, 1.0) as f32)
+      .include_x(0.0)
+      .include_x(width)
+      .include_y(0.0)
+      .include_y(-height)
+      .show_axes([false, false])
+      .show_x(false)
+      .show_y(false)
+      .allow_drag(false)
+      .allow_zoom(false)
+      .allow_scroll(false)
+      .allow_boxed_zoom(false)
+      .allow_double_click_reset(false)
+      .show(ui, |ui| {
+        ui.image(PlotImage::new(texture_id, PlotPoint::new(width / 2.0, -height / 2.0), [width as f32, height as f32]));
+        let [x0, y0] = view_bounds.min();
+        let [x1, y1] = view_bounds.max();
+        ui.polygon(
+          Polygon::new(PlotPoints::from(vec![[x0, y0], [x1, y0], [x1, y1], [x0, y1]]))
+            .color(Color32::from_rgb(100, 200, 255))
+            .fill_alpha(0.1)
+        );
+        (ui.plot_hovered() && ui.ctx().input().pointer.primary_down())
+          .then(|| ui.pointer_coordinate())
+          .flatten()
+      });
+
+    if let (Some(pt), true) = (resp.inner, view_bounds.is_valid()) {
+      let [half_w, half_h] = [view_bounds.width() / 2.0, view_bounds.height() / 2.0];
+      self.pending_bounds = Some(PlotBounds::from_min_max(
+        [pt.x - half_w, pt.y - half_h],
+        [pt.x + half_w, pt.y + half_h]
+      ));
+    }
+  }
+}
@@ -0,0 +1,120 @@
+use {
+  std::fs,
+  eframe::egui,
+  crate::{
+    gpu::GPUDriver,
+    palette::Palette,
+    rule::Rule,
+  },
+};
+
+pub(in super) struct PaletteState {
+  pub(in super) palette: Palette,
+  pub(in super) edit_path: String,
+  pub(in super) show_editor: bool,
+  status: String,
+}
+
+impl Default for PaletteState {
+  fn default() -> Self {
+    Self {
+      palette: Palette::default_for(Rule::ACTIVE),
+      edit_path: "./hutton32.colors".to_string(),
+      show_editor: false,
+      status: String::new(),
+    }}}
+
+fn state_name(state: usize) -> String {
+  const DIRS: [&str; 4] = ["E", "N", "W", "S"];
+  match state {
+    0 => "ground".to_string(),
+    1..=8 => format!("sensitized {state}"),
+    9..=12 => format!("OTS {}", DIRS[state - 9]),
+    13..=16 => format!("OTS {} excited", DIRS[state - 13]),
+    17..=20 => format!("STS {}", DIRS[state - 17]),
+    21..=24 => format!("STS {} excited", DIRS[state - 21]),
+    25..=28 => format!("confluent {}", state - 25),
+    29 => "crossing ─".to_string(),
+    30 => "crossing │".to_string(),
+    _ => "crossing ┼".to_string(),
+  }
+}
+
+impl super::GUI {
+  /// Uploads the palette whenever it differs from the one in use, which also
+  /// covers a recompiled driver.
+  pub(in super) fn palette_control(&mut self, gpu_driver: &mut GPUDriver, queue: &wgpu::Queue) {
+    if gpu_driver.palette != self.palette.palette {
+      gpu_driver.set_palette(queue, &self.palette.palette);
+      self.edit.invalidate_preview();
+    }
+  }
+
+  fn on_load_palette_click(&mut self) {
+    let result = fs::read_to_string(&self.palette.edit_path)
+      .map_err(|e| e.to_string())
+      .and_then(|text| Palette::default_for(Rule::ACTIVE).with_golly_colors(&text));
+    self.palette.status = match result {
+      Ok(palette) => {
+        self.palette.palette = palette;
+        "loaded".to_string()
+      },
+      Err(e) => format!("load failed: {e}"),
+    };
+  }
+
+  fn on_save_palette_click(&mut self) {
+    let path = &self.palette.edit_path;
+    if path.ends_with(".rule") {
+      self.palette.status = "save as .colors, rule files are not overwritten".to_string();
+      return;
+    }
+    let text = self.palette.palette.to_golly_colors(path.ends_with(".colors"));
+    self.palette.status = fs::write(path, text)
+      .map(|_| "saved".to_string())
+      .unwrap_or_else(|e| format!("save failed: {e}"));
+  }
+
+  pub(in super) fn palette_panel_ui(&mut self, ui: &mut egui::Ui) {
+    ui.horizontal_wrapped(|ui| {
+      ui.button("Default")
+        .on_hover_text_at_pointer(format!("{} colours", Rule::ACTIVE.name()))
+        .clicked().then(|| self.palette.palette = Palette::default_for(Rule::ACTIVE));
+      ui.button("Colour-blind safe")
+        .clicked().then(|| self.palette.palette = Palette::colour_blind_safe());
+    });
+    ui.checkbox(&mut self.palette.show_editor, "edit colours");
+    ui.horizontal_wrapped(|ui| {
+      ui.label("path: ");
+      ui.text_edit_singleline(&mut self.palette.edit_path)
+        .on_hover_text("Golly .colors file, or a .rule file with an @COLORS section");
+    });
+    ui.horizontal_wrapped(|ui| {
+      ui.button("💾 Save")
+        .clicked().then(|| self.on_save_palette_click());
+      ui.button("🗁 Load")
+        .clicked().then(|| self.on_load_palette_click());
+    });
+    if !self.palette.status.is_empty() {
+      ui.label(&self.palette.status);
+    }
+  }
+
+  /// Edits apply to the view right away.
+  pub(in super) fn palette_editor_window(&mut self, ctx: &egui::Context) {
+    let palette = &mut self.palette.palette;
+    egui::Window::new("🎨 Palette")
+      .open(&mut self.palette.show_editor)
+      .vscroll(true)
+      .show(ctx, |ui| {
+        egui::Grid::new("palette_grid").striped(true).show(ui, |ui| {
+          for (state, color) in palette.colors.iter_mut().enumerate() {
+            ui.label(state.to_string());
+            ui.color_edit_button_srgb(color);
+            ui.label(state_name(state));
+            ui.end_row();
+          }
+        });
+      });
+  }
+}
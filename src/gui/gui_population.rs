@@ -0,0 +1,84 @@
+use {
+  std::{collections::VecDeque, ops::RangeInclusive},
+  eframe::egui::{
+    self,
+    plot::{Legend, Line, Plot, PlotPoints},
+  },
+  crate::gpu::{GPUDriver, STATES},
+};
+
+/// Generations of history kept for the plot.
+const HISTORY_CAPACITY: usize = 1 << 16;
+
+/// Plotted populations, as sums of state ranges.
+const SERIES: [(&str, &[RangeInclusive<usize>]); 3] = [
+  ("non-ground", &[1..=31]),
+  ("excited transmission", &[13..=16, 21..=24]),
+  ("confluent", &[25..=28]),
+];
+
+#[derive(Default)]
+pub(in super) struct PopulationState {
+  enabled: bool,
+  show_plot: bool,
+  history: VecDeque<(u64, [u32; STATES])>,
+}
+
+fn sum(histogram: &[u32; STATES], ranges: &[RangeInclusive<usize>]) -> u64 {
+  ranges.iter().flat_map(|r| histogram[r.clone()].iter()).map(|&n| n as u64).sum()
+}
+
+impl super::GUI {
+  pub(in super) fn population_control(&mut self, gpu_driver: &mut GPUDriver) {
+    gpu_driver.set_count_populations(self.population.enabled);
+    let Some(histograms) = gpu_driver.poll_populations() else {
+      return;
+    };
+    let history = &mut self.population.history;
+    // generations computed again after stepping back replace the old ones
+    if let Some(&(first, _)) = histograms.first() {
+      while history.back().is_some_and(|&(generation, _)| generation >= first) {
+        history.pop_back();
+      }
+    }
+    history.extend(histograms);
+    let excess = history.len().saturating_sub(HISTORY_CAPACITY);
+    history.drain(..excess);
+  }
+
+  pub(in super) fn population_panel_ui(&mut self, ui: &mut egui::Ui) {
+    ui.checkbox(&mut self.population.enabled, "count populations")
+      .on_hover_text("histogram of states after each generation, while running");
+    if let Some((generation, histogram)) = self.population.history.back() {
+      ui.label(format!("generation {generation}"));
+      for (name, ranges) in SERIES {
+        ui.label(format!("{name}: {}", sum(histogram, ranges)));
+      }
+    }
+    ui.horizontal_wrapped(|ui| {
+      ui.checkbox(&mut self.population.show_plot, "show plot");
+      ui.button("Clear")
+        .clicked().then(|| self.population.history.clear());
+    });
+  }
+
+  pub(in super) fn population_plot_window(&mut self, ctx: &egui::Context) {
+    let history = &self.population.history;
+    egui::Window::new("📊 Populations")
+      .open(&mut self.population.show_plot)
+      .default_size([480.0, 240.0])
+      .show(ctx, |ui| {
+        Plot::new("populations")
+          .legend(Legend::default())
+          .label_formatter(|name, pt| format!("{name}\ngeneration {}\n{}", pt.x.round(), pt.y.round()))
+          .show(ui, |ui| {
+            for (name, ranges) in SERIES {
+              let points: PlotPoints = history.iter()
+                .map(|(generation, histogram)| [*generation as f64, sum(histogram, ranges) as f64])
+                .collect();
+              ui.line(Line::new(points).name(name));
+            }
+          });
+      });
+  }
+}
@@ -0,0 +1,192 @@
+use {
+  std::{fs::File, io::BufWriter},
+  eframe::egui::{
+    self,
+    plot::{Legend, Line, Plot, PlotPoints, PlotUi, Points},
+    Color32,
+  },
+  crate::{
+    gpu::{GPUDriver, MAX_PROBES},
+    pattern::CellRect,
+    probe::ProbeTrace,
+    rule::Rule,
+  },
+};
+
+/// Vertical distance between the lanes of the trace plot.
+const LANE_HEIGHT: f64 = 1.5;
+
+pub(in super) struct ProbeState {
+  trace: ProbeTrace,
+  edit_cell: [i64; 2],
+  pub(in super) edit_export_path: String,
+  show_trace: bool,
+  status: String,
+}
+
+impl Default for ProbeState {
+  fn default() -> Self {
+    Self {
+      trace: ProbeTrace::new(vec![]),
+      edit_cell: [0, 0],
+      edit_export_path: "./probes".to_string(),
+      show_trace: false,
+      status: String::new(),
+    }}}
+
+impl super::GUI {
+  /// Uploads the probed cells and collects the samples read back.
+  pub(in super) fn probe_control(&mut self, gpu_driver: &mut GPUDriver, queue: &wgpu::Queue) {
+    gpu_driver.set_probes(queue, &self.probe.trace.cells);
+    if let Some((first_generation, rows)) = gpu_driver.poll_probe_samples() {
+      self.probe.trace.append(first_generation, rows);
+    }
+  }
+
+  fn add_probes(&mut self, cells: impl IntoIterator<Item = [u32; 2]>) {
+    let mut probes = self.probe.trace.cells.clone();
+    for cell in cells {
+      if probes.len() < MAX_PROBES && !probes.contains(&cell) {
+        probes.push(cell);
+      }
+    }
+    self.probe.trace = ProbeTrace::new(probes);
+  }
+
+  fn on_export_probes_click(&mut self, vcd: bool) {
+    let path = format!("{}.{}", self.probe.edit_export_path, if vcd { "vcd" } else { "csv" });
+    let result = File::create(&path).and_then(|file| if vcd {
+      self.probe.trace.write_vcd(BufWriter::new(file), Rule::ACTIVE)
+    } else {
+      self.probe.trace.write_csv(BufWriter::new(file))
+    });
+    self.probe.status = match result {
+      Ok(()) => format!("wrote {} generations to {path}", self.probe.trace.rows.len()),
+      Err(e) => format!("export failed: {e}"),
+    };
+  }
+
+  pub(in super) fn probe_panel_ui(&mut self, ui: &mut egui::Ui, gpu_driver: &GPUDriver) {
+    let simulation_dimm = gpu_driver.uniforms.simulation_dimm;
+    ui.horizontal_wrapped(|ui| {
+      ui.label("cell: ");
+      ui.add(egui::DragValue::new(&mut self.probe.edit_cell[0]).prefix("x "));
+      ui.add(egui::DragValue::new(&mut self.probe.edit_cell[1]).prefix("y "));
+    });
+    ui.horizontal_wrapped(|ui| {
+      let can_add = self.probe.trace.cells.len() < MAX_PROBES;
+      let [x, y] = self.probe.edit_cell;
+      let cell = CellRect { x, y, width: 1, height: 1 }.clip(simulation_dimm);
+      ui.add_enabled(can_add && cell.is_some(), egui::Button::new("+ cell"))
+        .clicked().then(|| self.add_probes(cell.map(|c| [c.x as u32, c.y as u32])));
+      let selection = self.edit.selection.and_then(|s| s.clip(simulation_dimm));
+      ui.add_enabled(can_add && selection.is_some(), egui::Button::new("+ selection"))
+        .on_hover_text_at_pointer(format!("probe every cell of the selection, up to {MAX_PROBES}"))
+        .clicked().then(|| if let Some(s) = selection {
+          let cells = (0..s.height).flat_map(|dy| (0..s.width).map(move |dx| [s.x as u32 + dx, s.y as u32 + dy]));
+          self.add_probes(cells.collect::<Vec<_>>());
+        });
+    });
+
+    let mut removed = None;
+    for (i, [x, y]) in self.probe.trace.cells.iter().enumerate() {
+      ui.horizontal(|ui| {
+        ui.small_button("✖").clicked().then(|| removed = Some(i));
+        ui.label(format!("p{i} ({x}, {y})"));
+      });
+    }
+    if let Some(i) = removed {
+      let mut probes = self.probe.trace.cells.clone();
+      probes.remove(i);
+      self.probe.trace = ProbeTrace::new(probes);
+    }
+
+    ui.label(format!("{} generations recorded", self.probe.trace.rows.len()));
+    ui.horizontal_wrapped(|ui| {
+      ui.checkbox(&mut self.probe.show_trace, "show trace");
+      ui.button("Clear")
+        .clicked().then(|| self.probe.trace = ProbeTrace::new(self.probe.trace.cells.clone()));
+    });
+    ui.horizontal_wrapped(|ui| {
+      ui.label("export: ");
+      ui.text_edit_singleline(&mut self.probe.edit_export_path);
+    });
+    ui.horizontal_wrapped(|ui| {
+      let has_rows = !self.probe.trace.rows.is_empty();
+      ui.add_enabled(has_rows, egui::Button::new("CSV"))
+        .clicked().then(|| self.on_export_probes_click(false));
+      ui.add_enabled(has_rows, egui::Button::new("VCD"))
+        .on_hover_text_at_pointer("for GTKWave")
+        .clicked().then(|| self.on_export_probes_click(true));
+    });
+    if !self.probe.status.is_empty() {
+      ui.label(&self.probe.status);
+    }
+  }
+
+  /// Logic analyser view: one lane per probe, high while excited.
+  pub(in super) fn probe_trace_window(&mut self, ctx: &egui::Context) {
+    let trace = &self.probe.trace;
+    egui::Window::new("📈 Probe trace")
+      .open(&mut self.probe.show_trace)
+      .default_size([480.0, 240.0])
+      .show(ctx, |ui| {
+        let end = trace.end();
+        Plot::new("probe_trace")
+          .legend(Legend::default())
+          .show_y(false)
+          .y_axis_formatter(|_, _| String::new())
+          .label_formatter(|name, pt| format!("{name}\ngeneration {}", pt.x.round()))
+          .show(ui, |ui| {
+            for probe in 0..trace.cells.len() {
+              let lane = -(probe as f64) * LANE_HEIGHT;
+              // one line per run of generations, broken at gaps
+              let mut lines: Vec<Vec<[f64; 2]>> = vec![vec![]];
+              for (generation, state) in trace.transitions(probe) {
+                let points = lines.last_mut().unwrap();
+                let previous = points.last().map(|&[_, level]| level);
+                match state {
+                  Some(state) => {
+                    let level = lane + f64::from(u8::from(Rule::ACTIVE.is_excited(state)));
+                    if let Some(previous) = previous {
+                      points.push([generation as f64, previous]);
+                    }
+                    points.push([generation as f64, level]);
+                  },
+                  None => {
+                    if let Some(previous) = previous {
+                      points.push([generation as f64, previous]);
+                    }
+                    lines.push(vec![]);
+                  },
+                }
+              }
+              if let Some(points) = lines.last_mut() {
+                if let Some(&[_, last]) = points.last() {
+                  points.push([end as f64, last]);
+                }
+              }
+              for points in lines {
+                ui.line(Line::new(PlotPoints::from(points)).name(trace.name(probe)));
+              }
+            }
+          });
+      });
+  }
+
+  /// Probed cells.
+  pub(in super) fn probe_plot_ui(&self, ui: &mut PlotUi) {
+    if self.probe.trace.cells.is_empty() {
+      return;
+    }
+    let points: Vec<[f64; 2]> = self.probe.trace.cells.iter()
+      .map(|&[x, y]| [x as f64 + 0.5, -(y as f64) - 0.5])
+      .collect();
+    ui.points(
+      Points::new(PlotPoints::from(points))
+        .radius(3.0)
+        .color(Color32::from_rgb(0, 200, 255))
+        .name("Probes")
+    );
+  }
+}
@@ -0,0 +1,93 @@
+use {
+  eframe::egui,
+  crate::{
+    gpu::GPUDriver,
+    profile::{ProfileSample, ProfileTotals, BYTES_PER_CELL},
+  },
+};
+
+pub(in super) struct ProfileState {
+  totals: ProfileTotals,
+  latest: Option<ProfileSample>,
+  pub(in super) edit_path: String,
+  status: String,
+}
+
+impl Default for ProfileState {
+  fn default() -> Self {
+    Self {
+      totals: ProfileTotals::default(),
+      latest: None,
+      edit_path: "./profile.csv".to_string(),
+      status: String::new(),
+    }}}
+
+fn figure(value: Option<f64>, decimals: usize) -> String {
+  value.map_or("-".to_string(), |v| format!("{v:.decimals$}"))
+}
+
+impl super::GUI {
+  /// Collects the timings of the submissions completed since the last frame.
+  pub(in super) fn profile_control(&mut self, gpu_driver: &mut GPUDriver) {
+    if let Some(sample) = gpu_driver.poll_profile() {
+      self.profile.totals.add(&sample);
+      self.profile.latest = Some(sample);
+    }
+  }
+
+  fn device_name(&self) -> &str {
+    self.adapter_info.as_ref().map(|a| a.name.as_ref()).unwrap_or("")
+  }
+
+  fn on_append_profile_click(&mut self, gpu_driver: &GPUDriver) {
+    let path = &self.profile.edit_path;
+    let result = self.profile.totals.append_csv(path, self.device_name(), gpu_driver.uniforms.simulation_dimm);
+    self.profile.status = match result {
+      Ok(()) => format!("appended to {path}"),
+      Err(e) => format!("export failed: {e}"),
+    };
+  }
+
+  fn on_copy_profile_click(&mut self, ui: &egui::Ui, gpu_driver: &GPUDriver) {
+    ui.output().copied_text = self.profile.totals.to_markdown(self.device_name(), gpu_driver.uniforms.simulation_dimm);
+    self.profile.status = "copied as a Markdown table".to_string();
+  }
+
+  pub(in super) fn profile_panel_ui(&mut self, ui: &mut egui::Ui, gpu_driver: &GPUDriver) {
+    let totals = &self.profile.totals;
+    ui.label(if gpu_driver.has_timestamps() { "source: GPU timestamps" } else { "source: CPU wall time" })
+      .on_hover_text("without TIMESTAMP_QUERY, each submission is timed from its encoding \
+        until the queue reports it done, render and driver overhead included");
+    if let Some(latest) = &self.profile.latest {
+      let per_generation = latest.simulation_ms.map(|ms| ms / latest.generations.max(1) as f64);
+      ui.label(format!("last: simulation {} ms / gen, render {} ms",
+        figure(per_generation, 3), figure(latest.render_ms, 3)));
+    }
+    ui.label(format!("simulation: {} ms / gen over {} generations",
+      figure(totals.ms_per_generation(), 4), totals.generations));
+    ui.label(format!("throughput: {} cells / ns", figure(totals.cells_per_ns(), 3)));
+    ui.label(format!("bandwidth: ~{} GB/s", figure(totals.bandwidth_gb_per_s(), 2)))
+      .on_hover_text(format!("estimated at {BYTES_PER_CELL} bytes per cell and generation"));
+    ui.label(format!("render: {} ms over {} frames", figure(totals.ms_per_render(), 4), totals.renders));
+    ui.button("Reset")
+      .clicked().then(|| self.profile = ProfileState {
+        edit_path: std::mem::take(&mut self.profile.edit_path),
+        ..Default::default()
+      });
+
+    ui.horizontal_wrapped(|ui| {
+      ui.label("csv: ");
+      ui.text_edit_singleline(&mut self.profile.edit_path);
+    });
+    ui.horizontal_wrapped(|ui| {
+      let has_figures = self.profile.totals.generations > 0 || self.profile.totals.renders > 0;
+      ui.add_enabled(has_figures, egui::Button::new("Append to CSV"))
+        .clicked().then(|| self.on_append_profile_click(gpu_driver));
+      ui.add_enabled(has_figures, egui::Button::new("Copy as Markdown"))
+        .clicked().then(|| self.on_copy_profile_click(ui, gpu_driver));
+    });
+    if !self.profile.status.is_empty() {
+      ui.label(&self.profile.status);
+    }
+  }
+}
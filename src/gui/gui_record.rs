@@ -0,0 +1,137 @@
+use {
+  eframe::egui,
+  crate::{
+    gpu::GPUDriver,
+    pattern::CellRect,
+    recording::{Recording, RecordingFormat},
+  },
+};
+
+pub(in super) struct RecordState {
+  pub(in super) edit_path: String,
+  format: RecordingFormat,
+  /// Generations between frames.
+  every: u64,
+  fps: u32,
+  /// The recording stops by itself after this many frames.
+  max_frames: usize,
+  active: Option<ActiveRecording>,
+  status: String,
+}
+
+struct ActiveRecording {
+  recording: Recording,
+  rect: CellRect,
+  next_generation: u64,
+}
+
+impl Default for RecordState {
+  fn default() -> Self {
+    Self {
+      edit_path: "./recording.gif".to_string(),
+      format: RecordingFormat::Gif,
+      every: 1,
+      fps: 10,
+      max_frames: 100,
+      active: None,
+      status: String::new(),
+    }}}
+
+impl super::GUI {
+  pub(in super) fn on_start_recording_click(&mut self, gpu_driver: &GPUDriver, rect: CellRect) {
+    let record = &mut self.record;
+    record.active = Some(ActiveRecording {
+      recording: Recording::new(record.format, record.edit_path.clone(), record.fps),
+      rect,
+      next_generation: gpu_driver.generation,
+    });
+    record.status.clear();
+  }
+
+  pub(in super) fn on_stop_recording_click(&mut self) {
+    if let Some(active) = self.record.active.take() {
+      self.record.status = active.recording.finish().unwrap_or_else(|e| format!("recording failed: {e}"));
+    }
+  }
+
+  /// Generation the next frame is taken at, while recording.
+  pub(in super) fn record_next_generation(&self) -> Option<u64> {
+    self.record.active.as_ref().map(|active| active.next_generation)
+  }
+
+  /// Renders a frame once the recorded generation is reached, and shortens
+  /// the steps of this frame so that the next one is hit exactly.
+  pub(in super) fn record_control(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let Some((rect, next_generation)) = self.record.active.as_ref().map(|a| (a.rect, a.next_generation)) else {
+      return;
+    };
+    let generation = gpu_driver.generation;
+    if generation >= next_generation {
+      let frame = self.render_annotated(gpu_driver, device, queue, rect);
+      let Some(active) = self.record.active.as_mut() else {
+        return;
+      };
+      if let Err(e) = frame.and_then(|frame| active.recording.push(frame)) {
+        self.record.active = None;
+        self.record.status = format!("recording failed: {e}");
+        return;
+      }
+      active.next_generation = generation + self.record.every;
+      if active.recording.frame_count() >= self.record.max_frames {
+        self.on_stop_recording_click();
+        return;
+      }
+    }
+    if let Some(active) = &self.record.active {
+      let until_next = active.next_generation - generation;
+      gpu_driver.simulatiion_steps_per_call = gpu_driver.simulatiion_steps_per_call.min(until_next);
+    }
+  }
+
+  pub(in super) fn record_panel_ui(&mut self, ui: &mut egui::Ui, gpu_driver: &GPUDriver) {
+    if let Some(active) = &self.record.active {
+      ui.label(format!(
+        "recording {}×{} cells, {} frames",
+        active.rect.width, active.rect.height, active.recording.frame_count()
+      ));
+      ui.button("⏹ Stop")
+        .clicked().then(|| self.on_stop_recording_click());
+      return;
+    }
+
+    let record = &mut self.record;
+    egui::ComboBox::from_label("format")
+      .selected_text(record.format.name())
+      .show_ui(ui, |ui| {
+        for format in RecordingFormat::ALL {
+          ui.selectable_value(&mut record.format, format, format.name());
+        }
+      });
+    ui.horizontal_wrapped(|ui| {
+      ui.label("path: ");
+      ui.text_edit_singleline(&mut record.edit_path)
+        .on_hover_text("PNG sequences are numbered after the path")
+        .changed().then(|| record.format = RecordingFormat::from_path(&record.edit_path));
+    });
+    ui.add(egui::DragValue::new(&mut record.every).clamp_range(1..=u64::MAX).prefix("every ").suffix(" gens"));
+    ui.add(egui::DragValue::new(&mut record.fps).clamp_range(1..=100).suffix(" fps"));
+    ui.add(egui::DragValue::new(&mut record.max_frames).clamp_range(1..=100_000).prefix("at most ").suffix(" frames"));
+    ui.label("scale and overlays as for image export");
+
+    let simulation_dimm = gpu_driver.uniforms.simulation_dimm;
+    let regions = [
+      ("● Record view", self.view_rect(simulation_dimm)),
+      ("● Record selection", self.edit.selection.and_then(|s| s.clip(simulation_dimm))),
+    ];
+    ui.horizontal_wrapped(|ui| {
+      for (label, rect) in regions {
+        if let (true, Some(rect)) = (ui.add_enabled(rect.is_some(), egui::Button::new(label)).clicked(), rect) {
+          self.on_start_recording_click(gpu_driver, rect);
+        }
+      }
+    });
+    if !self.record.status.is_empty() {
+      ui.label(&self.record.status);
+    }
+  }
+}
@@ -0,0 +1,27 @@
+use {
+  eframe::egui,
+  crate::gpu::{GPUDriver, RenderMode, OVERLAY_GLYPHS, OVERLAY_GRID},
+};
+
+impl super::GUI {
+  pub(in super) fn render_panel_ui(&mut self, ui: &mut egui::Ui, gpu_driver: &mut GPUDriver) {
+    let uniforms = &mut gpu_driver.uniforms;
+    for mode in RenderMode::ALL {
+      ui.radio_value(&mut uniforms.render_mode, mode as u32, mode.name());
+    }
+    if uniforms.render_mode == RenderMode::Heat as u32 {
+      ui.add(egui::Slider::new(&mut uniforms.heat_decay, 0.5..=0.999).text("decay"))
+        .on_hover_text("fraction of the heat left after each generation");
+    }
+    ui.separator();
+    for (bit, label, hover) in [
+      (OVERLAY_GLYPHS, "cell glyphs", "arrows and state shapes, when zoomed in"),
+      (OVERLAY_GRID, "grid lines", "when zoomed in"),
+    ] {
+      let mut enabled = uniforms.overlay & bit != 0;
+      ui.checkbox(&mut enabled, label)
+        .on_hover_text(hover)
+        .changed().then(|| uniforms.overlay ^= bit);
+    }
+  }
+}
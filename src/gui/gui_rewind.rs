@@ -0,0 +1,65 @@
+use {
+  eframe::egui,
+  crate::gpu::GPUDriver,
+};
+
+pub(in super) struct RewindSettings {
+  pub enabled: bool,
+  pub depth: u32,
+}
+
+impl Default for RewindSettings {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      depth: 64,
+    }}}
+
+impl super::GUI {
+  pub(in super) fn apply_rewind_settings(&self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let depth = self.rewind.enabled.then_some(self.rewind.depth);
+    gpu_driver.set_rewind(device, queue, depth);
+  }
+
+  fn rewind_seek(&mut self, back: u32, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if gpu_driver.rewind_seek(device, queue, back) {
+      self.compute_requested = false;
+    }
+  }
+
+  pub(in super) fn on_step_back_click(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if let Some((_, cursor)) = gpu_driver.rewind_state() {
+      self.rewind_seek(cursor + 1, gpu_driver, device, queue);
+    }
+  }
+
+  pub(in super) fn rewind_panel_ui(
+    &mut self,
+    ui: &mut egui::Ui,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) {
+    let mut changed = ui.checkbox(&mut self.rewind.enabled, "enabled").changed();
+    ui.horizontal_wrapped(|ui| {
+      ui.label("depth: ");
+      changed |= ui.add(egui::DragValue::new(&mut self.rewind.depth).clamp_range(1..=4096))
+        .on_hover_text("generations retained on the GPU")
+        .lost_focus();
+    });
+    if changed {
+      self.apply_rewind_settings(gpu_driver, device, queue);
+    }
+
+    let Some((len, cursor)) = gpu_driver.rewind_state() else {
+      return;
+    };
+    // the timeline is shown in generations, newest on the right
+    let newest = gpu_driver.generation + cursor as u64;
+    let oldest = (newest + 1).saturating_sub(len as u64);
+    let mut shown = newest - cursor as u64;
+    ui.add_enabled(len > 1, egui::Slider::new(&mut shown, oldest..=newest).text("gen"))
+      .changed().then(|| self.rewind_seek((newest - shown) as u32, gpu_driver, device, queue));
+    ui.label(format!("{len} retained, {cursor} back"));
+  }
+}
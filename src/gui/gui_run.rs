@@ -0,0 +1,99 @@
+use {
+  eframe::egui,
+  crate::gpu::GPUDriver,
+};
+
+pub(in super) struct RunControl {
+  /// Generation the current run stops at, once the driver has reached it.
+  pub target: Option<u64>,
+  /// Stop as soon as a generation leaves every cell unchanged.
+  pub stop_when_stable: bool,
+  edit_generations: u64,
+  status: String,
+}
+
+impl Default for RunControl {
+  fn default() -> Self {
+    Self {
+      target: None,
+      stop_when_stable: false,
+      edit_generations: 8192,
+      status: String::new(),
+    }}}
+
+impl super::GUI {
+  pub(in super) fn run_until(&mut self, target: u64, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    if target <= gpu_driver.generation {
+      self.run.status = format!("already at generation {}", gpu_driver.generation);
+      return;
+    }
+    self.record_snapshot(gpu_driver, device, queue);
+    self.run.target = Some(target);
+    self.run.status.clear();
+    self.compute_requested = true;
+    self.t0 = Some(std::time::Instant::now());
+  }
+
+  fn stop_run(&mut self, status: String) {
+    self.compute_requested = false;
+    self.run.target = None;
+    self.run.status = status;
+  }
+
+  /// Evaluates the stop conditions, and picks the steps of this frame so that
+  /// a target generation is hit exactly.
+  pub(in super) fn run_control(&mut self, gpu_driver: &mut GPUDriver) {
+    let generation = gpu_driver.generation;
+
+    if let Some(change_log) = gpu_driver.poll_change_log() {
+      let stable = change_log.iter().find(|(_, changed)| *changed == 0);
+      if let (true, true, Some((reached, _))) = (self.run.stop_when_stable, self.compute_requested, stable) {
+        // changes are read back a frame or two late, the state has not moved since
+        self.stop_run(format!("stable since generation {}", reached - 1));
+      }
+    }
+
+    if let Some(target) = self.run.target {
+      if generation >= target {
+        self.stop_run(format!(
+          "stopped at generation {} ({:.3}s)",
+          generation,
+          self.t0.map(|t0| t0.elapsed().as_secs_f64()).unwrap_or(0.0)
+        ));
+      }
+    }
+
+    gpu_driver.simulatiion_steps_per_call = match self.run.target {
+      Some(target) => self.speed.steps.min(target - generation),
+      None => self.speed.steps
+    };
+    gpu_driver.uniforms.count_changes = u32::from(self.run.stop_when_stable && self.compute_requested);
+  }
+
+  pub(in super) fn run_panel_ui(
+    &mut self,
+    ui: &mut egui::Ui,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) {
+    ui.horizontal_wrapped(|ui| {
+      ui.label("N: ");
+      ui.add(egui::DragValue::new(&mut self.run.edit_generations).clamp_range(1..=u64::MAX));
+    });
+    ui.horizontal_wrapped(|ui| {
+      let n = self.run.edit_generations;
+      ui.button("Run to gen N")
+        .clicked().then(|| self.run_until(n, gpu_driver, device, queue));
+      ui.button("Run N more")
+        .clicked().then(|| self.run_until(gpu_driver.generation.saturating_add(n), gpu_driver, device, queue));
+    });
+    ui.checkbox(&mut self.run.stop_when_stable, "stop when stable")
+      .on_hover_text("counts changed cells on the GPU while running");
+    if let Some(target) = self.run.target {
+      ui.label(format!("running to generation {target}"));
+    } else if !self.run.status.is_empty() {
+      ui.label(&self.run.status);
+    }
+  }
+}
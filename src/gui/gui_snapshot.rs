@@ -0,0 +1,80 @@
+use {
+  std::{fs::File, io::{BufReader, BufWriter}},
+  eframe::egui::{self, plot::PlotBounds},
+  crate::{
+    gpu::GPUDriver,
+    rule::Rule,
+    snapshot::{Boundary, Snapshot},
+  },
+};
+
+impl super::GUI {
+  pub(in super) fn on_save_snapshot_click(&mut self, gpu_driver: &GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let snapshot = Snapshot {
+      rule: Rule::ACTIVE.name().to_string(),
+      lut_hash: gpu_driver.lut_hash,
+      boundary: Boundary::Bounded,
+      simulation_dimm: gpu_driver.uniforms.simulation_dimm,
+      generation: gpu_driver.generation,
+      view_bounds: [
+        self.view_bounds.min()[0], self.view_bounds.min()[1],
+        self.view_bounds.max()[0], self.view_bounds.max()[1]
+      ],
+      records: gpu_driver.read_records(device, queue, gpu_driver.simulation_rect()),
+    };
+    self.snapshot_status = File::create(&self.edit_snapshot_path)
+      .and_then(|file| snapshot.write_to(BufWriter::new(file)))
+      .map(|_| format!("saved generation {}", snapshot.generation))
+      .unwrap_or_else(|e| format!("save failed: {e}"));
+  }
+
+  pub(in super) fn on_load_snapshot_click(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    let snapshot = match File::open(&self.edit_snapshot_path)
+      .and_then(|file| Snapshot::read_from(
+        BufReader::new(file),
+        |dimm| GPUDriver::check_simulation_dimm(device, dimm)
+      ))
+    {
+      Ok(snapshot) => snapshot,
+      Err(e) => {
+        self.snapshot_status = format!("load failed: {e}");
+        return;
+      }
+    };
+    if let Err(e) = snapshot.check_compatible(Rule::ACTIVE, gpu_driver.lut_hash) {
+      self.snapshot_status = format!("load failed: {e}");
+      return;
+    }
+
+    self.record_snapshot(gpu_driver, device, queue);
+    gpu_driver.load_records(device, queue, snapshot.simulation_dimm, &snapshot.records);
+    gpu_driver.generation = snapshot.generation;
+    self.compute_requested = false;
+    self.t0 = None;
+    let [min_x, min_y, max_x, max_y] = snapshot.view_bounds;
+    self.pending_bounds = Some(PlotBounds::from_min_max([min_x, min_y], [max_x, max_y]));
+    self.snapshot_status = format!("loaded generation {}", snapshot.generation);
+  }
+
+  pub(in super) fn snapshot_panel_ui(
+    &mut self,
+    ui: &mut egui::Ui,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue
+  ) {
+    ui.horizontal_wrapped(|ui| {
+      ui.label("path: ");
+      ui.text_edit_singleline(&mut self.edit_snapshot_path);
+    });
+    ui.horizontal_wrapped(|ui| {
+      ui.button("💾 Save")
+        .clicked().then(|| self.on_save_snapshot_click(gpu_driver, device, queue));
+      ui.button("🗁 Load")
+        .clicked().then(|| self.on_load_snapshot_click(gpu_driver, device, queue));
+    });
+    if !self.snapshot_status.is_empty() {
+      ui.label(&self.snapshot_status);
+    }
+  }
+}
@@ -0,0 +1,177 @@
+use {
+  std::{ops::RangeInclusive, time::{Duration, Instant}},
+  eframe::egui::{self, Color32, RichText},
+  crate::gpu::{GPUDriver, Pace, Pacer, WorkerControl},
+};
+
+/// Slider range of `SpeedState::target_gens_per_second`, stored values are clamped to it.
+pub(in super) const TARGET_RATE_RANGE: RangeInclusive<f64> = 0.1..=100_000.0;
+/// Slider range of `SpeedState::frame_budget_ms`.
+pub(in super) const FRAME_BUDGET_RANGE: RangeInclusive<f64> = 5.0..=200.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(in super) enum SpeedMode {
+  /// A fixed number of generations each frame, so tied to the refresh rate.
+  ItersPerFrame,
+  /// Generations per second of wall time, below one per frame in slow motion.
+  TargetRate,
+  /// As many generations per frame as fit in the frame budget.
+  Fastest,
+}
+
+impl SpeedMode {
+  const ALL: [SpeedMode; 3] = [SpeedMode::ItersPerFrame, SpeedMode::TargetRate, SpeedMode::Fastest];
+
+  fn name(&self) -> &'static str {
+    match self {
+      SpeedMode::ItersPerFrame => "iters / frame",
+      SpeedMode::TargetRate => "target gens / s",
+      SpeedMode::Fastest => "as fast as possible",
+    }
+  }
+
+  /// Stable name the mode is stored under.
+  pub(in super) fn id(&self) -> &'static str {
+    match self {
+      SpeedMode::ItersPerFrame => "iters_per_frame",
+      SpeedMode::TargetRate => "target_rate",
+      SpeedMode::Fastest => "fastest",
+    }
+  }
+
+  pub(in super) fn from_id(id: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|mode| mode.id() == id)
+  }
+}
+
+pub(in super) struct SpeedState {
+  pub(in super) mode: SpeedMode,
+  /// The simulation thread paces itself rather than running the generations
+  /// each frame hands it, and the frames only show the latest generation.
+  pub(in super) background: bool,
+  pub(in super) target_gens_per_second: f64,
+  /// Longest frame the fastest mode aims for, in milliseconds.
+  pub(in super) frame_budget_ms: f64,
+  /// Generations of this frame, before run targets and recordings shorten it.
+  pub(in super) steps: u64,
+  /// Generations owed at the target rate and steps per frame settled on in
+  /// the fastest mode, carried over between frames.
+  pacer: Pacer,
+  last_frame: Option<Instant>,
+  /// Why `edit_iters_frame` was rejected.
+  pub(in super) error: Option<String>,
+}
+
+impl Default for SpeedState {
+  fn default() -> Self {
+    Self {
+      mode: SpeedMode::ItersPerFrame,
+      background: false,
+      target_gens_per_second: 60.0,
+      frame_budget_ms: 33.0,
+      steps: 1,
+      pacer: Pacer::default(),
+      last_frame: None,
+      error: None,
+    }}}
+
+impl SpeedState {
+  /// A single step runs on the next frame whatever the rate.
+  pub(in super) fn grant_step(&mut self) {
+    self.pacer.owed = self.pacer.owed.max(1.0);
+  }
+}
+
+impl super::GUI {
+  /// Picks the generations of this frame from the time since the last one.
+  pub(in super) fn speed_control(&mut self) {
+    let now = Instant::now();
+    // a stalled frame (dragging the window, a long readback) is not made up for
+    let dt = self.speed.last_frame
+      .map_or(0.0, |last| (now - last).as_secs_f64())
+      .min(0.25);
+    self.speed.last_frame = Some(now);
+    let speed = &mut self.speed;
+
+    speed.steps = match speed.mode {
+      SpeedMode::ItersPerFrame => self.iters_frame,
+      SpeedMode::TargetRate => {
+        let dt = if self.compute_requested { dt } else { 0.0 };
+        let steps = speed.pacer.due(speed.target_gens_per_second, dt);
+        speed.pacer.ran(steps);
+        steps
+      },
+      SpeedMode::Fastest => {
+        if self.compute_requested && dt > 0.0 {
+          speed.pacer.adapt(speed.frame_budget_ms, dt * 1000.0);
+        }
+        speed.pacer.adaptive as u64
+      },
+    };
+  }
+
+  /// Hands the generations of this frame to the simulation thread, or in
+  /// the background the run itself, where the speed modes become batches, a
+  /// rate or a GPU time budget per batch.
+  pub(in super) fn worker_control(&self, gpu_driver: &mut GPUDriver) {
+    let speed = &self.speed;
+    gpu_driver.worker = WorkerControl {
+      running: self.compute_requested,
+      stop_at: [self.run.target, self.record_next_generation()].into_iter().flatten().min(),
+      pace: match speed.mode {
+        _ if !speed.background => Pace::Frame(gpu_driver.simulatiion_steps_per_call),
+        SpeedMode::ItersPerFrame => Pace::Batch(self.iters_frame),
+        SpeedMode::TargetRate => Pace::Rate(speed.target_gens_per_second),
+        SpeedMode::Fastest => Pace::Budget(speed.frame_budget_ms),
+      },
+    };
+  }
+
+  /// Redraws right away, unless a slow motion run has nothing to show until
+  /// its next generation is due.
+  pub(in super) fn request_next_frame(&self, ctx: &egui::Context) {
+    let speed = &self.speed;
+    match speed.mode {
+      SpeedMode::TargetRate if !speed.background && speed.target_gens_per_second < 30.0 && speed.pacer.owed < 1.0 =>
+        ctx.request_repaint_after(Duration::from_secs_f64((1.0 - speed.pacer.owed) / speed.target_gens_per_second)),
+      _ => ctx.request_repaint(),
+    }
+  }
+
+  pub(in super) fn speed_panel_ui(&mut self, ui: &mut egui::Ui, gpu_driver: &mut GPUDriver) {
+    ui.horizontal_wrapped(|ui| {
+      for mode in SpeedMode::ALL {
+        ui.radio_value(&mut self.speed.mode, mode, mode.name());
+      }
+    });
+    ui.checkbox(&mut self.speed.background, "run in background")
+      .on_hover_text("advanced by a thread of its own, also while minimised; \
+        with watchpoints, probes or populations it waits for their logs to be read");
+    match self.speed.mode {
+      SpeedMode::ItersPerFrame => {
+        ui.horizontal_wrapped(|ui| {
+          ui.label("iters / frame: ");
+          ui.text_edit_singleline(&mut self.edit_iters_frame)
+            .lost_focus().then(|| self.on_edit_iters_frame_changed(gpu_driver));
+        });
+        if let Some(error) = &self.speed.error {
+          ui.label(RichText::new(error).color(Color32::RED));
+        }
+      },
+      SpeedMode::TargetRate => {
+        ui.add(egui::Slider::new(&mut self.speed.target_gens_per_second, TARGET_RATE_RANGE)
+          .logarithmic(true)
+          .text("gens / s"))
+          .on_hover_text("below the refresh rate, some frames show no new generation");
+      },
+      SpeedMode::Fastest => {
+        ui.add(egui::Slider::new(&mut self.speed.frame_budget_ms, FRAME_BUDGET_RANGE).text("ms / frame"))
+          .on_hover_text("steps per frame grow until a frame takes this long, \
+            or in the background until a batch takes this long on the GPU");
+        if !self.speed.background {
+          ui.label(format!("{} steps / frame", self.speed.pacer.adaptive as u64));
+        }
+      },
+    }
+  }
+}
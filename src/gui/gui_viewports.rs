@@ -0,0 +1,112 @@
+use {
+  eframe::egui::{
+    self,
+    plot::{PlotBounds, PlotImage, PlotPoints, Polygon},
+    Color32, TextureId,
+  },
+  crate::gpu::{GPUDriver, ViewId},
+};
+
+/// Extra viewer window onto the shared simulation.
+pub(in super) struct Viewport {
+  /// Window title and plot id.
+  number: usize,
+  view: ViewId,
+  /// Registered with the renderer at the end of the first frame.
+  texture_id: Option<TextureId>,
+  pending_bounds: Option<PlotBounds>,
+  open: bool,
+}
+
+#[derive(Default)]
+pub(in super) struct ViewportsState {
+  pub(in super) viewports: Vec<Viewport>,
+  next_number: usize,
+}
+
+impl super::GUI {
+  pub(in super) fn on_new_viewport_click(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device) {
+    self.viewports.next_number += 1;
+    self.viewports.viewports.push(Viewport {
+      number: self.viewports.next_number,
+      view: gpu_driver.add_view(device),
+      texture_id: None,
+      pending_bounds: self.view_bounds.is_valid().then_some(self.view_bounds),
+      open: true,
+    });
+  }
+
+  /// Views are lost with the driver they belong to.
+  pub(in super) fn recreate_views(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device) {
+    self.minimap.view = gpu_driver.add_view(device);
+    for viewport in &mut self.viewports.viewports {
+      viewport.view = gpu_driver.add_view(device);
+    }
+  }
+
+  /// Each window renders its own bounds, the main view is outlined.
+  pub(in super) fn viewport_windows(&mut self, ctx: &egui::Context, gpu_driver: &mut GPUDriver) {
+    let simulation_dimm = gpu_driver.uniforms.simulation_dimm;
+    let main_bounds = self.view_bounds;
+
+    for viewport in &mut self.viewports.viewports {
+      egui::Window::new(format!("🗖 View {}", viewport.number))
+        .id(egui::Id::new(("viewport", viewport.number)))
+        .open(&mut viewport.open)
+        .default_size([320.0, 240.0])
+        .resizable(true)
+        .show(ctx, |ui| {
+          let resp = super::viewer_plot(("viewport", viewport.number), simulation_dimm)
+            .show(ui, |ui| {
+              if let Some(bounds) = viewport.pending_bounds.take() {
+                ui.set_plot_bounds(bounds);
+              }
+              let bounds = ui.plot_bounds();
+              if let Some(texture_id) = viewport.texture_id {
+                ui.image(PlotImage::new(texture_id, bounds.center(), [bounds.width() as f32, bounds.height() as f32]));
+              }
+              let [x0, y0] = main_bounds.min();
+              let [x1, y1] = main_bounds.max();
+              ui.polygon(
+                Polygon::new(PlotPoints::from(vec![[x0, y0], [x1, y0], [x1, y1], [x0, y1]]))
+                  .color(Color32::from_rgb(100, 200, 255))
+                  .fill_alpha(0.0)
+                  .name("Main view")
+              );
+              bounds
+            });
+          let rect = resp.response.rect;
+          gpu_driver.request_view(viewport.view, resp.inner, [rect.width() as u32, rect.height() as u32]);
+        });
+    }
+
+    for viewport in self.viewports.viewports.iter().filter(|v| !v.open) {
+      gpu_driver.remove_view(viewport.view);
+    }
+  }
+
+  /// Registers new view textures and refreshes the others. Textures of closed
+  /// windows are freed.
+  pub(in super) fn update_viewport_textures(
+    &mut self,
+    renderer: &mut egui_wgpu::Renderer,
+    gpu_driver: &GPUDriver,
+    device: &wgpu::Device
+  ) {
+    let texture_views: Vec<_> = self.viewports.viewports.iter()
+      .map(|viewport| gpu_driver.view_texture(viewport.view))
+      .collect();
+
+    for (viewport, texture_view) in self.viewports.viewports.iter_mut().zip(texture_views) {
+      match (viewport.open, viewport.texture_id, texture_view) {
+        (false, Some(texture_id), _) => renderer.free_texture(&texture_id),
+        (true, Some(texture_id), Some(view)) =>
+          renderer.update_egui_texture_from_wgpu_texture(device, &view, wgpu::FilterMode::Nearest, texture_id),
+        (true, None, Some(view)) =>
+          viewport.texture_id = Some(renderer.register_native_texture(device, &view, wgpu::FilterMode::Nearest)),
+        _ => {},
+      }
+    }
+    self.viewports.viewports.retain(|viewport| viewport.open);
+  }
+}
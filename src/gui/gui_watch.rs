@@ -0,0 +1,138 @@
+use {
+  eframe::egui::{
+    self,
+    plot::{PlotPoints, PlotUi, Polygon},
+    Color32,
+  },
+  crate::{
+    gpu::{GPUDriver, Watchpoint, WatchCondition, WatchHit, MAX_WATCHPOINTS},
+    pattern::CellRect,
+  },
+};
+
+pub(in super) struct WatchState {
+  watchpoints: Vec<Watchpoint>,
+  edit_cell: [i64; 2],
+  edit_states: [u8; 2],
+  last_hit: Option<WatchHit>,
+  status: String,
+}
+
+impl Default for WatchState {
+  fn default() -> Self {
+    Self {
+      watchpoints: vec![],
+      edit_cell: [0, 0],
+      // excited OTS
+      edit_states: [13, 16],
+      last_hit: None,
+      status: String::new(),
+    }}}
+
+fn describe(watchpoint: &Watchpoint) -> String {
+  let r = watchpoint.rect;
+  match watchpoint.condition {
+    WatchCondition::Enters(lo, hi) => format!("({}, {}) enters {}..={}", r.x, r.y, lo, hi),
+    WatchCondition::Changes => format!("{}x{} at ({}, {}) changes", r.width, r.height, r.x, r.y),
+  }
+}
+
+impl super::GUI {
+  /// Uploads the watchpoints and pauses on the earliest hit. With rewind
+  /// enabled, the simulation is brought back to the generation of the hit.
+  pub(in super) fn watch_control(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    gpu_driver.set_watchpoints(queue, &self.watch.watchpoints);
+
+    let Some(hit) = gpu_driver.poll_watch_hits().into_iter().min_by_key(|hit| hit.generation) else {
+      return;
+    };
+    if !self.compute_requested {
+      return;
+    }
+    self.compute_requested = false;
+    self.run.target = None;
+
+    let back = gpu_driver.generation.saturating_sub(hit.generation);
+    if let Some((len, 0)) = gpu_driver.rewind_state() {
+      if back < len as u64 {
+        gpu_driver.rewind_seek(device, queue, back as u32);
+      }
+    }
+    self.watch.status = format!(
+      "#{} hit at generation {}, cell ({}, {})",
+      hit.watchpoint, hit.generation, hit.cell[0], hit.cell[1]
+    );
+    self.watch.last_hit = Some(hit);
+  }
+
+  fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+    if self.watch.watchpoints.len() < MAX_WATCHPOINTS {
+      self.watch.watchpoints.push(watchpoint);
+    }
+  }
+
+  pub(in super) fn watch_panel_ui(&mut self, ui: &mut egui::Ui) {
+    ui.horizontal_wrapped(|ui| {
+      ui.label("cell: ");
+      ui.add(egui::DragValue::new(&mut self.watch.edit_cell[0]).prefix("x "));
+      ui.add(egui::DragValue::new(&mut self.watch.edit_cell[1]).prefix("y "));
+    });
+    ui.horizontal_wrapped(|ui| {
+      ui.label("enters: ");
+      ui.add(egui::DragValue::new(&mut self.watch.edit_states[0]).clamp_range(0..=31));
+      ui.label("..=");
+      ui.add(egui::DragValue::new(&mut self.watch.edit_states[1]).clamp_range(0..=31));
+    });
+    ui.horizontal_wrapped(|ui| {
+      let can_add = self.watch.watchpoints.len() < MAX_WATCHPOINTS;
+      let [x, y] = self.watch.edit_cell;
+      let [lo, hi] = self.watch.edit_states;
+      ui.add_enabled(can_add, egui::Button::new("+ cell"))
+        .clicked().then(|| self.add_watchpoint(Watchpoint {
+          rect: CellRect { x, y, width: 1, height: 1 },
+          condition: WatchCondition::Enters(lo.min(hi), lo.max(hi)),
+        }));
+      ui.add_enabled(can_add && self.edit.selection.is_some(), egui::Button::new("+ selection"))
+        .on_hover_text_at_pointer("pause when any cell of the selection changes")
+        .clicked().then(|| if let Some(rect) = self.edit.selection {
+          self.add_watchpoint(Watchpoint { rect, condition: WatchCondition::Changes });
+        });
+    });
+
+    let mut removed = None;
+    for (i, watchpoint) in self.watch.watchpoints.iter().enumerate() {
+      ui.horizontal(|ui| {
+        ui.small_button("✖").clicked().then(|| removed = Some(i));
+        ui.label(format!("#{i} {}", describe(watchpoint)));
+      });
+    }
+    if let Some(i) = removed {
+      self.watch.watchpoints.remove(i);
+      self.watch.last_hit = None;
+    }
+    if !self.watch.status.is_empty() {
+      ui.label(&self.watch.status);
+    }
+  }
+
+  /// Watched areas, and the cell of the last hit.
+  pub(in super) fn watch_plot_ui(&self, ui: &mut PlotUi) {
+    for watchpoint in &self.watch.watchpoints {
+      ui.polygon(
+        Polygon::new(PlotPoints::from(watchpoint.rect.plot_corners().to_vec()))
+          .color(Color32::from_rgb(255, 160, 0))
+          .fill_alpha(0.0)
+          .name("Watchpoints")
+      );
+    }
+    if let Some(hit) = self.watch.last_hit {
+      let rect = CellRect { x: hit.cell[0] as i64, y: hit.cell[1] as i64, width: 1, height: 1 };
+      ui.polygon(
+        Polygon::new(PlotPoints::from(rect.plot_corners().to_vec()))
+          .color(Color32::RED)
+          .fill_alpha(0.5)
+          .name("Watchpoint hit")
+      );
+    }
+  }
+}
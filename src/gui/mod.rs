@@ -0,0 +1,665 @@
+use {
+  std::{ops::RangeInclusive, sync::{Arc, Mutex}},
+  eframe::{
+    CreationContext,
+    egui::{
+      self,
+      plot::{self, Plot, PlotImage, PlotBounds},
+      RichText, TextStyle, TextureId,
+      TopBottomPanel, CollapsingHeader, CentralPanel, SidePanel
+    },
+    emath::Vec2,
+    Storage,
+  },
+  crate::{
+    gpu::{self, GPUDriver, SharedDriver, SimulationWorker},
+    history::History,
+    rule::Rule,
+  },
+  gui_actions::Action,
+};
+
+mod gui_actions;
+mod gui_annotation;
+mod gui_assembler;
+mod gui_command_palette;
+mod gui_disassembler;
+mod gui_edit;
+mod gui_export;
+mod gui_follow;
+mod gui_history;
+mod gui_keymap;
+mod gui_minimap;
+mod gui_palette;
+mod gui_population;
+mod gui_probe;
+mod gui_profile;
+mod gui_record;
+mod gui_render;
+mod gui_rewind;
+mod gui_run;
+mod gui_snapshot;
+mod gui_speed;
+mod gui_viewports;
+mod gui_watch;
+
+#[allow(clippy::upper_case_acronyms)]
+pub struct GUI {
+  adapter_info: Option<wgpu::AdapterInfo>,
+  /// Advances the simulation, the generations each frame hands it or at its
+  /// own pace while `speed.background` is set, joined on drop.
+  _worker: SimulationWorker,
+  compute_requested: bool,
+  texture_id: TextureId,
+
+  edit_iters_frame: String,
+  /// Last valid value of `edit_iters_frame`.
+  iters_frame: u64,
+  t0: Option<std::time::Instant>,
+  frame_stats: FrameStats,
+  /// Plot bounds of the last frame.
+  view_bounds: PlotBounds,
+  /// Plot bounds to be applied on the next frame.
+  pending_bounds: Option<PlotBounds>,
+
+  edit_snapshot_path: String,
+  snapshot_status: String,
+
+  annotation: gui_annotation::AnnotationState,
+  assembler: gui_assembler::AssemblerState,
+  command_palette: gui_command_palette::CommandPalette,
+  disassembler: gui_disassembler::DisassemblerState,
+  edit: gui_edit::EditState,
+  export: gui_export::ExportState,
+  follow: gui_follow::FollowState,
+  history: History,
+  keymap: gui_keymap::KeymapState,
+  rewind: gui_rewind::RewindSettings,
+  run: gui_run::RunControl,
+  speed: gui_speed::SpeedState,
+  watch: gui_watch::WatchState,
+  probe: gui_probe::ProbeState,
+  profile: gui_profile::ProfileState,
+  record: gui_record::RecordState,
+  population: gui_population::PopulationState,
+  palette: gui_palette::PaletteState,
+  minimap: gui_minimap::Minimap,
+  viewports: gui_viewports::ViewportsState,
+  debug_windows: DebugWingows
+}
+
+/// Throughput measured between frames, smoothed.
+#[derive(Default)]
+struct FrameStats {
+  last_frame: Option<std::time::Instant>,
+  last_generation: u64,
+  frame_time: f64,
+  gens_per_second: f64,
+}
+
+impl FrameStats {
+  fn update(&mut self, generation: u64) {
+    const SMOOTHING: f64 = 0.1;
+    let now = std::time::Instant::now();
+    if let Some(last_frame) = self.last_frame {
+      let dt = (now - last_frame).as_secs_f64().max(1e-6);
+      let gens = generation.saturating_sub(self.last_generation) as f64;
+      self.frame_time += (dt - self.frame_time) * SMOOTHING;
+      self.gens_per_second += (gens / dt - self.gens_per_second) * SMOOTHING;
+    }
+    self.last_frame = Some(now);
+    self.last_generation = generation;
+  }
+}
+
+#[derive(Default)]
+struct DebugWingows {
+  ui_settings: bool,
+  inspection: bool,
+  memory: bool
+}
+
+impl GUI {
+  pub fn new<'a>(cc: &'a CreationContext<'a>) -> Option<Self> {
+    let wgpu_render_state = cc.wgpu_render_state.as_ref()?;
+
+    let adapter_info = wgpu::Instance::new(wgpu::Backends::all())
+      .enumerate_adapters(wgpu::Backends::all())
+      .next().map(|a| a.get_info());
+
+    let device = &wgpu_render_state.device;
+    let target_format = wgpu_render_state.target_format;
+
+    let edit_iters_frame = cc.storage.and_then(|s| s.get_string("edit_iters_frame"))
+      .unwrap_or("1".to_string());
+    let edit_snapshot_path = cc.storage.and_then(|s| s.get_string("edit_snapshot_path"))
+      .unwrap_or("./snapshot.h32s".to_string());
+    let mut probe = gui_probe::ProbeState::default();
+    if let Some(path) = cc.storage.and_then(|s| s.get_string("edit_probe_path")) {
+      probe.edit_export_path = path;
+    }
+    let mut profile = gui_profile::ProfileState::default();
+    if let Some(path) = cc.storage.and_then(|s| s.get_string("edit_profile_path")) {
+      profile.edit_path = path;
+    }
+    let mut assembler = gui_assembler::AssemblerState::default();
+    if let Some(source) = cc.storage.and_then(|s| s.get_string("assembler_source")) {
+      assembler.source = source;
+    }
+    if let Some(bits) = cc.storage.and_then(|s| s.get_string("loop_bits")) {
+      assembler.edit_loop_bits = bits;
+    }
+    let mut annotation = gui_annotation::AnnotationState::default();
+    if let Some(path) = cc.storage.and_then(|s| s.get_string("edit_annotation_path")) {
+      annotation.edit_path = path;
+    }
+    let mut export = gui_export::ExportState::default();
+    if let Some(path) = cc.storage.and_then(|s| s.get_string("edit_export_path")) {
+      export.edit_path = path;
+    }
+    let mut record = gui_record::RecordState::default();
+    if let Some(path) = cc.storage.and_then(|s| s.get_string("edit_record_path")) {
+      record.edit_path = path;
+    }
+    let mut palette = gui_palette::PaletteState::default();
+    if let Some(path) = cc.storage.and_then(|s| s.get_string("edit_palette_path")) {
+      palette.edit_path = path;
+    }
+    if let Some(Ok(colors)) = cc.storage.and_then(|s| s.get_string("palette"))
+      .map(|text| palette.palette.with_golly_colors(&text)) {
+      palette.palette = colors;
+    }
+    let mut speed = gui_speed::SpeedState::default();
+    if let Some(mode) = cc.storage.and_then(|s| s.get_string("speed_mode"))
+      .and_then(|id| gui_speed::SpeedMode::from_id(&id)) {
+      speed.mode = mode;
+    }
+    let stored = |key, range: RangeInclusive<f64>| cc.storage.and_then(|s| s.get_string(key))
+      .and_then(|s| s.parse::<f64>().ok())
+      .filter(|value| !value.is_nan())
+      .map(|value| value.clamp(*range.start(), *range.end()));
+    if let Some(rate) = stored("target_gens_per_second", gui_speed::TARGET_RATE_RANGE) {
+      speed.target_gens_per_second = rate;
+    }
+    if let Some(budget) = stored("frame_budget_ms", gui_speed::FRAME_BUDGET_RANGE) {
+      speed.frame_budget_ms = budget;
+    }
+    if let Some(background) = cc.storage.and_then(|s| s.get_string("run_in_background"))
+      .and_then(|s| s.parse::<bool>().ok()) {
+      speed.background = background;
+    }
+    let keymap = cc.storage.and_then(|s| s.get_string("keybindings"))
+      .map(|text| gui_keymap::Keymap::from_text(&text))
+      .unwrap_or_default();
+    let mut history = History::default();
+    if let Some(budget_mb) = cc.storage.and_then(|s| s.get_string("history_budget_mb"))
+      .and_then(|s| s.parse::<usize>().ok()) {
+      history.set_budget_bytes(budget_mb.saturating_mul(1024 * 1024));
+    }
+
+    let mut gpu_driver = GPUDriver::new(device, &wgpu_render_state.queue, target_format);
+    gpu_driver.load_simulation(device, &wgpu_render_state.queue);
+    gpu_driver.simulatiion_steps_per_call = edit_iters_frame.parse().unwrap_or(1);
+    let gpu_driver_steps = gpu_driver.simulatiion_steps_per_call;
+
+    let minimap_view = gpu_driver.add_view(device);
+    let (texture_id, minimap_texture_id) = {
+      let mut renderer = wgpu_render_state.renderer.write();
+      (
+        renderer.register_native_texture(device, &gpu_driver.create_view(), wgpu::FilterMode::Nearest),
+        renderer.register_native_texture(device, &gpu_driver.view_texture(minimap_view)?, wgpu::FilterMode::Linear)
+      )
+    };
+
+    let gpu_driver: SharedDriver = Arc::new(Mutex::new(gpu_driver));
+    let worker = SimulationWorker::spawn(gpu_driver.clone(), wgpu_render_state.device.clone(), wgpu_render_state.queue.clone());
+    wgpu_render_state
+      .renderer
+      .write()
+      .paint_callback_resources
+      .insert(gpu_driver);
+
+    configure_text_styles(&cc.egui_ctx);
+
+    Some(Self {
+      adapter_info,
+      _worker: worker,
+      compute_requested: false,
+      texture_id,
+
+      iters_frame: gpu_driver_steps,
+      edit_iters_frame,
+      t0: None,
+      frame_stats: FrameStats::default(),
+      view_bounds: PlotBounds::NOTHING,
+      pending_bounds: None,
+
+      edit_snapshot_path,
+      snapshot_status: String::new(),
+
+      annotation,
+      assembler,
+      command_palette: gui_command_palette::CommandPalette::default(),
+      disassembler: gui_disassembler::DisassemblerState::default(),
+      edit: gui_edit::EditState::default(),
+      export,
+      follow: gui_follow::FollowState::default(),
+      history,
+      keymap: gui_keymap::KeymapState::new(keymap),
+      rewind: gui_rewind::RewindSettings::default(),
+      run: gui_run::RunControl::default(),
+      speed,
+      watch: gui_watch::WatchState::default(),
+      probe,
+      profile,
+      record,
+      population: gui_population::PopulationState::default(),
+      palette,
+      minimap: gui_minimap::Minimap { view: minimap_view, texture_id: minimap_texture_id },
+      viewports: gui_viewports::ViewportsState::default(),
+      debug_windows: DebugWingows::default()
+    })
+  }
+
+  fn on_start_click(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    self.on_edit_iters_frame_changed(gpu_driver);
+    if !self.compute_requested {
+      self.record_snapshot(gpu_driver, device, queue);
+    }
+    self.compute_requested = !self.compute_requested;
+    self.run.target = None;
+    self.t0 = if self.t0.is_none() && self.compute_requested {
+      Some(std::time::Instant::now())
+    } else {
+      None
+    };
+  }
+
+  fn on_reset_click(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    self.record_snapshot(gpu_driver, device, queue);
+    self.reset_simulation(gpu_driver, device, queue);
+  }
+
+  fn reset_simulation(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    gpu_driver.load_simulation(device, queue);
+    self.on_edit_iters_frame_changed(gpu_driver);
+    self.t0 = None;
+  }
+
+  fn on_step_click(&mut self, gpu_driver: &mut GPUDriver, device: &wgpu::Device, queue: &wgpu::Queue) {
+    self.record_snapshot(gpu_driver, device, queue);
+    self.compute_requested = true;
+    self.run.target = Some(gpu_driver.generation + 1);
+    self.speed.grant_step();
+  }
+
+  fn on_recomple_click(
+    &mut self,
+    gpu_driver: &mut GPUDriver,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    target_format: wgpu::TextureFormat
+  ) {
+    self.record_snapshot(gpu_driver, device, queue);
+    *gpu_driver = GPUDriver::new(device, queue, target_format);
+    self.recreate_views(gpu_driver, device);
+    self.reset_simulation(gpu_driver, device, queue);
+    self.apply_rewind_settings(gpu_driver, device, queue);
+  }
+
+  fn on_edit_iters_frame_changed(&mut self, gpu_driver: &mut GPUDriver) {
+    match self.edit_iters_frame.trim().parse::<u64>() {
+      Ok(step_zize @ 1..=gpu::MAX_STEPS_PER_CALL) => {
+        self.iters_frame = step_zize;
+        gpu_driver.simulatiion_steps_per_call = step_zize;
+        self.speed.error = None;
+      },
+      _ => self.speed.error = Some(format!(
+        "\"{}\" is not a whole number in 1..={}, still at {}",
+        self.edit_iters_frame, gpu::MAX_STEPS_PER_CALL, self.iters_frame
+      )),
+    }
+  }
+}
+
+impl eframe::App for GUI {
+  fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+    frame.set_window_title("GPU Accelerated CA");
+
+    let render_state = frame.wgpu_render_state().unwrap();
+    let mut renderer = render_state.renderer.write();
+    let device = render_state.device.as_ref();
+    let queue = render_state.queue.as_ref();
+    let shared_driver = renderer.paint_callback_resources.get::<SharedDriver>().unwrap().clone();
+    let mut driver_guard = shared_driver.lock().unwrap();
+    let gpu_driver = &mut *driver_guard;
+
+    device.poll(wgpu::Maintain::Poll);
+
+    self.frame_stats.update(gpu_driver.generation);
+
+    let target_format = render_state.target_format;
+    for action in self.triggered_actions(ctx) {
+      self.run_action(action, gpu_driver, device, queue, target_format);
+    }
+
+    TopBottomPanel::top("control buttons").show(ctx, |ui| {
+      ui.add_space(1.0);
+
+      ui.horizontal_wrapped(|ui| {
+        ui.style_mut().visuals.button_frame = false;
+
+        ui.button(if !self.compute_requested { "▶ Start" } else { "⏸ Stop" })
+          .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::StartStop))
+          .clicked().then(|| self.on_start_click(gpu_driver, device, queue));
+
+        ui.label("|");
+
+        ui.button("↺  Reset")
+          .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::Reset))
+          .clicked().then(|| self.on_reset_click(gpu_driver, device, queue));
+
+        ui.label("|");
+
+        ui.button("▶|| Step")
+          .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::Step))
+          .clicked().then(|| self.on_step_click(gpu_driver, device, queue));
+
+        ui.label("|");
+
+        ui.add_enabled(
+          gpu_driver.rewind_state().is_some_and(|(len, cursor)| cursor + 1 < len),
+          egui::Button::new("◀ Step back")
+        )
+          .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::StepBack))
+          .clicked().then(|| self.on_step_back_click(gpu_driver, device, queue));
+
+        ui.label("|");
+
+        ui.button("< / > Recompile")
+          .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::Recompile))
+          .clicked().then(|| self.on_recomple_click(gpu_driver, device, queue, target_format));
+
+        ui.label("|");
+
+        ui.button("🔍 Commands")
+          .on_hover_text_at_pointer(self.keymap.keymap.hint(Action::CommandPalette))
+          .clicked().then(|| self.command_palette.open());
+      });
+
+      ui.add_space(1.0);
+    });
+
+    SidePanel::left("left_panel")
+      .default_width(180.0)
+      .show(ctx, |ui| {
+        ui.add_space(10.0);
+        self.speed_panel_ui(ui, gpu_driver);
+
+        ui.add_space(10.0);
+        ui.label("\
+          LMB: pan\n\
+          Ctrl+Scroll: zoom\n\
+          RMB: boxed zoom mode\n\
+          Shift+LMB: select\n"
+        );
+        ui.separator();
+        CollapsingHeader::new("Overview")
+          .default_open(true)
+          .show(ui, |ui| {
+            self.minimap_ui(ui, gpu_driver);
+            ui.button("🗖 New view window")
+              .on_hover_text_at_pointer("another view onto the same simulation")
+              .clicked().then(|| self.on_new_viewport_click(gpu_driver, device));
+          });
+        CollapsingHeader::new("Follow")
+          .default_open(false)
+          .show(ui, |ui| self.follow_panel_ui(ui, gpu_driver));
+        CollapsingHeader::new("Render")
+          .default_open(false)
+          .show(ui, |ui| self.render_panel_ui(ui, gpu_driver));
+        CollapsingHeader::new("Palette")
+          .default_open(false)
+          .show(ui, |ui| self.palette_panel_ui(ui));
+        CollapsingHeader::new("Selection")
+          .default_open(true)
+          .show(ui, |ui| self.edit_panel_ui(ui, gpu_driver, device, queue));
+        CollapsingHeader::new("Snapshot")
+          .default_open(false)
+          .show(ui, |ui| self.snapshot_panel_ui(ui, gpu_driver, device, queue));
+        CollapsingHeader::new("Annotations")
+          .default_open(false)
+          .show(ui, |ui| self.annotation_panel_ui(ui));
+        CollapsingHeader::new("Export image")
+          .default_open(false)
+          .show(ui, |ui| self.export_panel_ui(ui, gpu_driver, device, queue));
+        CollapsingHeader::new("Record")
+          .default_open(false)
+          .show(ui, |ui| self.record_panel_ui(ui, gpu_driver));
+        CollapsingHeader::new("Rewind")
+          .default_open(false)
+          .show(ui, |ui| self.rewind_panel_ui(ui, gpu_driver, device, queue));
+        CollapsingHeader::new("Run")
+          .default_open(false)
+          .show(ui, |ui| self.run_panel_ui(ui, gpu_driver, device, queue));
+        CollapsingHeader::new("Watchpoints")
+          .default_open(false)
+          .show(ui, |ui| self.watch_panel_ui(ui));
+        CollapsingHeader::new("Probes")
+          .default_open(false)
+          .show(ui, |ui| self.probe_panel_ui(ui, gpu_driver));
+        CollapsingHeader::new("Populations")
+          .default_open(false)
+          .show(ui, |ui| self.population_panel_ui(ui));
+        CollapsingHeader::new("Profiling")
+          .default_open(false)
+          .show(ui, |ui| self.profile_panel_ui(ui, gpu_driver));
+        CollapsingHeader::new("History")
+          .default_open(false)
+          .show(ui, |ui| self.history_panel_ui(ui, gpu_driver, device, queue));
+        ui.separator();
+        ui.add_space(10.0);
+        CollapsingHeader::new("Statistics")
+          .default_open(true)
+          .show(ui, |ui| ui.scope(|ui| {
+            //ui.style_mut().wrap = Some(false);
+            ui.label(RichText::new(format!("\
+              device: {}\n\
+              rule: {}\n\
+              generation: {}\n\
+              gens/s: {:.1}\n\
+              frame_time: {:.2}ms\n\
+              texture_size: {:?}\n\
+              simulation_size: {:?}\n\
+              T: {:.3}s",
+              self.adapter_info.as_ref().map(|a| a.name.as_ref()).unwrap_or(""),
+              Rule::ACTIVE.name(),
+              gpu_driver.generation,
+              self.frame_stats.gens_per_second,
+              self.frame_stats.frame_time * 1000.0,
+              gpu_driver.texture_size,
+              gpu_driver.uniforms.simulation_dimm,
+              self.t0.map(|t0| t0.elapsed().as_secs_f64()).unwrap_or(0.0)
+            )).text_style(TextStyle::Name("mono_small".into())))
+          }));
+
+        ui.add_space(10.0);
+        ui.checkbox(&mut self.assembler.show, "📼 Construction assembler");
+        ui.checkbox(&mut self.disassembler.show, "📼 Tape disassembler");
+        ui.checkbox(&mut self.keymap.show_editor, "⌨ Keybindings");
+        ui.checkbox(&mut self.debug_windows.ui_settings, "🔧 UI Settings");
+        ui.checkbox(&mut self.debug_windows.inspection, "🔍 Inspection");
+        ui.checkbox(&mut self.debug_windows.memory, "📝 Memory");
+
+        egui::Window::new("🔧 UI Settings")
+          .open(&mut self.debug_windows.ui_settings)
+          .vscroll(true)
+          .show(ctx, |ui| {
+            ctx.settings_ui(ui);
+          });
+        egui::Window::new("🔍 Inspection")
+          .open(&mut self.debug_windows.inspection)
+          .vscroll(true)
+          .show(ctx, |ui| {
+            ctx.inspection_ui(ui);
+          });
+        egui::Window::new("📝 Memory")
+          .open(&mut self.debug_windows.memory)
+          .resizable(false)
+          .show(ctx, |ui| {
+            ctx.memory_ui(ui);
+          });
+      });
+
+    self.probe_trace_window(ctx);
+    self.population_plot_window(ctx);
+    self.palette_editor_window(ctx);
+    self.assembler_window(ctx);
+    self.disassembler_window(ctx, gpu_driver, device, queue);
+    self.keymap_editor_window(ctx);
+    if let Some(action) = self.command_palette_window(ctx) {
+      self.run_action(action, gpu_driver, device, queue, target_format);
+    }
+    self.viewport_windows(ctx, gpu_driver);
+    self.palette_control(gpu_driver, queue);
+
+    // Last chance to stop or shorten the run before the callback is issued.
+    self.speed_control();
+    self.watch_control(gpu_driver, device, queue);
+    self.run_control(gpu_driver);
+    self.record_control(gpu_driver, device, queue);
+    self.probe_control(gpu_driver, queue);
+    self.population_control(gpu_driver);
+    self.disassembler_control(gpu_driver, device, queue);
+    self.profile_control(gpu_driver);
+    self.follow_control(ctx, gpu_driver, queue);
+    self.worker_control(gpu_driver);
+
+    let simulation_dimm = gpu_driver.uniforms.simulation_dimm;
+    let selecting = ctx.input().modifiers.shift;
+
+    CentralPanel::default().show(ctx, |ui| {
+      let mut bounds = PlotBounds::NOTHING;
+      let resp = viewer_plot("my_plot", simulation_dimm)
+        .allow_drag(!selecting)
+        .show(ui, |ui| {
+          if let Some(pending_bounds) = self.pending_bounds.take() {
+            ui.set_plot_bounds(pending_bounds);
+          }
+          bounds = ui.plot_bounds();
+
+          // Render the plot texture filling the viewport.
+          ui.image(
+            PlotImage::new(
+              self.texture_id,
+              bounds.center(),
+              [bounds.width() as f32, bounds.height() as f32],
+            ).name("Game of Life (GPU)"),
+          );
+
+          self.watch_plot_ui(ui);
+          self.probe_plot_ui(ui);
+          self.follow_plot_ui(ui);
+          self.disassembler_plot_ui(ui);
+          self.annotation_plot_ui(ui);
+          self.edit_plot_ui(ui, selecting, gpu_driver, device, queue);
+        });
+
+      self.view_bounds = bounds;
+
+      // Add a callback to egui to render the plot contents to
+      // texture.
+      ui.painter().add(gpu::egui_wgpu_callback(
+        bounds,
+        resp.response.rect,
+      ));
+    });
+
+    // Update the texture handle in egui from the previously
+    // rendered texture (from the last frame).
+    let texture_view = gpu_driver.create_view();
+    let minimap_view = gpu_driver.view_texture(self.minimap.view);
+    renderer.update_egui_texture_from_wgpu_texture(
+      &render_state.device,
+      &texture_view,
+      wgpu::FilterMode::Nearest,
+      self.texture_id,
+    );
+    if let Some(minimap_view) = minimap_view {
+      renderer.update_egui_texture_from_wgpu_texture(
+        &render_state.device,
+        &minimap_view,
+        wgpu::FilterMode::Linear,
+        self.minimap.texture_id,
+      );
+    }
+
+    self.update_viewport_textures(&mut renderer, gpu_driver, device);
+
+    self.compute_requested.then(||
+      self.request_next_frame(ctx)
+    );
+  }
+
+  // save app state on exit
+  fn save(&mut self, storage: &mut dyn Storage) {
+    storage.set_string("edit_iters_frame", self.edit_iters_frame.clone());
+    storage.set_string("edit_snapshot_path", self.edit_snapshot_path.clone());
+    storage.set_string("edit_probe_path", self.probe.edit_export_path.clone());
+    storage.set_string("edit_profile_path", self.profile.edit_path.clone());
+    storage.set_string("assembler_source", self.assembler.source.clone());
+    storage.set_string("loop_bits", self.assembler.edit_loop_bits.clone());
+    storage.set_string("edit_annotation_path", self.annotation.edit_path.clone());
+    storage.set_string("edit_export_path", self.export.edit_path.clone());
+    storage.set_string("edit_record_path", self.record.edit_path.clone());
+    storage.set_string("edit_palette_path", self.palette.edit_path.clone());
+    storage.set_string("palette", self.palette.palette.to_golly_colors(true));
+    storage.set_string("keybindings", self.keymap.keymap.to_text());
+    storage.set_string("speed_mode", self.speed.mode.id().to_string());
+    storage.set_string("target_gens_per_second", self.speed.target_gens_per_second.to_string());
+    storage.set_string("frame_budget_ms", self.speed.frame_budget_ms.to_string());
+    storage.set_string("run_in_background", self.speed.background.to_string());
+    storage.set_string("history_budget_mb", (self.history.budget_bytes() / (1024 * 1024)).to_string());
+  }
+}
+
+/// Plot showing the simulation, shared by the main view and view windows.
+fn viewer_plot(id: impl std::hash::Hash, simulation_dimm: [u32; 2]) -> Plot {
+  Plot::new(id)
+    //.legend(Legend::default())
+    .data_aspect(1.0)
+    // Must set margins to zero or the image and plot bounds will
+    // constantly fight, expanding the plot to infinity.
+    .set_margin_fraction(Vec2::new(0.0, 0.0))
+    .include_x(simulation_dimm[0] as f64 * -0.33)
+    .include_x(simulation_dimm[0] as f64 * 1.33)
+    .include_y(simulation_dimm[1] as f64 * 0.33)
+    .include_y(simulation_dimm[1] as f64 * -1.33)
+    .x_grid_spacer(plot::log_grid_spacer(16))
+    .y_grid_spacer(plot::log_grid_spacer(16))
+    .coordinates_formatter(
+      plot::Corner::LeftTop,
+      plot::CoordinatesFormatter::new(move |pt, _|
+        format!("x = {}\ny = {}", pt.x as i64, pt.y as i64, )
+      ))
+    .show_x(false)
+    .show_y(false)
+    .allow_scroll(false)
+    .x_axis_formatter(move |x, _| if x >= 0.0 { x.to_string() } else { "".to_string() })
+    .y_axis_formatter(move |y, _| if y <= 0.0 { (-y).to_string() } else { "".to_string() })
+}
+
+fn configure_text_styles(ctx: &egui::Context) {
+  use egui::{FontFamily::{Proportional, Monospace}, FontId};
+
+  let mut style = (*ctx.style()).clone();
+  style.text_styles = [
+    (TextStyle::Heading, FontId::new(25.0, Proportional)),
+    (TextStyle::Body, FontId::new(12.0, Proportional)),
+    (TextStyle::Monospace, FontId::new(12.0, Monospace)),
+    (TextStyle::Button, FontId::new(12.0, Proportional)),
+    (TextStyle::Small, FontId::new(8.0, Proportional)),
+    (TextStyle::Name("mono_small".into()), FontId::new(8.0, Monospace)),
+  ].into();
+  ctx.set_style(style);
+}
@@ -0,0 +1,332 @@
+use {
+  std::{fs::File, io::{BufWriter, Write}, time::Instant},
+  crate::{
+    annotation::{self, Annotation},
+    gpu::{GPUDriver, Watchpoint, WatchCondition, MAX_STEPS_PER_CALL, OVERLAY_GLYPHS, OVERLAY_GRID, STATES},
+    pattern::CellRect,
+    probe::ProbeTrace,
+    profile::ProfileTotals,
+    recording::{Recording, RecordingFormat},
+    rule::Rule,
+  },
+};
+
+const USAGE: &str = "\
+usage: hutton32-gpu --headless [options]
+  --generations N       stop at generation N (default 8192)
+  --iters K             generations per submission, 1..=512 (default 32)
+  --watch X,Y,LO-HI     stop when cell (X, Y) enters a state in LO..=HI
+  --watch-rect X,Y,W,H  stop when any cell in the rectangle changes
+  --probe X,Y           record the state of cell (X, Y) every generation
+  --trace PATH          write the probe trace to PATH.csv and PATH.vcd
+  --populations PATH    write the cells per state of every generation to PATH
+  --export PATH         write a PNG of the region once the run stops
+  --record PATH         record the region to PATH, a .gif, an .apng/.png
+                        animation, or else a sequence of PATH_NNNNN.png
+  --every K             record a frame every K generations (default 1)
+  --fps N               frame rate of the recording (default 10)
+  --region X,Y,W,H      cells to export (default the whole simulation)
+  --scale N             pixels per cell of the export (default 4)
+  --glyphs              draw cell glyphs in the export
+  --grid                draw grid lines in the export
+  --annotations PATH    draw the annotations of PATH over the export
+  --profile PATH        time the simulation, with GPU timestamps if supported,
+                        and append the figures to the CSV at PATH";
+
+struct Options {
+  generations: u64,
+  iters: u64,
+  watchpoints: Vec<Watchpoint>,
+  probes: Vec<[u32; 2]>,
+  trace_path: Option<String>,
+  populations_path: Option<String>,
+  export_path: Option<String>,
+  export_region: Option<CellRect>,
+  export_scale: u32,
+  export_overlay: u32,
+  record_path: Option<String>,
+  record_every: u64,
+  record_fps: u32,
+  annotations: Vec<Annotation>,
+  profile_path: Option<String>,
+}
+
+fn parse_numbers<const N: usize>(s: &str) -> Option<[i64; N]> {
+  let numbers: Vec<i64> = s.split(',')
+    .map(|n| n.trim().parse().ok())
+    .collect::<Option<_>>()?;
+  numbers.try_into().ok()
+}
+
+/// `X,Y,W,H` with a non-empty size.
+fn parse_rect(s: &str) -> Option<CellRect> {
+  let [x, y, width, height] = parse_numbers(s)?;
+  let size = |n: i64| u32::try_from(n).ok().filter(|&n| n > 0);
+  Some(CellRect { x, y, width: size(width)?, height: size(height)? })
+}
+
+impl Options {
+  fn parse(args: &[String]) -> Result<Self, String> {
+    let mut options = Options {
+      generations: 8192,
+      iters: 32,
+      watchpoints: vec![],
+      probes: vec![],
+      trace_path: None,
+      populations_path: None,
+      export_path: None,
+      export_region: None,
+      export_scale: 4,
+      export_overlay: 0,
+      record_path: None,
+      record_every: 1,
+      record_fps: 10,
+      annotations: vec![],
+      profile_path: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+      let mut value = || args.next().ok_or(format!("missing value for {arg}\n{USAGE}"));
+      match arg.as_str() {
+        "--generations" => {
+          options.generations = value()?.parse().map_err(|_| format!("invalid {arg}"))?;
+        },
+        "--iters" => {
+          options.iters = match value()?.parse() {
+            Ok(iters @ 1..=MAX_STEPS_PER_CALL) => iters,
+            _ => return Err(format!("invalid {arg}")),
+          };
+        },
+        "--watch" => {
+          let (cell, states) = value()?.rsplit_once(',').ok_or(format!("invalid {arg}"))?;
+          let [x, y] = parse_numbers(cell).ok_or(format!("invalid {arg}"))?;
+          let (lo, hi) = states.split_once('-').ok_or(format!("invalid {arg}, expected a LO-HI state range"))?;
+          let state = |n: &str| n.trim().parse::<u8>().ok().filter(|&state| (state as usize) < STATES);
+          let (Some(lo), Some(hi)) = (state(lo), state(hi)) else {
+            return Err(format!("invalid {arg}, states are 0-{}", STATES - 1));
+          };
+          options.watchpoints.push(Watchpoint {
+            rect: CellRect { x, y, width: 1, height: 1 },
+            condition: WatchCondition::Enters(lo, hi),
+          });
+        },
+        "--watch-rect" => {
+          options.watchpoints.push(Watchpoint {
+            rect: parse_rect(value()?).ok_or(format!("invalid {arg}"))?,
+            condition: WatchCondition::Changes,
+          });
+        },
+        "--probe" => {
+          let [x, y] = parse_numbers(value()?).ok_or(format!("invalid {arg}"))?;
+          let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+            return Err(format!("invalid {arg}, ({x}, {y}) lies outside the simulation"));
+          };
+          options.probes.push([x, y]);
+        },
+        "--trace" => {
+          options.trace_path = Some(value()?.clone());
+        },
+        "--populations" => {
+          options.populations_path = Some(value()?.clone());
+        },
+        "--export" => {
+          options.export_path = Some(value()?.clone());
+        },
+        "--record" => {
+          options.record_path = Some(value()?.clone());
+        },
+        "--every" => {
+          options.record_every = match value()?.parse() {
+            Ok(every @ 1..) => every,
+            _ => return Err(format!("invalid {arg}")),
+          };
+        },
+        "--fps" => {
+          options.record_fps = match value()?.parse() {
+            Ok(fps @ 1..) => fps,
+            _ => return Err(format!("invalid {arg}")),
+          };
+        },
+        "--region" => {
+          options.export_region = Some(parse_rect(value()?).ok_or(format!("invalid {arg}"))?);
+        },
+        "--scale" => {
+          options.export_scale = match value()?.parse() {
+            Ok(scale @ 1..) => scale,
+            _ => return Err(format!("invalid {arg}")),
+          };
+        },
+        "--annotations" => {
+          let path = value()?;
+          let text = std::fs::read_to_string(path).map_err(|e| format!("reading {path}: {e}"))?;
+          options.annotations = annotation::parse(&text).map_err(|e| format!("{path}: {e}"))?;
+        },
+        "--profile" => options.profile_path = Some(value()?.clone()),
+        "--glyphs" => options.export_overlay |= OVERLAY_GLYPHS,
+        "--grid" => options.export_overlay |= OVERLAY_GRID,
+        _ => return Err(format!("unknown argument {arg}\n{USAGE}")),
+      }
+    }
+    Ok(options)
+  }
+}
+
+/// Runs the simulation without a window, reporting watchpoint hits and
+/// recording probes.
+pub fn run(args: &[String]) -> Result<(), String> {
+  let options = Options::parse(args)?;
+
+  let instance = wgpu::Instance::new(wgpu::Backends::all());
+  let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+    .ok_or("no suitable adapter found")?;
+  let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+    label: None,
+    features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+    limits: wgpu::Limits::default(),
+  }, None)).map_err(|e| e.to_string())?;
+  let device_name = adapter.get_info().name;
+  println!("device: {device_name}");
+
+  let mut gpu_driver = GPUDriver::new(&device, &queue, wgpu::TextureFormat::Rgba8Unorm);
+  gpu_driver.load_simulation(&device, &queue);
+  let simulation_dimm = gpu_driver.uniforms.simulation_dimm;
+  if let Some([x, y]) = options.probes.iter().find(|[x, y]| *x >= simulation_dimm[0] || *y >= simulation_dimm[1]) {
+    return Err(format!("--probe ({x}, {y}) lies outside the {}×{} simulation", simulation_dimm[0], simulation_dimm[1]));
+  }
+  gpu_driver.set_watchpoints(&queue, &options.watchpoints);
+  gpu_driver.set_probes(&queue, &options.probes);
+  let mut trace = ProbeTrace::new(options.probes.clone());
+  trace.capacity = usize::MAX;
+  let mut populations = match &options.populations_path {
+    Some(path) => {
+      let mut w = BufWriter::new(File::create(path).map_err(|e| format!("creating {path}: {e}"))?);
+      let header: String = (0..STATES).map(|state| format!(",s{state}")).collect();
+      writeln!(w, "generation{header}").map_err(|e| e.to_string())?;
+      gpu_driver.set_count_populations(true);
+      Some(w)
+    },
+    None => None,
+  };
+
+  let region = match options.export_region {
+    Some(region) if region.clip(simulation_dimm) != Some(region) => {
+      return Err(format!("--region exceeds the {}×{} simulation", simulation_dimm[0], simulation_dimm[1]));
+    },
+    Some(region) => region,
+    None => gpu_driver.simulation_rect(),
+  };
+  let render_annotated = |gpu_driver: &GPUDriver| {
+    let mut image = gpu_driver.render_region(&device, &queue, region, options.export_scale, options.export_overlay)?;
+    annotation::draw(&mut image, &options.annotations, region, options.export_scale);
+    Ok::<_, String>(image)
+  };
+  let mut recording = options.record_path.as_ref()
+    .map(|path| Recording::new(RecordingFormat::from_path(path), path.clone(), options.record_fps));
+  let mut record_frame = |gpu_driver: &GPUDriver| -> Result<(), String> {
+    match recording.as_mut() {
+      Some(recording) if gpu_driver.generation.is_multiple_of(options.record_every) => recording.push(
+        render_annotated(gpu_driver)?
+      ),
+      _ => Ok(()),
+    }
+  };
+
+  let mut profile = ProfileTotals::default();
+  let t0 = Instant::now();
+  record_frame(&gpu_driver)?;
+  let mut stopped_by_watchpoint = false;
+  while gpu_driver.generation < options.generations {
+    // stop on each recorded generation
+    let next_frame = (gpu_driver.generation / options.record_every + 1) * options.record_every;
+    gpu_driver.simulatiion_steps_per_call = options.iters
+      .min(options.generations - gpu_driver.generation)
+      .min(next_frame - gpu_driver.generation);
+    gpu_driver.simulation_advance_submit(&device, &queue);
+    device.poll(wgpu::Maintain::Wait);
+    if let Some((first_generation, rows)) = gpu_driver.poll_probe_samples() {
+      trace.append(first_generation, rows);
+    }
+    if let (Some(w), Some(histograms)) = (populations.as_mut(), gpu_driver.poll_populations()) {
+      for (generation, histogram) in histograms {
+        let counts: String = histogram.iter().map(|n| format!(",{n}")).collect();
+        writeln!(w, "{generation}{counts}").map_err(|e| e.to_string())?;
+      }
+    }
+
+    if let Some(sample) = gpu_driver.poll_profile() {
+      profile.add(&sample);
+    }
+
+    record_frame(&gpu_driver)?;
+    if let Some(hit) = gpu_driver.poll_watch_hits().into_iter().min_by_key(|hit| hit.generation) {
+      println!(
+        "watchpoint #{} hit at generation {}, cell ({}, {})",
+        hit.watchpoint, hit.generation, hit.cell[0], hit.cell[1]
+      );
+      stopped_by_watchpoint = true;
+      break;
+    }
+  }
+  let elapsed = t0.elapsed().as_secs_f64();
+  match stopped_by_watchpoint {
+    true => println!("stopped at generation {} after {elapsed:.3}s", gpu_driver.generation),
+    false => println!("generation {} reached in {elapsed:.3}s", gpu_driver.generation),
+  }
+
+  if let Some(path) = options.trace_path {
+    let write = |extension: &str, vcd: bool| File::create(format!("{path}.{extension}"))
+      .and_then(|file| if vcd {
+        trace.write_vcd(BufWriter::new(file), Rule::ACTIVE)
+      } else {
+        trace.write_csv(BufWriter::new(file))
+      })
+      .map_err(|e| format!("writing {path}.{extension}: {e}"));
+    write("csv", false)?;
+    write("vcd", true)?;
+    println!("{} generations of {} probes written to {path}.csv/.vcd", trace.rows.len(), trace.cells.len());
+  }
+
+  if let Some(path) = options.profile_path {
+    print!("{}", profile.to_markdown(&device_name, simulation_dimm));
+    profile.append_csv(&path, &device_name, simulation_dimm).map_err(|e| format!("writing {path}: {e}"))?;
+    println!("{} of {} generations appended to {path}", profile.source(), profile.generations);
+  }
+
+  if let Some(recording) = recording {
+    println!("{}", recording.finish()?);
+  }
+
+  if let Some(path) = options.export_path {
+    let image = render_annotated(&gpu_driver)?;
+    image.save(&path).map_err(|e| format!("writing {path}: {e}"))?;
+    println!("{}×{} image written to {path}", image.width(), image.height());
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(args: &[&str]) -> Result<Options, String> {
+    Options::parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+  }
+
+  #[test]
+  fn parses_negative_numbers() {
+    assert_eq!(parse_numbers("-5, 3"), Some([-5, 3]));
+    assert_eq!(parse_numbers::<2>("1-2"), None);
+    assert_eq!(parse_numbers::<2>("1,2,3"), None);
+  }
+
+  #[test]
+  fn parses_watch_ranges() {
+    let options = parse(&["--watch", "-5,3,13-16"]).unwrap();
+    assert_eq!(options.watchpoints[0].rect, CellRect { x: -5, y: 3, width: 1, height: 1 });
+    assert!(matches!(options.watchpoints[0].condition, WatchCondition::Enters(13, 16)));
+    assert!(parse(&["--watch", "5,3,13"]).is_err());
+    assert!(parse(&["--watch", "5,3,13-32"]).is_err());
+    assert!(parse(&["--probe", "-1,3"]).is_err());
+    assert!(parse(&["--region", "0,0,0,4"]).is_err());
+  }
+}
@@ -0,0 +1,218 @@
+use std::{collections::VecDeque, mem};
+
+/// A single cell change, in simulation coordinates.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CellEdit {
+  pub x: u32,
+  pub y: u32,
+  pub old: u8,
+  pub new: u8,
+}
+
+pub enum HistoryEntry {
+  /// Sparse cell changes, applied in order.
+  Edit(Vec<CellEdit>),
+  /// Raw simulation buffer, taken before running.
+  Snapshot {
+    generation: u64,
+    simulation_dimm: [u32; 2],
+    records: Vec<u32>,
+  },
+}
+
+impl HistoryEntry {
+  fn size_bytes(&self) -> usize {
+    match self {
+      HistoryEntry::Edit(edits) => edits.len() * mem::size_of::<CellEdit>(),
+      HistoryEntry::Snapshot { records, .. } => records.len() * mem::size_of::<u32>(),
+    }
+  }
+}
+
+/// Undo/redo stacks, bounded by `budget_bytes`. Oldest entries are dropped first,
+/// the newest of each stack is kept even when it alone exceeds the budget.
+pub struct History {
+  undo: VecDeque<HistoryEntry>,
+  /// Newest last, like `undo`.
+  redo: VecDeque<HistoryEntry>,
+  /// Running total of both stacks.
+  size_bytes: usize,
+  budget_bytes: usize,
+  /// Take a full snapshot of the simulation buffer before Start, Step and Reset.
+  pub snapshot_before_run: bool,
+}
+
+impl Default for History {
+  fn default() -> Self {
+    Self {
+      undo: VecDeque::new(),
+      redo: VecDeque::new(),
+      size_bytes: 0,
+      budget_bytes: 256 * 1024 * 1024,
+      snapshot_before_run: true,
+    }}}
+
+impl History {
+  /// Record a new entry, invalidating the redo stack.
+  pub fn push(&mut self, entry: HistoryEntry) {
+    if let HistoryEntry::Edit(edits) = &entry {
+      if edits.is_empty() {
+        return;
+      }
+    }
+    self.size_bytes -= self.redo.drain(..).map(|entry| entry.size_bytes()).sum::<usize>();
+    self.push_undo(entry);
+  }
+
+  pub fn pop_undo(&mut self) -> Option<HistoryEntry> {
+    let entry = self.undo.pop_back()?;
+    self.size_bytes -= entry.size_bytes();
+    Some(entry)
+  }
+
+  pub fn pop_redo(&mut self) -> Option<HistoryEntry> {
+    let entry = self.redo.pop_back()?;
+    self.size_bytes -= entry.size_bytes();
+    Some(entry)
+  }
+
+  /// Entry which can revert an undo.
+  pub fn push_redo(&mut self, entry: HistoryEntry) {
+    self.size_bytes += entry.size_bytes();
+    self.redo.push_back(entry);
+    self.enforce_budget();
+  }
+
+  /// Entry which can revert a redo, keeps the redo stack.
+  pub fn push_undo(&mut self, entry: HistoryEntry) {
+    self.size_bytes += entry.size_bytes();
+    self.undo.push_back(entry);
+    self.enforce_budget();
+  }
+
+  pub fn can_undo(&self) -> bool {
+    !self.undo.is_empty()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    !self.redo.is_empty()
+  }
+
+  pub fn size_bytes(&self) -> usize {
+    self.size_bytes
+  }
+
+  pub fn budget_bytes(&self) -> usize {
+    self.budget_bytes
+  }
+
+  pub fn set_budget_bytes(&mut self, budget_bytes: usize) {
+    self.budget_bytes = budget_bytes;
+    self.enforce_budget();
+  }
+
+  pub fn depth(&self) -> (usize, usize) {
+    (self.undo.len(), self.redo.len())
+  }
+
+  pub fn clear(&mut self) {
+    self.undo.clear();
+    self.redo.clear();
+    self.size_bytes = 0;
+  }
+
+  fn enforce_budget(&mut self) {
+    while self.size_bytes > self.budget_bytes {
+      // redo entries are the least likely to be used
+      let evicted = if self.redo.len() > 1 {
+        self.redo.pop_front()
+      } else if self.undo.len() > 1 {
+        self.undo.pop_front()
+      } else {
+        break;
+      };
+      self.size_bytes -= evicted.map_or(0, |entry| entry.size_bytes());
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// An edit of `cells` cells, told apart by `x`.
+  fn edit(x: u32, cells: usize) -> HistoryEntry {
+    HistoryEntry::Edit(vec![CellEdit { x, y: 0, old: 0, new: 9 }; cells])
+  }
+
+  fn id(entry: &HistoryEntry) -> u32 {
+    match entry {
+      HistoryEntry::Edit(edits) => edits[0].x,
+      HistoryEntry::Snapshot { generation, .. } => *generation as u32,
+    }
+  }
+
+  fn ids(entries: &VecDeque<HistoryEntry>) -> Vec<u32> {
+    entries.iter().map(id).collect()
+  }
+
+  const EDIT_BYTES: usize = mem::size_of::<CellEdit>();
+
+  #[test]
+  fn undo_and_redo() {
+    let mut history = History::default();
+    history.push(edit(1, 1));
+    history.push(edit(2, 2));
+    history.push(HistoryEntry::Edit(vec![]));
+    assert_eq!(history.depth(), (2, 0));
+    assert_eq!(history.size_bytes(), 3 * EDIT_BYTES);
+
+    assert_eq!(history.pop_undo().as_ref().map(id), Some(2));
+    history.push_redo(edit(2, 2));
+    assert_eq!(history.depth(), (1, 1));
+    assert_eq!(history.pop_redo().as_ref().map(id), Some(2));
+    history.push_undo(edit(2, 2));
+    assert_eq!(ids(&history.undo), [1, 2]);
+    assert_eq!(history.size_bytes(), 3 * EDIT_BYTES);
+    assert!(history.pop_redo().is_none());
+  }
+
+  #[test]
+  fn new_edits_invalidate_redo() {
+    let mut history = History::default();
+    history.push(edit(1, 1));
+    history.pop_undo();
+    history.push_redo(edit(1, 1));
+    history.push(edit(3, 4));
+    assert!(!history.can_redo());
+    assert_eq!(ids(&history.undo), [3]);
+    assert_eq!(history.size_bytes(), 4 * EDIT_BYTES);
+    history.clear();
+    assert_eq!((history.depth(), history.size_bytes()), ((0, 0), 0));
+  }
+
+  #[test]
+  fn budget_evicts_oldest_redo_then_oldest_undo() {
+    let mut history = History::default();
+    for x in 1..=4 {
+      history.push(edit(x, 1));
+    }
+    for x in [4, 3] {
+      history.pop_undo();
+      history.push_redo(edit(x, 1));
+    }
+    assert_eq!((ids(&history.undo), ids(&history.redo)), (vec![1, 2], vec![4, 3]));
+
+    history.set_budget_bytes(3 * EDIT_BYTES);
+    assert_eq!((ids(&history.undo), ids(&history.redo)), (vec![1, 2], vec![3]));
+    history.set_budget_bytes(2 * EDIT_BYTES);
+    assert_eq!((ids(&history.undo), ids(&history.redo)), (vec![2], vec![3]));
+    // the newest of each stack stays, over budget or not
+    history.set_budget_bytes(0);
+    assert_eq!((ids(&history.undo), ids(&history.redo)), (vec![2], vec![3]));
+    assert_eq!(history.size_bytes(), 2 * EDIT_BYTES);
+    history.push(edit(5, 10));
+    assert_eq!((ids(&history.undo), ids(&history.redo)), (vec![5], vec![]));
+    assert_eq!(history.size_bytes(), 10 * EDIT_BYTES);
+  }
+}
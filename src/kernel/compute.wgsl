@@ -16,6 +16,13 @@
   let lut_offset = w | s << 5u | e << 10u | n << 15u | c << 20u;
   let cell = (hutton32_lut[lut_offset / 4u] >> ((lut_offset % 4u) * 8u)) & 0xFFu;
 
+  if (uniforms.count_changes != 0u && cell != c) {
+    atomicAdd(&change_counter, 1u);
+  }
+  if (uniforms.render_mode == RENDER_HEAT) {
+    heat_buffer[offset] = max(heat_buffer[offset] * uniforms.heat_decay, f32(cell != c));
+  }
+
   let next_record = set_cell(current_record, cell, parity);
   simulation_buffer[offset] = next_record | (u32(!parity) << 16u);
 }
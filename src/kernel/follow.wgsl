@@ -0,0 +1,49 @@
+struct FollowWindow {
+  // x, y, width, height
+  rect: vec4<u32>,
+  // 0u: cells which changed, 1u: cells carrying a signal, 2u: non-ground cells
+  cells: u32,
+};
+
+var<workgroup> local_follow_sums: array<atomic<u32>, 3>;
+@group(1) @binding(0) var<storage, read> follow_window: FollowWindow;
+// matching cells, and the sums of their x and y within the window
+@group(1) @binding(1) var<storage, read_write> follow_sums: array<atomic<u32>, 3>;
+
+// Evaluated once per frame over the followed window, reduced per workgroup
+// first like `compute_populations`.
+@compute @workgroup_size(8, 8) fn compute_follow(
+  @builtin(global_invocation_id) global_id: vec3<u32>,
+  @builtin(local_invocation_index) local_index: u32
+) {
+  if (local_index < 3u) {
+    atomicStore(&local_follow_sums[local_index], 0u);
+  }
+  workgroupBarrier();
+
+  let xy = follow_window.rect.xy + global_id.xy;
+  if (all(global_id.xy < follow_window.rect.zw) && sim_boundary_check(xy)) {
+    let record = simulation_buffer[xy.y * uniforms.simulation_dimm.x + xy.x];
+    let cell = get_cell(xy, bool(record >> 16u));
+    var matches = cell != 0u;
+    if (follow_window.cells == 0u) {
+      // both bytes hold the last two generations
+      matches = ((record >> 8u) & 0xffu) != (record & 0xffu);
+    } else if (follow_window.cells == 1u) {
+      matches = carries_signal(cell);
+    }
+    if (matches) {
+      atomicAdd(&local_follow_sums[0], 1u);
+      atomicAdd(&local_follow_sums[1], global_id.x);
+      atomicAdd(&local_follow_sums[2], global_id.y);
+    }
+  }
+  workgroupBarrier();
+
+  if (local_index < 3u) {
+    let sum = atomicLoad(&local_follow_sums[local_index]);
+    if (sum != 0u) {
+      atomicAdd(&follow_sums[local_index], sum);
+    }
+  }
+}
@@ -7,22 +7,43 @@
   let xy = (in.tex_coords * scale + vec2(uniforms.display_x_range[0], -uniforms.display_y_range[0]))
     / vec2<f32>(uniforms.simulation_dimm);
   let boundary = u32(xy.x >= 0.0 && xy.x <= 1.0 && xy.y >= 0.0 && xy.y <= 1.0);
-  let pixel = vec2<u32>(xy * vec2<f32>(uniforms.simulation_dimm));
+  let cell_xy = xy * vec2<f32>(uniforms.simulation_dimm);
+  // screen pixels across a cell
+  let cell_pixels = 1.0 / max(fwidth(cell_xy.x), 1e-6);
+  let pixel = vec2<u32>(cell_xy);
   let offset = pixel.y * uniforms.simulation_dimm.x + pixel.x;
   let parity = bool(simulation_buffer[offset] >> 16u);
   let cell = get_cell(pixel, parity) & (boundary * 0xffu);
-  var color = vec3<f32>(hutton32_colors[cell]) / 255.0;
-  if ((cell >= 9u && cell <= 12u)) {
-    //color = (color + vec3((color.x + color.y + color.z) / 3.0)) / 2.0;
-    color = color * 0.85;
+  var color = palette[cell].rgb;
+
+  if (uniforms.render_mode == RENDER_CHANGES) {
+    // both bytes hold the last two generations
+    let record = simulation_buffer[offset];
+    let changed = f32(((record >> 8u) & 0xffu) != (record & 0xffu)) * f32(boundary);
+    color = max(color * 0.1, vec3(changed, 0.0, 0.0));
+  } else if (uniforms.render_mode == RENDER_EXCITED) {
+    if (!carries_signal(cell)) {
+      color = vec3((color.x + color.y + color.z) / 3.0) * 0.1;
+    }
+  } else if (uniforms.render_mode == RENDER_HEAT) {
+    let heat = heat_buffer[offset] * f32(boundary);
+    // black-body like ramp: red, then yellow, then white
+    let ramp = clamp(vec3(heat * 3.0, heat * 3.0 - 1.0, heat * 3.0 - 2.0), vec3(0.0), vec3(1.0));
+    color = max(color * 0.15, ramp);
+  }
+
+  let any_zoom = (uniforms.overlay & OVERLAY_ANY_ZOOM) != 0u;
+  if ((uniforms.overlay & OVERLAY_GLYPHS) != 0u && (any_zoom || cell_pixels >= GLYPH_MIN_PIXELS)) {
+    let glyph = cell_glyph(cell, fract(cell_xy) - 0.5, color);
+    color = mix(color, glyph.rgb, glyph.a);
+  }
+  if ((uniforms.overlay & OVERLAY_GRID) != 0u && (any_zoom || cell_pixels >= GRID_MIN_PIXELS)) {
+    let edge = min(fract(cell_xy), 1.0 - fract(cell_xy)) * cell_pixels;
+    if (min(edge.x, edge.y) < 0.5) {
+      color = mix(color, vec3(0.5), 0.5);
+    }
   }
 
   color = pow(color, vec3(2.2));
-  /*if (highlight_signals) {
-    let offset = pixel.y * uniforms.simulation_dimm.x + pixel.x;
-    let cell_diff = simulation_buffer[offset];
-    let cell_diff = f32((cell_diff >> 8u) != (cell_diff & 0xffu));
-    return vec4(max(vec3<f32>(hutton32_colors[cell]) / 255.0 * 0.1, vec3(cell_diff, 0.0, 0.0)), f32(boundary));
-  }*/
   return vec4(color, f32(boundary));
 }
\ No newline at end of file
@@ -0,0 +1,61 @@
+// Cell glyphs drawn past a zoom threshold, see `fs_main`.
+
+// Unit vector of a transmission state direction: east, north, west, south.
+fn dir_vector(dir: u32) -> vec2<f32> {
+  var dirs = array<vec2<f32>, 4>(
+    vec2<f32>( 1.0,  0.0),
+    vec2<f32>( 0.0, -1.0),
+    vec2<f32>(-1.0,  0.0),
+    vec2<f32>( 0.0,  1.0)
+  );
+  return dirs[dir];
+}
+
+fn arrow(q: vec2<f32>) -> bool {
+  let shaft = q.x >= -0.38 && q.x <= 0.05 && abs(q.y) <= 0.07;
+  let head = q.x >= 0.0 && q.x <= 0.4 && abs(q.y) <= (0.4 - q.x) * 0.9;
+  return shaft || head;
+}
+
+// `p` is the position within the cell, -0.5..0.5 with y pointing south.
+// Returns the glyph colour, alpha is the coverage.
+fn cell_glyph(cell: u32, p: vec2<f32>, background: vec3<f32>) -> vec4<f32> {
+  let ink = background * 0.25;
+  let lit = vec3(1.0);
+
+  // sensitized: hollow diamond
+  if (cell >= 1u && cell <= 8u) {
+    let d = abs(p.x) + abs(p.y);
+    return vec4(ink, f32(d >= 0.22 && d <= 0.32));
+  }
+
+  // transmission: arrow, with a crossbar at the tail for STS
+  if (cell >= 9u && cell <= 24u) {
+    let f = dir_vector((cell - 9u) % 4u);
+    let q = vec2(dot(p, f), dot(p, vec2(-f.y, f.x)));
+    var covered = arrow(q);
+    if (cell >= 17u) {
+      covered = covered || (q.x >= -0.42 && q.x <= -0.3 && abs(q.y) <= 0.28);
+    }
+    return vec4(select(ink, lit, carries_signal(cell)), f32(covered));
+  }
+
+  // confluent: ring, lit while the next output is excited (26, 28), and a
+  // dot while the output is excited (27, 28)
+  if (cell >= 25u && cell <= 28u) {
+    let r = length(p);
+    if (r <= 0.13) {
+      return vec4(select(ink, lit, cell == 27u || cell == 28u), 1.0);
+    }
+    return vec4(select(ink, lit, cell == 26u || cell == 28u), f32(r >= 0.26 && r <= 0.34));
+  }
+
+  // crossings: horizontal (29), vertical (30), both (31)
+  if (cell >= 29u && cell <= 31u) {
+    let horizontal = cell != 30u && abs(p.y) <= 0.07 && abs(p.x) <= 0.4;
+    let vertical = cell != 29u && abs(p.x) <= 0.07 && abs(p.y) <= 0.4;
+    return vec4(ink, f32(horizontal || vertical));
+  }
+
+  return vec4(0.0);
+}
@@ -2,6 +2,10 @@ struct Uniforms {
   display_x_range: vec2<f32>,
   display_y_range: vec2<f32>,
   simulation_dimm: vec2<u32>,
+  count_changes: u32,
+  render_mode: u32,
+  heat_decay: f32,
+  overlay: u32,
 };
 
 struct VertexInput {
@@ -13,14 +17,36 @@ struct VertexOutput {
   @location(0) tex_coords: vec2<f32>,
 };
 
+// `RenderMode` in `./src/gpu/mod.rs`
+let RENDER_PLAIN: u32 = 0u;
+let RENDER_CHANGES: u32 = 1u;
+let RENDER_EXCITED: u32 = 2u;
+let RENDER_HEAT: u32 = 3u;
+
+// `Uniform::overlay` bits
+let OVERLAY_GLYPHS: u32 = 1u;
+let OVERLAY_GRID: u32 = 2u;
+let OVERLAY_ANY_ZOOM: u32 = 4u;
+// zoom thresholds, in screen pixels per cell
+let GLYPH_MIN_PIXELS: f32 = 12.0;
+let GRID_MIN_PIXELS: f32 = 6.0;
+
 @group(0) @binding(0) var<uniform> uniforms: Uniforms;
 @group(0) @binding(1) var<storage, read_write> simulation_buffer: array<u32>;
 @group(0) @binding(2) var<storage, read_write> hutton32_lut: array<atomic<u32>>;
+@group(0) @binding(3) var<storage, read_write> change_counter: atomic<u32>;
+@group(0) @binding(4) var<storage, read_write> heat_buffer: array<f32>;
+// colour of each state, `Palette` in `./src/palette.rs`
+@group(0) @binding(5) var<uniform> palette: array<vec4<f32>, 32>;
 
 
-//!define hutton32_colors
 
 //!include ./src/kernel/util.wgsl
 //!include ./src/kernel/vertex.wgsl
+//!include ./src/kernel/glyph.wgsl
 //!include ./src/kernel/fragment.wgsl
-//!include ./src/kernel/compute.wgsl
\ No newline at end of file
+//!include ./src/kernel/compute.wgsl
+//!include ./src/kernel/watch.wgsl
+//!include ./src/kernel/probe.wgsl
+//!include ./src/kernel/population.wgsl
+//!include ./src/kernel/follow.wgsl
\ No newline at end of file
@@ -0,0 +1,30 @@
+var<workgroup> local_populations: array<atomic<u32>, 32>;
+// cells per state, summed over the universe
+@group(1) @binding(0) var<storage, read_write> populations: array<atomic<u32>, 32>;
+
+// Evaluated after each generation, reduced per workgroup first to keep the
+// contention on the global bins low.
+@compute @workgroup_size(8, 8) fn compute_populations(
+  @builtin(global_invocation_id) global_id: vec3<u32>,
+  @builtin(local_invocation_index) local_index: u32
+) {
+  if (local_index < 32u) {
+    atomicStore(&local_populations[local_index], 0u);
+  }
+  workgroupBarrier();
+
+  let xy = global_id.xy;
+  if (sim_boundary_check(xy)) {
+    let offset = xy.y * uniforms.simulation_dimm.x + xy.x;
+    let state = get_cell(xy, bool(simulation_buffer[offset] >> 16u));
+    atomicAdd(&local_populations[min(state, 31u)], 1u);
+  }
+  workgroupBarrier();
+
+  if (local_index < 32u) {
+    let count = atomicLoad(&local_populations[local_index]);
+    if (count != 0u) {
+      atomicAdd(&populations[local_index], count);
+    }
+  }
+}
@@ -0,0 +1,14 @@
+@group(1) @binding(0) var<storage, read> probe_cells: array<vec2<u32>>;
+// state of each probed cell in the generation just computed
+@group(1) @binding(1) var<storage, read_write> probe_samples: array<u32>;
+
+// Evaluated after each generation, global_id.x is the probe.
+@compute @workgroup_size(1) fn compute_probe(@builtin(global_invocation_id) global_id: vec3<u32>) {
+  let xy = probe_cells[global_id.x];
+  var state = 0u;
+  if (sim_boundary_check(xy)) {
+    let offset = xy.y * uniforms.simulation_dimm.x + xy.x;
+    state = get_cell(xy, bool(simulation_buffer[offset] >> 16u));
+  }
+  probe_samples[global_id.x] = state;
+}
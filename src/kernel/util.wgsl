@@ -16,9 +16,12 @@ fn get_cell(xy: vec2<u32>, parity: bool) -> u32 {
            |                                     |
         parity                                parity
   */
+  // ground beyond the edges, see `Boundary::Bounded`
+  if (!sim_boundary_check(xy)) {
+    return 0u;
+  }
   let offset = xy.y * uniforms.simulation_dimm.x + xy.x;
  return
-    //(u32(sim_boundary_check(xy)) * 0xffffffffu) & // if sim_boundary_check(xy)
     (simulation_buffer[offset] >> (u32(!parity) * 8u)) &
     0xffu;
 }
@@ -29,3 +32,9 @@ fn set_cell(cell_record: u32, next: u32, parity: bool) -> u32 {
   let current = cell_record & (0xffu << (8u - shift));
   return next | current;
 }
+
+// Excited transmission states, and confluent states with an excited output,
+// see `Rule::is_excited`.
+fn carries_signal(cell: u32) -> bool {
+  return (cell >= 13u && cell <= 16u) || (cell >= 21u && cell <= 24u) || cell == 27u || cell == 28u;
+}
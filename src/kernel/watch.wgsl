@@ -0,0 +1,44 @@
+struct Watchpoint {
+  // x, y, width, height
+  rect: vec4<u32>,
+  // inclusive state range, for kind == 0u
+  states: vec2<u32>,
+  // 0u: a cell enters the state range, 1u: a cell changes
+  kind: u32,
+  _padding: u32,
+};
+
+@group(1) @binding(0) var<storage, read> watchpoints: array<Watchpoint>;
+// linear offset + 1 of a cell which hit, per watchpoint
+@group(1) @binding(1) var<storage, read_write> watch_hits: array<atomic<u32>>;
+
+fn in_state_range(cell: u32, states: vec2<u32>) -> bool {
+  return cell >= states.x && cell <= states.y;
+}
+
+// Evaluated after each generation, global_id.z is the watchpoint.
+@compute @workgroup_size(1) fn compute_watch(@builtin(global_invocation_id) global_id: vec3<u32>) {
+  let watchpoint = watchpoints[global_id.z];
+  if (global_id.x >= watchpoint.rect.z || global_id.y >= watchpoint.rect.w) {
+    return;
+  }
+  let xy = watchpoint.rect.xy + global_id.xy;
+  if (!sim_boundary_check(xy)) {
+    return;
+  }
+
+  let offset = xy.y * uniforms.simulation_dimm.x + xy.x;
+  let parity = bool(simulation_buffer[offset] >> 16u);
+  let current = get_cell(xy, parity);
+  let previous = get_cell(xy, !parity);
+
+  var hit = false;
+  if (watchpoint.kind == 0u) {
+    hit = in_state_range(current, watchpoint.states) && !in_state_range(previous, watchpoint.states);
+  } else {
+    hit = current != previous;
+  }
+  if (hit) {
+    atomicMax(&watch_hits[global_id.z], offset + 1u);
+  }
+}
@@ -1,9 +1,40 @@
+mod annotation;
+mod construction;
 mod gui;
 mod gpu;
+mod headless;
+mod history;
+mod palette;
+mod pattern;
+mod probe;
+mod profile;
+mod recording;
+mod rule;
+mod snapshot;
 
 fn main () {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if args.first().map(String::as_str) == Some("--headless") {
+    if let Err(e) = headless::run(&args[1..]) {
+      eprintln!("{e}");
+      std::process::exit(1);
+    }
+    return;
+  }
+
+  let mut wgpu_options = egui_wgpu::WgpuConfiguration::default();
+  // Timestamps for the profiler, only requested if whichever adapter eframe
+  // picks is sure to have them.
+  let backends = wgpu_options.backends;
+  if wgpu::Instance::new(backends).enumerate_adapters(backends)
+    .all(|adapter| adapter.features().contains(wgpu::Features::TIMESTAMP_QUERY))
+  {
+    wgpu_options.device_descriptor.features |= wgpu::Features::TIMESTAMP_QUERY;
+  }
+
   let native_options = eframe::NativeOptions {
     renderer: eframe::Renderer::Wgpu,
+    wgpu_options,
     initial_window_size: Some(egui::Vec2::new(631.0, 465.0)),
     vsync: true,
     ..Default::default()
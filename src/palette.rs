@@ -0,0 +1,107 @@
+use crate::{gpu::HUTTON32_COLORS, rule::Rule};
+
+/// Colour of each state, uploaded to `fs_main` as `palette`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Palette {
+  pub colors: [[u8; 3]; 32],
+}
+
+/// `N` colours from `from` to `to`, inclusive.
+fn ramp<const N: usize>(from: [u8; 3], to: [u8; 3]) -> [[u8; 3]; N] {
+  std::array::from_fn(|i| {
+    let t = if N > 1 { i as f32 / (N - 1) as f32 } else { 0.0 };
+    std::array::from_fn(|c| (from[c] as f32 + (to[c] as f32 - from[c] as f32) * t).round() as u8)
+  })
+}
+
+impl Palette {
+  pub fn default_for(rule: Rule) -> Self {
+    match rule {
+      Rule::Hutton32 | Rule::Hutton32b => {
+        let mut colors = HUTTON32_COLORS;
+        // quiescent OTS a bit darker, so that excited ones stand out
+        for color in &mut colors[9..=12] {
+          *color = color.map(|c| (c as f32 * 0.85).round() as u8);
+        }
+        Self { colors }
+      },
+      Rule::GameOfLife => {
+        let mut colors = [[0; 3]; 32];
+        colors[1] = [255, 255, 255];
+        Self { colors }
+      },
+    }
+  }
+
+  /// Built on the Okabe-Ito set: each group of states gets a hue told apart
+  /// under the common forms of colour blindness, excited states are lighter.
+  pub fn colour_blind_safe() -> Self {
+    let mut colors = [[0; 3]; 32];
+    colors[1..=8].copy_from_slice(&ramp::<8>([230, 159, 0], [255, 215, 130]));
+    colors[9..=12].copy_from_slice(&ramp::<4>([0, 114, 178], [40, 140, 200]));
+    colors[13..=16].copy_from_slice(&ramp::<4>([240, 228, 66], [255, 245, 150]));
+    colors[17..=20].copy_from_slice(&ramp::<4>([213, 94, 0], [225, 120, 40]));
+    colors[21..=24].copy_from_slice(&ramp::<4>([204, 121, 167], [230, 170, 205]));
+    colors[25..=28].copy_from_slice(&[[0, 158, 115], [60, 190, 150], [150, 235, 205], [200, 250, 230]]);
+    colors[29..=31].copy_from_slice(&ramp::<3>([86, 180, 233], [150, 210, 240]));
+    Self { colors }
+  }
+
+  /// Read Golly colours on top of `self`, either a `.colors` file
+  /// (`color = state r g b`, `gradient = r g b r g b`) or the `@COLORS`
+  /// section of a `.rule` file (`state r g b`, `r g b r g b`). A gradient
+  /// spans states 1 to 31.
+  pub fn with_golly_colors(mut self, text: &str) -> Result<Self, String> {
+    let mut lines: Vec<(usize, &str)> = text.lines().enumerate().collect();
+    if let Some(start) = lines.iter().position(|(_, l)| l.trim_start().starts_with("@COLORS")) {
+      lines.drain(..=start);
+      if let Some(end) = lines.iter().position(|(_, l)| l.trim_start().starts_with('@')) {
+        lines.truncate(end);
+      }
+    }
+
+    for (i, line) in lines {
+      let line = line.split('#').next().unwrap_or("").trim();
+      let values = match line.split_once('=') {
+        Some((key, values)) if matches!(key.trim(), "color" | "gradient") => values,
+        Some(_) => continue,
+        None => line,
+      };
+      if values.trim().is_empty() {
+        continue;
+      }
+      let numbers: Vec<u32> = values.split_whitespace()
+        .map(|n| n.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("line {}: expected numbers", i + 1))?;
+      let channel = |n: u32| u8::try_from(n).map_err(|_| format!("line {}: {n} is not a colour channel", i + 1));
+
+      match numbers[..] {
+        [state, r, g, b] => {
+          let color = self.colors.get_mut(state as usize)
+            .ok_or(format!("line {}: no state {state}", i + 1))?;
+          *color = [channel(r)?, channel(g)?, channel(b)?];
+        },
+        [r0, g0, b0, r1, g1, b1] => {
+          let gradient = ramp::<31>([channel(r0)?, channel(g0)?, channel(b0)?], [channel(r1)?, channel(g1)?, channel(b1)?]);
+          self.colors[1..].copy_from_slice(&gradient);
+        },
+        _ => return Err(format!("line {}: expected 4 or 6 numbers", i + 1)),
+      }
+    }
+    Ok(self)
+  }
+
+  /// Golly colours, as a `.colors` file or as an `@COLORS` section.
+  pub fn to_golly_colors(self, colors_file: bool) -> String {
+    let mut text = if colors_file { String::new() } else { "@COLORS\n\n".to_string() };
+    for (state, [r, g, b]) in self.colors.iter().enumerate() {
+      if colors_file {
+        text += &format!("color = {state} {r} {g} {b}\n");
+      } else {
+        text += &format!("{state} {r} {g} {b}\n");
+      }
+    }
+    text
+  }
+}
@@ -0,0 +1,178 @@
+/// Rectangle of cells, in simulation coordinates (y pointing down).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CellRect {
+  pub x: i64,
+  pub y: i64,
+  pub width: u32,
+  pub height: u32,
+}
+
+impl CellRect {
+  /// Rectangle spanning both cells, inclusive.
+  pub fn from_corners(a: [i64; 2], b: [i64; 2]) -> Self {
+    Self {
+      x: a[0].min(b[0]),
+      y: a[1].min(b[1]),
+      width: (a[0] - b[0]).unsigned_abs() as u32 + 1,
+      height: (a[1] - b[1]).unsigned_abs() as u32 + 1,
+    }
+  }
+
+  /// Intersection with the simulation area.
+  pub fn clip(&self, simulation_dimm: [u32; 2]) -> Option<CellRect> {
+    let x0 = self.x.max(0);
+    let y0 = self.y.max(0);
+    let x1 = (self.x + self.width as i64).min(simulation_dimm[0] as i64);
+    let y1 = (self.y + self.height as i64).min(simulation_dimm[1] as i64);
+    (x1 > x0 && y1 > y0).then(|| CellRect {
+      x: x0,
+      y: y0,
+      width: (x1 - x0) as u32,
+      height: (y1 - y0) as u32,
+    })
+  }
+
+  /// Rectangle covered once transformed about its centre. Quarter turns
+  /// of odd and even sides round the offset towards zero, so that turning
+  /// back restores the rectangle.
+  pub fn transformed(&self, transform: Transform) -> CellRect {
+    match transform {
+      Transform::RotateCW | Transform::RotateCCW => {
+        let offset = (self.width as i64 - self.height as i64) / 2;
+        CellRect { x: self.x + offset, y: self.y - offset, width: self.height, height: self.width }
+      },
+      Transform::FlipH | Transform::FlipV => *self,
+    }
+  }
+
+  /// Whether `cell` lies within the rectangle.
+  pub fn contains(&self, [x, y]: [i64; 2]) -> bool {
+    x >= self.x && y >= self.y && x < self.x + self.width as i64 && y < self.y + self.height as i64
+  }
+
+  /// Corners in plot coordinates, where y is pointing up.
+  pub fn plot_corners(&self) -> [[f64; 2]; 4] {
+    let (x0, y0) = (self.x as f64, -self.y as f64);
+    let (x1, y1) = (x0 + self.width as f64, y0 - self.height as f64);
+    [[x0, y0], [x1, y0], [x1, y1], [x0, y1]]
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Transform {
+  RotateCW,
+  RotateCCW,
+  FlipH,
+  FlipV,
+}
+
+/// A CPU side block of cell states, row-major.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Pattern {
+  pub width: u32,
+  pub height: u32,
+  pub cells: Vec<u8>,
+}
+
+impl Pattern {
+  pub fn new(width: u32, height: u32) -> Self {
+    Self { width, height, cells: vec![0; (width * height) as usize] }
+  }
+
+  pub fn get(&self, x: u32, y: u32) -> u8 {
+    self.cells[(y * self.width + x) as usize]
+  }
+
+  pub fn set(&mut self, x: u32, y: u32, state: u8) {
+    self.cells[(y * self.width + x) as usize] = state;
+  }
+
+  /// Geometric transform, followed by the `state_permutation` of the rule,
+  /// see `Rule::state_permutation`.
+  pub fn transformed(&self, transform: Transform, state_permutation: &[u8; 32]) -> Self {
+    let (w, h) = (self.width, self.height);
+    let mut out = match transform {
+      Transform::RotateCW | Transform::RotateCCW => Pattern::new(h, w),
+      Transform::FlipH | Transform::FlipV => Pattern::new(w, h),
+    };
+    for y in 0..h {
+      for x in 0..w {
+        let (tx, ty) = match transform {
+          Transform::RotateCW => (h - 1 - y, x),
+          Transform::RotateCCW => (y, w - 1 - x),
+          Transform::FlipH => (w - 1 - x, y),
+          Transform::FlipV => (x, h - 1 - y),
+        };
+        let state = self.get(x, y);
+        out.set(tx, ty, state_permutation.get(state as usize).copied().unwrap_or(state));
+      }
+    }
+    out
+  }
+
+  /// RGBA preview, used by paste previews.
+  pub fn to_color_image(&self, palette: &[[u8; 3]; 32]) -> egui::ColorImage {
+    let pixels = self.cells.iter()
+      .map(|&state| {
+        let [r, g, b] = palette[state as usize % palette.len()];
+        if state == 0 {
+          egui::Color32::TRANSPARENT
+        } else {
+          egui::Color32::from_rgb(r, g, b)
+        }
+      })
+      .collect();
+    egui::ColorImage {
+      size: [self.width as usize, self.height as usize],
+      pixels,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, crate::rule::Rule};
+
+  fn transformed(pattern: &Pattern, transforms: &[Transform]) -> Pattern {
+    transforms.iter().fold(pattern.clone(), |pattern, &transform| {
+      pattern.transformed(transform, &Rule::ACTIVE.state_permutation(transform))
+    })
+  }
+
+  /// Every state once, on a non-square block.
+  fn all_states() -> Pattern {
+    Pattern { width: 8, height: 4, cells: (0..32).collect() }
+  }
+
+  #[test]
+  fn transforms_undo_themselves() {
+    use Transform::*;
+    let pattern = all_states();
+    assert_eq!(transformed(&pattern, &[RotateCW; 4]), pattern);
+    assert_eq!(transformed(&pattern, &[RotateCCW; 4]), pattern);
+    assert_eq!(transformed(&pattern, &[RotateCW, RotateCCW]), pattern);
+    assert_eq!(transformed(&pattern, &[FlipH; 2]), pattern);
+    assert_eq!(transformed(&pattern, &[FlipV; 2]), pattern);
+    assert_eq!(transformed(&pattern, &[FlipH, FlipV]), transformed(&pattern, &[RotateCW; 2]));
+  }
+
+  #[test]
+  fn arrows_keep_pointing_at_their_neighbour() {
+    // an east OTS feeding a confluent
+    let pattern = Pattern { width: 2, height: 1, cells: vec![9, 25] };
+    assert_eq!(transformed(&pattern, &[Transform::RotateCCW]), Pattern { width: 1, height: 2, cells: vec![25, 10] });
+    assert_eq!(transformed(&pattern, &[Transform::RotateCW]), Pattern { width: 1, height: 2, cells: vec![12, 25] });
+    assert_eq!(transformed(&pattern, &[Transform::FlipH]), Pattern { width: 2, height: 1, cells: vec![25, 11] });
+    assert_eq!(transformed(&pattern, &[Transform::FlipV]), pattern);
+  }
+
+  #[test]
+  fn rects_turn_about_their_centre() {
+    let rect = CellRect { x: 10, y: 20, width: 5, height: 2 };
+    let turned = rect.transformed(Transform::RotateCW);
+    assert_eq!(turned, CellRect { x: 11, y: 19, width: 2, height: 5 });
+    assert_eq!(turned.transformed(Transform::RotateCCW), rect);
+    assert_eq!(turned.transformed(Transform::RotateCW), rect);
+    assert_eq!(rect.transformed(Transform::FlipH), rect);
+  }
+}
@@ -0,0 +1,199 @@
+use {
+  std::{collections::VecDeque, io::{self, Write}},
+  crate::rule::Rule,
+};
+
+/// States of the probed cells over runs of consecutive generations.
+pub struct ProbeTrace {
+  pub cells: Vec<[u32; 2]>,
+  /// One row per generation, oldest first, with one state per cell. Rows
+  /// missed in between, e.g. dropped while the viewer lagged, leave a gap.
+  pub rows: VecDeque<(u64, Vec<u8>)>,
+  /// Oldest rows are dropped past this many generations.
+  pub capacity: usize,
+}
+
+impl ProbeTrace {
+  pub fn new(cells: Vec<[u32; 2]>) -> Self {
+    Self {
+      cells,
+      rows: VecDeque::new(),
+      capacity: 1 << 16,
+    }
+  }
+
+  /// Append rows starting at `first_generation`. Rows from that generation on
+  /// are replaced, as after stepping back; rows before are kept, across a gap
+  /// if there is one.
+  pub fn append(&mut self, first_generation: u64, rows: Vec<Vec<u8>>) {
+    while self.rows.back().is_some_and(|&(generation, _)| generation >= first_generation) {
+      self.rows.pop_back();
+    }
+    self.rows.extend((first_generation..).zip(rows));
+    let excess = self.rows.len().saturating_sub(self.capacity);
+    self.rows.drain(..excess);
+  }
+
+  /// Generation after the last row.
+  pub fn end(&self) -> u64 {
+    self.rows.back().map_or(0, |&(generation, _)| generation + 1)
+  }
+
+  /// Generation after each row followed by a gap.
+  fn gap_after(&self, i: usize) -> Option<u64> {
+    let (generation, _) = self.rows[i];
+    self.rows.get(i + 1).filter(|&&(next, _)| next != generation + 1).map(|_| generation + 1)
+  }
+
+  pub fn name(&self, probe: usize) -> String {
+    let [x, y] = self.cells[probe];
+    format!("p{probe}_{x}_{y}")
+  }
+
+  /// `(generation, Some(state))` wherever the state of `probe` changes,
+  /// starting with the first row and again after each gap, and
+  /// `(generation, None)` where a gap starts.
+  pub fn transitions(&self, probe: usize) -> impl Iterator<Item = (u64, Option<u8>)> + '_ {
+    let mut last = None;
+    self.rows.iter().enumerate().flat_map(move |(i, (generation, row))| {
+      let state = row[probe];
+      let changed = (last.replace(state) != Some(state)).then_some((*generation, Some(state)));
+      let gap = self.gap_after(i).map(|gap| {
+        last = None;
+        (gap, None)
+      });
+      changed.into_iter().chain(gap)
+    })
+  }
+
+  /// One line per generation, with the raw state of each probe.
+  pub fn write_csv(&self, mut w: impl Write) -> io::Result<()> {
+    write!(w, "generation")?;
+    for probe in 0..self.cells.len() {
+      write!(w, ",{}", self.name(probe))?;
+    }
+    writeln!(w)?;
+    for (generation, row) in &self.rows {
+      write!(w, "{generation}")?;
+      for state in row {
+        write!(w, ",{state}")?;
+      }
+      writeln!(w)?;
+    }
+    Ok(())
+  }
+
+  /// Value change dump, one time unit per generation. Each probe has a 1-bit
+  /// signal telling whether it is excited under `rule`, and a 5-bit state.
+  pub fn write_vcd(&self, mut w: impl Write, rule: Rule) -> io::Result<()> {
+    // identifiers are printable ASCII from '!', two per probe
+    let id = |probe: usize, signal: usize| (b'!' + (probe * 2 + signal) as u8) as char;
+
+    writeln!(w, "$version hutton32-gpu $end")?;
+    writeln!(w, "$comment one time unit per generation, rule {} $end", rule.name())?;
+    writeln!(w, "$timescale 1 ns $end")?;
+    writeln!(w, "$scope module probes $end")?;
+    for probe in 0..self.cells.len() {
+      writeln!(w, "$var wire 1 {} {} $end", id(probe, 0), self.name(probe))?;
+      writeln!(w, "$var wire 5 {} {}_state $end", id(probe, 1), self.name(probe))?;
+    }
+    writeln!(w, "$upscope $end")?;
+    writeln!(w, "$enddefinitions $end")?;
+
+    let mut last: Option<&Vec<u8>> = None;
+    for (i, (generation, row)) in self.rows.iter().enumerate() {
+      let changed: Vec<usize> = (0..row.len())
+        .filter(|&probe| last.is_none_or(|last| last[probe] != row[probe]))
+        .collect();
+      if !changed.is_empty() {
+        writeln!(w, "#{generation}")?;
+        for probe in changed {
+          let state = row[probe];
+          writeln!(w, "{}{}", u8::from(rule.is_excited(state)), id(probe, 0))?;
+          writeln!(w, "b{:05b} {}", state, id(probe, 1))?;
+        }
+      }
+      last = Some(row);
+      // unknown values over generations missing from the trace
+      if let Some(gap) = self.gap_after(i) {
+        writeln!(w, "#{gap}")?;
+        for probe in 0..row.len() {
+          writeln!(w, "x{}", id(probe, 0))?;
+          writeln!(w, "bx {}", id(probe, 1))?;
+        }
+        last = None;
+      }
+    }
+    writeln!(w, "#{}", self.end())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn trace(batches: &[(u64, &[u8])]) -> ProbeTrace {
+    let mut trace = ProbeTrace::new(vec![[3, 4]]);
+    for &(first_generation, states) in batches {
+      trace.append(first_generation, states.iter().map(|&state| vec![state]).collect());
+    }
+    trace
+  }
+
+  fn generations(trace: &ProbeTrace) -> Vec<u64> {
+    trace.rows.iter().map(|&(generation, _)| generation).collect()
+  }
+
+  #[test]
+  fn append_contiguous() {
+    let trace = trace(&[(5, &[9, 13]), (7, &[9])]);
+    assert_eq!(generations(&trace), [5, 6, 7]);
+    assert_eq!(trace.end(), 8);
+    assert_eq!(trace.transitions(0).collect::<Vec<_>>(), [(5, Some(9)), (6, Some(13)), (7, Some(9))]);
+  }
+
+  #[test]
+  fn append_overlapping_replaces() {
+    let trace = trace(&[(5, &[9, 13, 9]), (6, &[0])]);
+    assert_eq!(trace.rows, [(5, vec![9]), (6, vec![0])]);
+    let trace = self::trace(&[(5, &[9, 13]), (2, &[1])]);
+    assert_eq!(trace.rows, [(2, vec![1])]);
+  }
+
+  #[test]
+  fn append_keeps_rows_across_gaps() {
+    let trace = trace(&[(5, &[9, 9]), (10, &[9, 13])]);
+    assert_eq!(generations(&trace), [5, 6, 10, 11]);
+    assert_eq!(
+      trace.transitions(0).collect::<Vec<_>>(),
+      [(5, Some(9)), (7, None), (10, Some(9)), (11, Some(13))]
+    );
+  }
+
+  #[test]
+  fn append_drops_the_oldest_past_capacity() {
+    let mut trace = trace(&[(0, &[9, 13, 9])]);
+    trace.capacity = 2;
+    trace.append(3, vec![vec![13]]);
+    assert_eq!(generations(&trace), [2, 3]);
+  }
+
+  #[test]
+  fn writes_csv() {
+    let trace = trace(&[(5, &[9, 13]), (9, &[13])]);
+    let mut csv = vec![];
+    trace.write_csv(&mut csv).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "generation,p0_3_4\n5,9\n6,13\n9,13\n");
+  }
+
+  #[test]
+  fn writes_vcd() {
+    let trace = trace(&[(5, &[9, 9, 13]), (9, &[13])]);
+    let mut vcd = vec![];
+    trace.write_vcd(&mut vcd, Rule::Hutton32b).unwrap();
+    let vcd = String::from_utf8(vcd).unwrap();
+    let (header, changes) = vcd.split_once("$enddefinitions $end\n").unwrap();
+    assert!(header.contains("$var wire 1 ! p0_3_4 $end\n$var wire 5 \" p0_3_4_state $end\n"));
+    assert_eq!(changes, "#5\n0!\nb01001 \"\n#7\n1!\nb01101 \"\n#8\nx!\nbx \"\n#9\n1!\nb01101 \"\n#10\n");
+  }
+}
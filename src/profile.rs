@@ -0,0 +1,113 @@
+use {
+  std::{fs::OpenOptions, io::{self, Write}},
+  crate::rule::Rule,
+};
+
+/// Bytes moved per cell and generation for the bandwidth estimate: the
+/// record read and written once, neighbours and LUT entries assumed to be
+/// served from cache. A lower bound on the real traffic.
+pub const BYTES_PER_CELL: f64 = 8.0;
+
+/// Timing of one command buffer.
+#[derive(Clone, Copy, Debug)]
+pub struct ProfileSample {
+  /// GPU timestamps, otherwise CPU wall time of the whole submission.
+  pub timestamps: bool,
+  pub generations: u64,
+  /// Cells per generation.
+  pub cells: u64,
+  pub simulation_ms: Option<f64>,
+  pub render_ms: Option<f64>,
+}
+
+/// Samples summed since the last reset.
+#[derive(Clone, Default, Debug)]
+pub struct ProfileTotals {
+  pub timestamps: bool,
+  pub generations: u64,
+  pub cell_generations: f64,
+  pub simulation_ms: f64,
+  pub renders: u64,
+  pub render_ms: f64,
+}
+
+const COLUMNS: [&str; 11] = [
+  "device", "rule", "width", "height", "source", "generations",
+  "ms_per_generation", "cells_per_ns", "est_gb_per_s", "renders", "ms_per_render",
+];
+
+impl ProfileTotals {
+  pub fn add(&mut self, sample: &ProfileSample) {
+    self.timestamps = sample.timestamps;
+    if let Some(ms) = sample.simulation_ms {
+      self.generations += sample.generations;
+      self.cell_generations += (sample.cells * sample.generations) as f64;
+      self.simulation_ms += ms;
+    }
+    if let Some(ms) = sample.render_ms {
+      self.renders += 1;
+      self.render_ms += ms;
+    }
+  }
+
+  pub fn ms_per_generation(&self) -> Option<f64> {
+    (self.generations > 0).then(|| self.simulation_ms / self.generations as f64)
+  }
+
+  pub fn cells_per_ns(&self) -> Option<f64> {
+    (self.simulation_ms > 0.0).then(|| self.cell_generations / (self.simulation_ms * 1e6))
+  }
+
+  /// Estimated from `BYTES_PER_CELL`; bytes per nanosecond are GB/s.
+  pub fn bandwidth_gb_per_s(&self) -> Option<f64> {
+    self.cells_per_ns().map(|cells_per_ns| cells_per_ns * BYTES_PER_CELL)
+  }
+
+  pub fn ms_per_render(&self) -> Option<f64> {
+    (self.renders > 0).then(|| self.render_ms / self.renders as f64)
+  }
+
+  pub fn source(&self) -> &'static str {
+    if self.timestamps { "gpu timestamps" } else { "cpu wall time" }
+  }
+
+  fn row(&self, device: &str, simulation_dimm: [u32; 2]) -> [String; 11] {
+    let figure = |value: Option<f64>, decimals: usize| value.map_or(String::new(), |v| format!("{v:.decimals$}"));
+    [
+      device.to_string(),
+      Rule::ACTIVE.name().to_string(),
+      simulation_dimm[0].to_string(),
+      simulation_dimm[1].to_string(),
+      self.source().to_string(),
+      self.generations.to_string(),
+      figure(self.ms_per_generation(), 4),
+      figure(self.cells_per_ns(), 3),
+      figure(self.bandwidth_gb_per_s(), 2),
+      self.renders.to_string(),
+      figure(self.ms_per_render(), 4),
+    ]
+  }
+
+  /// A header and a row, as a Markdown table for benchmark notes.
+  pub fn to_markdown(&self, device: &str, simulation_dimm: [u32; 2]) -> String {
+    format!(
+      "| {} |\n|{}\n| {} |\n",
+      COLUMNS.join(" | "),
+      " --- |".repeat(COLUMNS.len()),
+      self.row(device, simulation_dimm).join(" | ")
+    )
+  }
+
+  /// Appends a row to the CSV at `path`, with the header if the file is new.
+  pub fn append_csv(&self, path: &str, device: &str, simulation_dimm: [u32; 2]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+      writeln!(file, "{}", COLUMNS.join(","))?;
+    }
+    let row = self.row(device, simulation_dimm).map(|field| match field.contains([',', '"']) {
+      true => format!("\"{}\"", field.replace('"', "\"\"")),
+      false => field,
+    });
+    writeln!(file, "{}", row.join(","))
+  }
+}
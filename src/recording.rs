@@ -0,0 +1,167 @@
+use {
+  std::{fs::File, io::{BufReader, BufWriter, Read, Write}},
+  image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, RgbaImage,
+  },
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecordingFormat {
+  Gif,
+  Apng,
+  /// One PNG per frame, numbered after the path.
+  PngSequence,
+}
+
+impl RecordingFormat {
+  pub const ALL: [RecordingFormat; 3] = [RecordingFormat::Gif, RecordingFormat::Apng, RecordingFormat::PngSequence];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      RecordingFormat::Gif => "GIF",
+      RecordingFormat::Apng => "APNG",
+      RecordingFormat::PngSequence => "PNG sequence",
+    }
+  }
+
+  /// `.gif` and `.png`/`.apng` are animations, anything else a PNG sequence.
+  pub fn from_path(path: &str) -> Self {
+    match path.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase()).as_deref() {
+      Some("gif") => RecordingFormat::Gif,
+      Some("png" | "apng") => RecordingFormat::Apng,
+      _ => RecordingFormat::PngSequence,
+    }
+  }
+}
+
+/// Where frames go as they are pushed, opened with the first one.
+enum Sink {
+  Gif(GifEncoder<BufWriter<File>>),
+  /// Raw RGBA frames, since an APNG header needs the frame count up front.
+  Apng {
+    spool: BufWriter<File>,
+    width: u32,
+    height: u32,
+  },
+}
+
+/// Frames rendered every few generations. Nothing is held in memory: GIF
+/// frames are encoded as they come, APNG frames are spooled to disk until
+/// the recording is finished, PNG sequences are written frame by frame.
+pub struct Recording {
+  pub format: RecordingFormat,
+  pub path: String,
+  pub fps: u32,
+  sink: Option<Sink>,
+  frame_count: usize,
+}
+
+impl Recording {
+  pub fn new(format: RecordingFormat, path: String, fps: u32) -> Self {
+    Self { format, path, fps: fps.max(1), sink: None, frame_count: 0 }
+  }
+
+  pub fn frame_count(&self) -> usize {
+    self.frame_count
+  }
+
+  /// `PATH_00042.png`, the extension of `PATH` dropped.
+  fn sequence_path(&self, frame: usize) -> String {
+    let stem = self.path.strip_suffix(".png").unwrap_or(&self.path);
+    format!("{stem}_{frame:05}.png")
+  }
+
+  /// `PATH.frames`, the APNG spool.
+  fn spool_path(&self) -> String {
+    format!("{}.frames", self.path)
+  }
+
+  fn open_sink(&self, frame: &RgbaImage) -> Result<Sink, String> {
+    Ok(match self.format {
+      RecordingFormat::Gif => {
+        let file = File::create(&self.path).map_err(|e| format!("writing {}: {e}", self.path))?;
+        let mut encoder = GifEncoder::new(BufWriter::new(file));
+        encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("writing {}: {e}", self.path))?;
+        Sink::Gif(encoder)
+      },
+      RecordingFormat::Apng => {
+        let path = self.spool_path();
+        let file = File::create(&path).map_err(|e| format!("writing {path}: {e}"))?;
+        Sink::Apng { spool: BufWriter::new(file), width: frame.width(), height: frame.height() }
+      },
+      RecordingFormat::PngSequence => unreachable!("PNG sequences are written frame by frame"),
+    })
+  }
+
+  pub fn push(&mut self, frame: RgbaImage) -> Result<(), String> {
+    if self.format == RecordingFormat::PngSequence {
+      let path = self.sequence_path(self.frame_count);
+      frame.save(&path).map_err(|e| format!("writing {path}: {e}"))?;
+      self.frame_count += 1;
+      return Ok(());
+    }
+
+    let sink = match self.sink.take() {
+      Some(sink) => sink,
+      None => self.open_sink(&frame)?,
+    };
+    match self.sink.insert(sink) {
+      Sink::Gif(encoder) => {
+        let delay = Delay::from_numer_denom_ms(1000, self.fps);
+        encoder.encode_frame(Frame::from_parts(frame, 0, 0, delay))
+          .map_err(|e| format!("writing {}: {e}", self.path))?;
+      },
+      Sink::Apng { spool, width, height } => {
+        if frame.dimensions() != (*width, *height) {
+          return Err(format!("frame is {}×{}, the recording {width}×{height}", frame.width(), frame.height()));
+        }
+        spool.write_all(frame.as_raw()).map_err(|e| format!("writing {}.frames: {e}", self.path))?;
+      },
+    }
+    self.frame_count += 1;
+    Ok(())
+  }
+
+  /// Complete the animation, returns a description of what was written.
+  pub fn finish(mut self) -> Result<String, String> {
+    let path = self.path.clone();
+    let error = |e: &dyn std::fmt::Display| format!("writing {path}: {e}");
+    if self.format == RecordingFormat::PngSequence {
+      return Ok(format!("{} frames written to {}…", self.frame_count, self.sequence_path(0)));
+    }
+    match self.sink.take() {
+      None => return Err("no frames recorded".to_string()),
+      // the trailer is written when the encoder is dropped
+      Some(Sink::Gif(_)) => (),
+      Some(Sink::Apng { spool, width, height }) => {
+        let spool_path = self.spool_path();
+        spool.into_inner().map_err(|e| format!("writing {spool_path}: {}", e.error()))?;
+        let mut frames = BufReader::new(File::open(&spool_path).map_err(|e| format!("reading {spool_path}: {e}"))?);
+
+        let file = File::create(&path).map_err(|e| error(&e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frame_count as u32, 0).map_err(|e| error(&e))?;
+        encoder.set_frame_delay(1, self.fps.min(u16::MAX as u32) as u16).map_err(|e| error(&e))?;
+        let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+        let mut frame = vec![0u8; width as usize * height as usize * 4];
+        for _ in 0..self.frame_count {
+          frames.read_exact(&mut frame).map_err(|e| format!("reading {spool_path}: {e}"))?;
+          writer.write_image_data(&frame).map_err(|e| error(&e))?;
+        }
+        writer.finish().map_err(|e| error(&e))?;
+      },
+    }
+    Ok(format!("{} frames written to {path}", self.frame_count))
+  }
+}
+
+impl Drop for Recording {
+  fn drop(&mut self) {
+    if self.format == RecordingFormat::Apng {
+      let _ = std::fs::remove_file(self.spool_path());
+    }
+  }
+}
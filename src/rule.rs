@@ -0,0 +1,119 @@
+use crate::pattern::Transform;
+
+#[cfg(test)]
+pub mod hutton32b;
+
+/// Transition rules shipped in `./src/kernel/rules`.
+#[allow(dead_code)] // only `Rule::ACTIVE` is compiled in
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rule {
+  Hutton32,
+  Hutton32b,
+  GameOfLife,
+}
+
+impl Rule {
+  /// The rule `compute_lut` is built from, see `./src/kernel/compute.wgsl`.
+  pub const ACTIVE: Rule = Rule::Hutton32b;
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Rule::Hutton32 => "Hutton32",
+      Rule::Hutton32b => "Hutton32b",
+      Rule::GameOfLife => "Life",
+    }
+  }
+
+  /// Source `compute_lut` builds the table from, compiled in so that the
+  /// table can be fingerprinted wherever the binary runs.
+  pub fn source(&self) -> &'static [u8] {
+    match self {
+      Rule::Hutton32 => include_bytes!("kernel/rules/hutton32.rule.wgsl"),
+      Rule::Hutton32b => include_bytes!("kernel/rules/hutton32b.rule.wgsl"),
+      Rule::GameOfLife => include_bytes!("kernel/rules/game_of_life.rule.wgsl"),
+    }
+  }
+
+  /// Whether a cell in `state` carries a signal: excited transmission states,
+  /// and confluent states whose output is excited (C10, C11). For Life, a
+  /// live cell.
+  pub fn is_excited(&self, state: u8) -> bool {
+    match self {
+      Rule::GameOfLife => state == 1,
+      Rule::Hutton32 | Rule::Hutton32b => matches!(state, 13..=16 | 21..=24 | 27 | 28),
+    }
+  }
+
+  /// State permutation to be applied alongside a geometric transform.
+  ///
+  /// For the JvN family, transmission states 9..=24 are grouped by four in
+  /// east-north-west-south order (see `dir` in the rule files), so rotating a
+  /// pattern must also rotate the arrows. Crossing states 29/30 are horizontal
+  /// and vertical, and swap under a quarter turn. Sensitized states 1..=8 carry
+  /// no direction (the arrow they revert to is chosen by the cell writing them),
+  /// same for confluent states 25..=28, so both are left in place.
+  ///
+  /// Note that Hutton32 construction signals encode "turn left"/"turn right",
+  /// hence a mirrored machine will generally not work as before.
+  pub fn state_permutation(&self, transform: Transform) -> [u8; 32] {
+    let mut table: [u8; 32] = std::array::from_fn(|i| i as u8);
+    if *self == Rule::GameOfLife {
+      return table;
+    }
+
+    // direction index: 0 = east, 1 = north, 2 = west, 3 = south
+    let map_dir = |dir: u8| match transform {
+      Transform::RotateCW => (dir + 3) % 4,
+      Transform::RotateCCW => (dir + 1) % 4,
+      Transform::FlipH => [2, 1, 0, 3][dir as usize],
+      Transform::FlipV => [0, 3, 2, 1][dir as usize],
+    };
+    for state in 9..=24u8 {
+      let group = (state - 9) / 4 * 4 + 9;
+      table[state as usize] = group + map_dir((state - 9) % 4);
+    }
+    if matches!(transform, Transform::RotateCW | Transform::RotateCCW) {
+      table.swap(29, 30);
+    }
+    table
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn state_permutations_turn_arrows() {
+    let rule = Rule::Hutton32b;
+    // each group of four keeps its kind and excitation
+    assert_eq!(rule.state_permutation(Transform::RotateCCW)[9], 10, "OTS east to north");
+    assert_eq!(rule.state_permutation(Transform::RotateCCW)[24], 21, "excited STS south to east");
+    assert_eq!(rule.state_permutation(Transform::RotateCW)[13], 16, "excited OTS east to south");
+    assert_eq!(rule.state_permutation(Transform::FlipH)[17], 19, "STS east to west");
+    assert_eq!(rule.state_permutation(Transform::FlipH)[18], 18, "STS north stays");
+    assert_eq!(rule.state_permutation(Transform::FlipV)[22], 24, "excited STS north to south");
+    assert_eq!(rule.state_permutation(Transform::RotateCW)[29], 30, "crossings swap");
+    assert_eq!(rule.state_permutation(Transform::FlipH)[29], 29);
+  }
+
+  #[test]
+  fn state_permutations_are_undone() {
+    use Transform::*;
+    for rule in [Rule::Hutton32, Rule::Hutton32b, Rule::GameOfLife] {
+      let apply = |transforms: &[Transform]| transforms.iter().fold(
+        std::array::from_fn::<u8, 32, _>(|i| i as u8),
+        |table, &transform| table.map(|state| rule.state_permutation(transform)[state as usize])
+      );
+      let identity = apply(&[]);
+      assert_eq!(apply(&[RotateCW; 4]), identity);
+      assert_eq!(apply(&[RotateCW, RotateCCW]), identity);
+      assert_eq!(apply(&[FlipH; 2]), identity);
+      assert_eq!(apply(&[FlipV; 2]), identity);
+      // sensitized and confluent states carry no direction
+      for state in (0..=8).chain(25..=28) {
+        assert_eq!(rule.state_permutation(RotateCW)[state], state as u8);
+      }
+    }
+  }
+}
@@ -0,0 +1,222 @@
+//! CPU port of `./src/kernel/rules/hutton32b.rule.wgsl`, line for line, to
+//! check construction against the rule in tests. Arithmetic wraps as `u32`
+//! does in WGSL, and results are truncated to the byte `compute_lut` stores.
+
+use crate::pattern::Pattern;
+
+fn is_ots(c: u32) -> bool {
+  (9..=16).contains(&c)
+}
+fn is_sts(c: u32) -> bool {
+  (17..=24).contains(&c)
+}
+fn is_ts(c: u32) -> bool {
+  is_ots(c) || is_sts(c)
+}
+fn is_sensitized(c: u32) -> bool {
+  (1..=8).contains(&c)
+}
+fn is_east(c: u32) -> bool {
+  matches!(c, 9 | 13 | 17 | 21)
+}
+fn is_north(c: u32) -> bool {
+  matches!(c, 10 | 14 | 18 | 22)
+}
+fn is_west(c: u32) -> bool {
+  matches!(c, 11 | 15 | 19 | 23)
+}
+fn is_south(c: u32) -> bool {
+  matches!(c, 12 | 16 | 20 | 24)
+}
+fn is_excited(c: u32) -> bool {
+  matches!(c, 13..=16 | 21..=24)
+}
+
+fn dir(c: u32) -> u32 {
+  c.wrapping_sub(9) % 4
+}
+fn output(c: u32, n: u32, s: u32, e: u32, w: u32) -> u32 {
+  if is_east(c) { e }
+  else if is_north(c) { n }
+  else if is_west(c) { w }
+  else if is_south(c) { s }
+  else { 0 }
+}
+fn input(n: u32, s: u32, e: u32, w: u32) -> u32 {
+  if is_east(w) && is_excited(w) { w }
+  else if is_north(s) && is_excited(s) { s }
+  else if is_west(e) && is_excited(e) { e }
+  else if is_south(n) && is_excited(n) { n }
+  else { 0 }
+}
+
+fn output_will_become_ots(c: u32, n: u32, s: u32, e: u32, w: u32) -> bool {
+  let out = output(c, n, s, e, w);
+  out == 8 || (out == 4 && is_excited(c)) || (out == 5 && !is_excited(c))
+}
+fn output_will_become_confluent(c: u32, n: u32, s: u32, e: u32, w: u32) -> bool {
+  output(c, n, s, e, w) == 7 && is_excited(c)
+}
+fn output_will_become_sensitized(c: u32, n: u32, s: u32, e: u32, w: u32) -> bool {
+  let out = output(c, n, s, e, w);
+  (out == 0 && is_excited(c)) || out == 1 || out == 2 || out == 3 || (out == 4 && !is_ots(c))
+}
+fn excited_ots_to_us(c: u32, n: u32, s: u32, e: u32, w: u32) -> bool {
+  (matches!(n, 16 | 27 | 28 | 30 | 31) && !(c == 14 || c == 10))
+    || (matches!(s, 14 | 27 | 28 | 30 | 31) && !(c == 16 || c == 12))
+    || (matches!(e, 15 | 27 | 28 | 29 | 31) && !(c == 13 || c == 9))
+    || (matches!(w, 13 | 27 | 28 | 29 | 31) && !(c == 15 || c == 11))
+}
+fn excited_ots_arrow_to_us(c: u32, n: u32, s: u32, e: u32, w: u32) -> bool {
+  (n == 16 && !(c == 14 || c == 10))
+    || (s == 14 && !(c == 16 || c == 12))
+    || (e == 15 && !(c == 13 || c == 9))
+    || (w == 13 && !(c == 15 || c == 11))
+}
+fn ots_arrow_to_us(n: u32, s: u32, e: u32, w: u32) -> bool {
+  (is_ots(n) && is_south(n)) || (is_ots(s) && is_north(s))
+    || (is_ots(e) && is_west(e)) || (is_ots(w) && is_east(w))
+}
+fn excited_sts_to_us(c: u32, n: u32, s: u32, e: u32, w: u32) -> bool {
+  (matches!(n, 24 | 27 | 28 | 30 | 31) && !(c == 22 || c == 18))
+    || (matches!(s, 22 | 27 | 28 | 30 | 31) && !(c == 24 || c == 20))
+    || (matches!(e, 23 | 27 | 28 | 29 | 31) && !(c == 21 || c == 17))
+    || (matches!(w, 21 | 27 | 28 | 29 | 31) && !(c == 23 || c == 19))
+}
+fn excited_sts_arrow_to_us(c: u32, n: u32, s: u32, e: u32, w: u32) -> bool {
+  (n == 24 && !(c == 22 || c == 18))
+    || (s == 22 && !(c == 24 || c == 20))
+    || (e == 23 && !(c == 21 || c == 17))
+    || (w == 21 && !(c == 23 || c == 19))
+}
+fn all_inputs_on(n: u32, s: u32, e: u32, w: u32) -> bool {
+  !(n == 12 || s == 10 || e == 11 || w == 9) && (n == 16 || s == 14 || e == 15 || w == 13)
+}
+fn is_crossing(n: u32, s: u32, e: u32, w: u32) -> bool {
+  let n_inputs = [is_south(n), is_east(w), is_west(e), is_north(s)].into_iter().filter(|&b| b).count();
+  let n_outputs = [
+    is_ts(n) && !is_south(n),
+    is_ts(w) && !is_east(w),
+    is_ts(e) && !is_west(e),
+    is_ts(s) && !is_north(s),
+  ].into_iter().filter(|&b| b).count();
+  n_inputs == 2 && n_outputs == 2
+}
+fn xnor_crossing(n: u32, s: u32, e: u32, w: u32) -> bool {
+  (is_south(n) && is_north(s) && (is_west(w) || is_north(w) || is_south(w)) && (is_east(e) || is_north(e) || is_south(e)))
+    || (is_east(w) && is_west(e) && (is_north(n) || is_west(n) || is_east(n)) && (is_south(s) || is_west(s) || is_east(s)))
+}
+fn xnor_output(n: u32, s: u32, e: u32, w: u32) -> bool {
+  (e == 11 && w == 9) || (e == 15 && w == 13) || (n == 12 && s == 10) || (n == 16 && s == 14)
+}
+fn quiesce(c: u32) -> u32 {
+  match c {
+    13..=16 | 21..=24 => c - 4,
+    26..=31 => 25,
+    _ => c,
+  }
+}
+
+fn next(c: u32, n: u32, s: u32, e: u32, w: u32) -> u32 {
+  let crossing = || {
+    if excited_sts_arrow_to_us(c, n, s, e, w) { 0 }
+    else if (n == 16 || s == 14) && (e == 15 || w == 13) { 31 }
+    else if n == 16 || s == 14 { 30 }
+    else if e == 15 || w == 13 { 29 }
+    else { 25 }
+  };
+  let turned = |quarters: u32, group: u32| quiesce(input(n, s, e, w)).wrapping_sub(9).wrapping_add(quarters) % 4 + group;
+  let excited = excited_ots_arrow_to_us(c, n, s, e, w);
+  let inputs_on = all_inputs_on(n, s, e, w) || (xnor_crossing(n, s, e, w) && xnor_output(n, s, e, w));
+  match c {
+    9..=16 => {
+      if excited_sts_arrow_to_us(c, n, s, e, w) {
+        0
+      } else if excited_ots_to_us(c, n, s, e, w) {
+        let out = output(c, n, s, e, w);
+        if output_will_become_ots(c, n, s, e, w) || (is_sts(out) && !is_excited(out)) { 0 }
+        else if output_will_become_confluent(c, n, s, e, w) { 1 }
+        else { quiesce(c) + 4 }
+      } else if output_will_become_confluent(c, n, s, e, w) {
+        0
+      } else if is_excited(c) && output_will_become_sensitized(c, n, s, e, w) {
+        quiesce(c) + 12
+      } else {
+        quiesce(c)
+      }
+    },
+    17..=24 => {
+      let out = output(c, n, s, e, w);
+      if is_excited(c) && is_sensitized(out) && ots_arrow_to_us(n, s, e, w) {
+        match (output_will_become_sensitized(c, n, s, e, w), excited) {
+          (true, true) => c - 8,
+          (true, false) => c,
+          (false, true) => 0,
+          (false, false) => quiesce(c) - 8,
+        }
+      } else if is_excited(c) && out == 0 {
+        if excited_sts_to_us(c, n, s, e, w) { c } else { quiesce(c) }
+      } else if excited {
+        0
+      } else if excited_sts_to_us(c, n, s, e, w) {
+        quiesce(c) + 4
+      } else {
+        quiesce(c)
+      }
+    },
+    0 => {
+      if excited { 1 }
+      else if excited_sts_arrow_to_us(c, n, s, e, w) { quiesce(input(n, s, e, w)).wrapping_sub(8) }
+      else { c }
+    },
+    1 => if excited { 3 } else { 2 },
+    2 => if excited { 5 } else { 4 },
+    3 => if excited { 7 } else { 6 },
+    4 => if excited { turned(2, 9) } else { 8 },
+    5 => if excited { quiesce(input(n, s, e, w)) + 8 } else { turned(3, 9) },
+    6 => if excited { turned(2, 17) } else { turned(1, 17) },
+    7 => if excited { 25 } else { turned(3, 17) },
+    8 => if excited { 9 + dir(input(n, s, e, w) + 1) } else { 9 + dir(input(n, s, e, w)) },
+    25 => {
+      if excited_sts_arrow_to_us(c, n, s, e, w) { 0 }
+      else if xnor_crossing(n, s, e, w) { if xnor_output(n, s, e, w) { 26 } else { 25 } }
+      else if is_crossing(n, s, e, w) { crossing() }
+      else if all_inputs_on(n, s, e, w) { 26 }
+      else { 25 }
+    },
+    26 | 28 => {
+      if excited_sts_arrow_to_us(c, n, s, e, w) { 0 }
+      else if inputs_on { 28 }
+      else { 27 }
+    },
+    27 => {
+      if excited_sts_arrow_to_us(c, n, s, e, w) { 0 }
+      else if inputs_on { 26 }
+      else { 25 }
+    },
+    29..=31 => if is_crossing(n, s, e, w) { crossing() } else { 25 },
+    _ => c,
+  }
+}
+
+/// Entry of the transition table for a cell and its neighbours.
+pub fn transition(c: u8, n: u8, s: u8, e: u8, w: u8) -> u8 {
+  next(c as u32, n as u32, s as u32, e as u32, w as u32) as u8
+}
+
+/// One generation of `pattern`, surrounded by ground.
+pub fn step(pattern: &Pattern) -> Pattern {
+  let at = |x: i64, y: i64| match x >= 0 && y >= 0 && x < pattern.width as i64 && y < pattern.height as i64 {
+    true => pattern.get(x as u32, y as u32),
+    false => 0,
+  };
+  let mut out = Pattern::new(pattern.width, pattern.height);
+  for y in 0..pattern.height {
+    for x in 0..pattern.width {
+      let (x, y) = (x as i64, y as i64);
+      let state = transition(at(x, y), at(x, y - 1), at(x, y + 1), at(x + 1, y), at(x - 1, y));
+      out.set(x as u32, y as u32, state);
+    }
+  }
+  out
+}
@@ -0,0 +1,201 @@
+use {
+  std::io::{self, Read, Write},
+  crate::rule::Rule,
+};
+
+const MAGIC: &[u8; 8] = b"H32SNAP\0";
+const VERSION: u32 = 1;
+
+/// How cells beyond the edges of the universe are treated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Boundary {
+  /// The only mode `compute_main` implements: cells beyond the edges read
+  /// as ground, see `get_cell`, and are never written.
+  Bounded = 0,
+}
+
+/// Native snapshot of a simulation. Records are stored raw, parity included,
+/// so a run resumes exactly where it was paused.
+pub struct Snapshot {
+  pub rule: String,
+  pub lut_hash: u64,
+  pub boundary: Boundary,
+  pub simulation_dimm: [u32; 2],
+  pub generation: u64,
+  /// `[min_x, min_y, max_x, max_y]` of the plot.
+  pub view_bounds: [f64; 4],
+  pub records: Vec<u32>,
+}
+
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+impl Snapshot {
+  pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+    w.write_all(MAGIC)?;
+    w.write_all(&VERSION.to_le_bytes())?;
+    w.write_all(&[self.rule.len() as u8])?;
+    w.write_all(self.rule.as_bytes())?;
+    w.write_all(&self.lut_hash.to_le_bytes())?;
+    w.write_all(&[self.boundary as u8])?;
+    w.write_all(&self.simulation_dimm[0].to_le_bytes())?;
+    w.write_all(&self.simulation_dimm[1].to_le_bytes())?;
+    w.write_all(&self.generation.to_le_bytes())?;
+    for v in self.view_bounds {
+      w.write_all(&v.to_le_bytes())?;
+    }
+    // cell records go last, in one piece
+    if cfg!(target_endian = "little") {
+      w.write_all(bytemuck::cast_slice(&self.records))?;
+    } else {
+      for record in &self.records {
+        w.write_all(&record.to_le_bytes())?;
+      }
+    }
+    Ok(())
+  }
+
+  /// `check_dimm` vets the universe size before the records are allocated.
+  pub fn read_from(mut r: impl Read, check_dimm: impl FnOnce([u32; 2]) -> Result<(), String>) -> io::Result<Self> {
+    fn read_array<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+      let mut buf = [0u8; N];
+      r.read_exact(&mut buf)?;
+      Ok(buf)
+    }
+
+    if &read_array::<8>(&mut r)? != MAGIC {
+      return Err(invalid_data("not a snapshot file"));
+    }
+    let version = u32::from_le_bytes(read_array(&mut r)?);
+    if version != VERSION {
+      return Err(invalid_data(format!("unsupported snapshot version {version}")));
+    }
+    let [rule_len] = read_array::<1>(&mut r)?;
+    let mut rule = vec![0u8; rule_len as usize];
+    r.read_exact(&mut rule)?;
+    let rule = String::from_utf8(rule).map_err(|_| invalid_data("invalid rule name"))?;
+    let lut_hash = u64::from_le_bytes(read_array(&mut r)?);
+    let boundary = match read_array::<1>(&mut r)? {
+      [0] => Boundary::Bounded,
+      [b] => return Err(invalid_data(format!("unknown boundary mode {b}"))),
+    };
+    let simulation_dimm = [
+      u32::from_le_bytes(read_array(&mut r)?),
+      u32::from_le_bytes(read_array(&mut r)?),
+    ];
+    let generation = u64::from_le_bytes(read_array(&mut r)?);
+    let mut view_bounds = [0.0; 4];
+    for v in view_bounds.iter_mut() {
+      *v = f64::from_le_bytes(read_array(&mut r)?);
+    }
+
+    check_dimm(simulation_dimm).map_err(invalid_data)?;
+    let cells = simulation_dimm[0] as usize * simulation_dimm[1] as usize;
+    let mut bytes = vec![0u8; cells * 4];
+    r.read_exact(&mut bytes).map_err(|e| match e.kind() {
+      io::ErrorKind::UnexpectedEof => invalid_data(format!("expected {cells} cell records, the file ends early")),
+      _ => e,
+    })?;
+    if r.read(&mut [0u8])? != 0 {
+      return Err(invalid_data(format!("trailing data after {cells} cell records")));
+    }
+    let records = bytes.chunks_exact(4)
+      .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+      .collect();
+
+    Ok(Self { rule, lut_hash, boundary, simulation_dimm, generation, view_bounds, records })
+  }
+
+  /// Refuse snapshots taken with a different transition table.
+  pub fn check_compatible(&self, rule: Rule, lut_hash: u64) -> Result<(), String> {
+    if self.rule != rule.name() {
+      Err(format!("snapshot rule is {}, running {}", self.rule, rule.name()))
+    } else if self.lut_hash != lut_hash {
+      Err(format!("LUT hash mismatch: {:016x} != {:016x}", self.lut_hash, lut_hash))
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// FNV-1a, stable across builds unlike `DefaultHasher`.
+pub fn fnv1a_64(data: &[u8]) -> u64 {
+  data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn snapshot() -> Snapshot {
+    Snapshot {
+      rule: Rule::Hutton32b.name().to_string(),
+      lut_hash: fnv1a_64(Rule::Hutton32b.source()),
+      boundary: Boundary::Bounded,
+      simulation_dimm: [3, 2],
+      generation: 12345,
+      view_bounds: [-1.5, 2.0, 30.25, 40.0],
+      records: vec![0, 9, 1 << 16 | 13 << 8 | 9, 25, 0xffff_ffff, 7],
+    }
+  }
+
+  fn bytes(snapshot: &Snapshot) -> Vec<u8> {
+    let mut bytes = vec![];
+    snapshot.write_to(&mut bytes).unwrap();
+    bytes
+  }
+
+  fn read(bytes: &[u8]) -> io::Result<Snapshot> {
+    Snapshot::read_from(bytes, |_| Ok(()))
+  }
+
+  #[test]
+  fn round_trips() {
+    let snapshot = snapshot();
+    let read = read(&bytes(&snapshot)).unwrap();
+    assert_eq!(read.rule, snapshot.rule);
+    assert_eq!(read.lut_hash, snapshot.lut_hash);
+    assert_eq!(read.boundary, snapshot.boundary);
+    assert_eq!(read.simulation_dimm, snapshot.simulation_dimm);
+    assert_eq!(read.generation, snapshot.generation);
+    assert_eq!(read.view_bounds, snapshot.view_bounds);
+    assert_eq!(read.records, snapshot.records);
+    assert_eq!(read.check_compatible(Rule::Hutton32b, snapshot.lut_hash), Ok(()));
+    assert!(read.check_compatible(Rule::Hutton32b, snapshot.lut_hash ^ 1).is_err());
+    assert!(read.check_compatible(Rule::GameOfLife, snapshot.lut_hash).is_err());
+  }
+
+  #[test]
+  fn rejects_truncated_files() {
+    let bytes = bytes(&snapshot());
+    for len in [0, 4, 20, bytes.len() - 4, bytes.len() - 1] {
+      assert!(read(&bytes[..len]).is_err(), "{len} bytes");
+    }
+  }
+
+  #[test]
+  fn rejects_mismatched_sizes() {
+    let mut bytes = bytes(&snapshot());
+    bytes.extend([0; 4]);
+    assert!(read(&bytes).is_err(), "a record too many");
+
+    // a larger universe than the records stored
+    let mut larger = snapshot();
+    larger.simulation_dimm = [4, 2];
+    assert!(read(&self::bytes(&larger)).is_err());
+
+    let refused = Snapshot::read_from(&self::bytes(&snapshot())[..], |dimm| Err(format!("{dimm:?} is too large")));
+    assert_eq!(refused.err().map(|e| e.to_string()), Some("[3, 2] is too large".to_string()));
+  }
+
+  #[test]
+  fn rejects_other_files() {
+    let mut bytes = bytes(&snapshot());
+    bytes[0] = b'X';
+    assert!(read(&bytes).is_err());
+    let mut bytes = self::bytes(&snapshot());
+    bytes[8] = 2;
+    assert!(read(&bytes).is_err(), "version 2");
+  }
+}